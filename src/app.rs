@@ -4,127 +4,3620 @@ use arrow::{
     util::display::{ArrayFormatter, FormatOptions},
 };
 use comfy_table::{Cell, Table};
-use duckdb::Connection;
+use duckdb::{params_from_iter, Connection};
 
-use ratatui::widgets::ScrollbarState;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use ratatui::{layout::Rect, widgets::ListState};
+
+#[cfg(feature = "scripting")]
+use crate::script::Script;
+use crate::{
+    audit::AuditLog,
+    bench::Bench,
+    cache::{CachedResult, ResultCache},
+    chart::{self, ChartKind, Histogram},
+    command::{Benchmark, Command},
+    complete,
+    config::{CellFormat, Config, ConnectionProfile},
+    db,
+    diff::ResultDiff,
+    dml::{CellEdit, Target},
+    draft::Draft,
+    erd::Diagram,
+    files::{self, FileBrowser},
+    finder::{self, Finder},
+    grid,
+    import::{self, ImportWizard},
+    insert::InsertForm,
+    jobs::{JobEntry, JobHistory, JobState},
+    lakehouse::{self, TableFormat},
+    layout::PaneSizes,
+    lint,
+    pane::{
+        AfterRun, Cancelling, Job, JobDone, Page, Pane, Place, Running, Sort, SplitLayout,
+        Untransposed,
+    },
+    params::{Param, ParamsForm, Submit},
+    perf::PerfStats,
+    pivot::{self, PivotForm},
+    plan::PlanView,
+    plugin::{to_csv, ExternalPlugin, Output, Plugin, PluginPane},
+    profile::ProfileView,
+    recent::{Recent, RecentKind},
+    remote::{self, RemoteForm, RemoteKind},
+    samples::{self, Sample},
+    schema::{self, Node, SchemaBrowser},
+    secrets::SecretsPanel,
+    settings::{self, SettingsPanel},
+    sql::{self, quote_identifier, quote_literal},
+    stats::ColumnStats,
+    stream::{Fetch, QueryStream},
+    transcript::Transcript,
+    tutorial::{Step, Tutorial},
+};
+#[cfg(feature = "scripting")]
+use std::path::Path;
 
 pub enum InputMode {
     Normal,
     Editing,
+    Browsing,
+    Importing,
+    Recent,
+    Files,
+    Command,
+    Plan,
+    Profile,
+    Chart,
+    Stats,
+    Columns,
+    CellEdit,
+    Inserting,
+    Confirm,
+    Ddl,
+    Describe,
+    Finder,
+    Diagram,
+    PluginPane,
+    Connections,
+    Remote,
+    Secrets,
+    Settings,
+    Params,
+    Templates,
+    Jobs,
+    Pivot,
+}
+
+/// How often the editors are saved to the draft while they change.
+const DRAFT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long typing has to pause before the editor is checked again.
+const LINT_DELAY: Duration = Duration::from_millis(300);
+
+/// Characters a column grows or shrinks by per key press.
+const COLUMN_WIDTH_STEP: u16 = 2;
+
+/// Most rows a result can have to be transposed, each becoming a column.
+const MAX_TRANSPOSED_ROWS: usize = 100;
+
+/// A result column being resized with the mouse.
+pub struct ColumnDrag {
+    pane: usize,
+    column: String,
+    /// Where the drag started, and the column's width then.
+    from: u16,
+    width: u16,
+}
+
+/// The `CREATE` statement of a table or view, shown over the schema
+/// browser.
+pub struct Ddl {
+    pub name: String,
+    pub sql: String,
+    pub scroll: u16,
+}
+
+/// Columns of a table or view, as `DESCRIBE` lists them.
+pub struct Describe {
+    pub name: String,
+    /// Name, type, whether NULL is allowed, key and default of each column.
+    pub columns: Vec<[String; 5]>,
+    /// Whether it was opened from the editor, which Esc goes back to.
+    editing: bool,
+}
+
+pub struct Watch {
+    pub interval: Duration,
+    pub next_run: Instant,
+    /// Index of the pane the watched query runs in.
+    pub pane: usize,
 }
 
-pub struct App<'a> {
-    pub input: String,
-    pub cursor_position: usize,
+/// A shell command for the frontend to run with the result on its stdin,
+/// while the TUI is suspended.
+pub struct Pipe {
+    pub command: String,
+    pub input: Vec<u8>,
+}
+
+pub struct App {
+    pub panes: [Pane; 2],
+    /// Index of the pane keys go to.
+    pub active: usize,
+    /// How both panes are laid out, or `None` to show only the active one.
+    pub split: Option<SplitLayout>,
+    pub sizes: PaneSizes,
     pub input_mode: InputMode,
-    pub data: Vec<RecordBatch>,
-    pub vertical_scroll_state: ScrollbarState,
-    pub vertical_scroll: usize,
-    db: &'a Connection,
+    pub schema: SchemaBrowser,
+    pub show_schema: bool,
+    /// Whether the result header shows each column's type under its name.
+    pub show_types: bool,
+    /// Whether the result grid numbers its rows, counting from 1.
+    pub show_row_numbers: bool,
+    pub import: Option<ImportWizard>,
+    pub insert: Option<InsertForm>,
+    /// Candidates from the last Tab completion, shown until the next key.
+    pub completions: Vec<String>,
+    pub recent: Recent,
+    pub recent_state: ListState,
+    pub connections_state: ListState,
+    pub templates_state: ListState,
+    pub jobs_state: ListState,
+    job_history: JobHistory,
+    pub remote: Option<RemoteForm>,
+    pub secrets: Option<SecretsPanel>,
+    pub settings: Option<SettingsPanel>,
+    /// Settings as they were when civciv started, to tell the changed ones.
+    pub initial_settings: BTreeMap<String, String>,
+    /// The prompt for the values of a query's placeholders.
+    pub params: Option<ParamsForm>,
+    pub pivot: Option<PivotForm>,
+    /// Values last typed for each placeholder name.
+    param_values: HashMap<String, String>,
+    /// Set with `:set`, and bound to the placeholders of the same name.
+    pub variables: BTreeMap<String, String>,
+    /// The guided tour, while it is being taken.
+    pub tutorial: Option<Tutorial>,
+    pub column_picker: ListState,
+    pub files: Option<FileBrowser>,
+    /// One-line message shown in the status bar, e.g. errors from actions.
+    pub status: Option<String>,
+    /// Copied text for the frontend to put on the system clipboard.
+    pub clipboard: Option<String>,
+    pub pipe: Option<Pipe>,
+    /// Whether submitted queries print their tables to the terminal's
+    /// scrollback, for a frontend that keeps it.
+    pub inline: bool,
+    /// Tables waiting for the frontend to print above the UI.
+    pub printed: Vec<String>,
+    /// Messages about long queries that finished, for the frontend to
+    /// pass on.
+    pub notifications: Vec<String>,
+    /// Whether the terminal has focus, as the frontend last heard.
+    pub focused: bool,
+    pub transcript: Option<Transcript>,
+    pub audit: Option<AuditLog>,
+    /// The editors as last saved to the draft file, and when.
+    draft: Draft,
+    draft_saved: Instant,
+    /// When the active editor was first seen changed since its last lint.
+    input_changed: Option<Instant>,
+    pub config: Config,
+    pub command_input: String,
+    pub cell_edit: Option<CellEdit>,
+    pub column_drag: Option<ColumnDrag>,
+    /// A generated statement waiting to be confirmed before it runs.
+    pub pending: Option<String>,
+    /// Submitted statements, oldest first.
+    pub history: Vec<String>,
+    pub watch: Option<Watch>,
+    pub plan: Option<PlanView>,
+    pub profile: Option<ProfileView>,
+    pub chart: Option<ChartKind>,
+    pub histogram: Option<Histogram>,
+    pub histogram_bins: usize,
+    pub stats: Option<ColumnStats>,
+    pub ddl: Option<Ddl>,
+    pub describe: Option<Describe>,
+    pub finder: Option<Finder>,
+    pub diagram: Option<Diagram>,
+    plugins: Vec<Box<dyn Plugin>>,
+    /// Where the schema browser was last drawn, empty while it is hidden.
+    pub schema_area: Rect,
+    /// Timings for the performance overlay, while it is shown.
+    pub perf: Option<PerfStats>,
+    /// Register a keyboard macro is being recorded into, for the status bar.
+    pub recording: Option<char>,
+    /// Output of the last plugin command, while it is shown.
+    pub plugin_pane: Option<PluginPane>,
+    /// Whether the finder was opened from the editor, which Esc goes back to.
+    finder_editing: bool,
+    pub cache: ResultCache,
+    /// Result to compare later runs against.
+    pub pinned: Option<Vec<RecordBatch>>,
+    /// Shown instead of the result while set, kept up to date after each run.
+    pub diff: Option<ResultDiff>,
+    /// One DuckDB session per pane, so each runs its queries on its own.
+    /// Attached databases are shared, but `USE`, `SET` and temporary tables
+    /// only affect the pane they are run in.
+    sessions: [Arc<Mutex<Connection>>; 2],
 }
 
-impl<'a> App<'a> {
-    pub fn new(db: &'a Connection) -> Self {
-        Self {
-            input: String::new(),
+impl App {
+    pub fn new(sessions: [Arc<Mutex<Connection>>; 2], mut config: Config) -> Self {
+        let mut plugins: Vec<Box<dyn Plugin>> = std::mem::take(&mut config.plugins)
+            .into_iter()
+            .map(|plugin| Box::new(ExternalPlugin::new(plugin)) as Box<dyn Plugin>)
+            .collect();
+        let macros = sessions
+            .iter()
+            .try_for_each(|db| db::create_macros(&db::lock(db), &config.macros))
+            .err();
+        let (scripts, failed) = load_scripts(&config, &sessions[0]);
+        plugins.extend(scripts);
+        let histogram_bins = config.histogram_bins.max(1);
+        let cache = ResultCache::new(config.result_cache_bytes);
+        let schema = SchemaBrowser::load(&db::lock(&sessions[0])).unwrap_or_default();
+        let initial_settings = settings::values(&db::lock(&sessions[0])).unwrap_or_default();
+        let mut app = Self {
+            panes: Default::default(),
+            active: 0,
+            split: None,
+            sizes: PaneSizes::load(),
             input_mode: InputMode::Normal,
-            cursor_position: 0,
-            data: vec![],
-            vertical_scroll_state: ScrollbarState::default(),
-            vertical_scroll: 0,
-            db,
+            schema,
+            show_schema: false,
+            show_types: config.show_column_types,
+            show_row_numbers: config.show_row_numbers,
+            import: None,
+            insert: None,
+            completions: vec![],
+            recent: Recent::load(),
+            recent_state: ListState::default(),
+            connections_state: ListState::default(),
+            templates_state: ListState::default(),
+            jobs_state: ListState::default(),
+            job_history: JobHistory::default(),
+            remote: None,
+            secrets: None,
+            settings: None,
+            initial_settings,
+            params: None,
+            pivot: None,
+            param_values: HashMap::new(),
+            variables: BTreeMap::new(),
+            tutorial: None,
+            column_picker: ListState::default(),
+            files: None,
+            status: macros.or(failed),
+            clipboard: None,
+            pipe: None,
+            inline: false,
+            printed: vec![],
+            notifications: vec![],
+            focused: true,
+            transcript: None,
+            audit: None,
+            draft: Draft::default(),
+            draft_saved: Instant::now(),
+            input_changed: None,
+            config,
+            command_input: String::new(),
+            cell_edit: None,
+            column_drag: None,
+            pending: None,
+            history: vec![],
+            watch: None,
+            plan: None,
+            profile: None,
+            chart: None,
+            histogram: None,
+            histogram_bins,
+            stats: None,
+            ddl: None,
+            describe: None,
+            finder: None,
+            diagram: None,
+            plugins,
+            schema_area: Rect::default(),
+            perf: None,
+            recording: None,
+            plugin_pane: None,
+            finder_editing: false,
+            cache,
+            pinned: None,
+            diff: None,
+            sessions,
+        };
+        app.restore_draft();
+        app
+    }
+
+    /// Puts back what was left in the editors when civciv last stopped.
+    fn restore_draft(&mut self) {
+        let draft = Draft::load();
+        if draft.is_empty() {
+            return;
+        }
+        for (pane, input) in self.panes.iter_mut().zip(&draft.inputs) {
+            pane.cursor_position = input.len();
+            pane.input = input.clone();
+        }
+        self.status
+            .get_or_insert_with(|| "Restored the unsaved draft".to_string());
+        self.draft = draft;
+    }
+
+    /// Checks the active editor again once typing has paused. A running
+    /// query holds the session, so the check waits for it.
+    pub fn lint_tick(&mut self) {
+        let pane = &self.panes[self.active];
+        if pane.input == pane.linted {
+            self.input_changed = None;
+            return;
+        }
+        let changed = *self.input_changed.get_or_insert_with(Instant::now);
+        if changed.elapsed() < LINT_DELAY {
+            return;
+        }
+        let input = pane.input.clone();
+        let Ok(lints) = db::with_idle(self.session(), |db| {
+            Ok::<_, String>(lint::check(db, &input))
+        }) else {
+            return;
+        };
+        let pane = self.pane_mut();
+        pane.lints = lints;
+        pane.linted = input;
+        self.input_changed = None;
+    }
+
+    /// Saves the editors to the draft file if they changed and the last
+    /// save was a while ago.
+    pub fn autosave_draft(&mut self) {
+        if self.draft_saved.elapsed() >= DRAFT_INTERVAL {
+            self.save_draft();
+        }
+    }
+
+    /// Saves the editors to the draft file if they changed since the last
+    /// save.
+    pub fn save_draft(&mut self) {
+        self.draft_saved = Instant::now();
+        let draft = Draft {
+            inputs: self.panes.iter().map(|pane| pane.input.clone()).collect(),
+        };
+        if draft == self.draft {
+            return;
+        }
+        match draft.save() {
+            Ok(()) => self.draft = draft,
+            Err(err) => self.status = Some(format!("Could not save the draft: {err}")),
         }
     }
 
-    pub fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.cursor_position.saturating_sub(10);
-        self.cursor_position = self.clamp_cursor(cursor_moved_left);
+    pub fn pane(&self) -> &Pane {
+        &self.panes[self.active]
     }
 
-    pub fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.cursor_position.saturating_add(10);
-        self.cursor_position = self.clamp_cursor(cursor_moved_right);
+    pub fn pane_mut(&mut self) -> &mut Pane {
+        &mut self.panes[self.active]
     }
 
-    pub fn enter_char(&mut self, new_char: char) {
-        self.input.insert(self.cursor_position, new_char);
+    /// Cycles between a single pane, two panes side by side and two stacked.
+    pub fn cycle_split(&mut self) {
+        self.split = match self.split {
+            None => Some(SplitLayout::SideBySide),
+            Some(SplitLayout::SideBySide) => Some(SplitLayout::Stacked),
+            Some(SplitLayout::Stacked) => None,
+        };
+    }
 
-        self.move_cursor_right();
+    /// Grows or shrinks the editors and remembers the new size.
+    pub fn resize_editor(&mut self, grow: bool) {
+        if grow {
+            self.sizes.grow_editor();
+        } else {
+            self.sizes.shrink_editor();
+        }
+        if let Err(err) = self.sizes.save() {
+            self.status = Some(format!("Could not save pane sizes: {err}"));
+        }
     }
 
-    pub fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.cursor_position != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
+    pub fn focus_other_pane(&mut self) {
+        if self.split.is_some() {
+            self.active = 1 - self.active;
+        }
+    }
 
-            let current_index = self.cursor_position;
-            let from_left_to_current_index = current_index - 1;
+    pub fn remember_database(&mut self, target: &str) {
+        self.recent.add_database(target);
+        self.save_recent();
+    }
 
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
+    fn save_recent(&mut self) {
+        if let Err(err) = self.recent.save() {
+            self.status = Some(format!("Could not save recent list: {err}"));
+        }
+    }
 
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+    pub fn open_recent(&mut self) {
+        if self.recent.is_empty() {
+            self.status = Some("No recent databases or files".to_string());
+            return;
         }
+        self.recent_state.select(Some(0));
+        self.input_mode = InputMode::Recent;
     }
 
-    pub fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.len())
+    pub fn select_next_recent(&mut self) {
+        let last = self.recent.entries().len().saturating_sub(1);
+        let next = self.recent_state.selected().map_or(0, |i| i + 1);
+        self.recent_state.select(Some(next.min(last)));
     }
 
-    pub fn reset_cursor(&mut self) {
-        self.cursor_position = 0;
+    pub fn select_previous_recent(&mut self) {
+        let previous = self
+            .recent_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(1));
+        self.recent_state.select(Some(previous));
     }
 
-    pub fn submit_sql(&mut self) {
-        let mut stmt = self.db.prepare(self.input.as_str()).unwrap();
+    /// Reopens the selected entry: databases are attached and made the
+    /// default catalog, data files go through the import wizard.
+    pub fn choose_recent(&mut self) {
+        self.input_mode = InputMode::Normal;
+
+        let Some((kind, target)) = self
+            .recent_state
+            .selected()
+            .and_then(|i| self.recent.entries().get(i).copied())
+            .map(|(kind, target)| (kind, target.to_string()))
+        else {
+            return;
+        };
 
-        self.data = stmt.query_arrow([]).unwrap().collect();
+        match kind {
+            RecentKind::Database => self.attach(&target),
+            RecentKind::File => {
+                let mut wizard = ImportWizard::new();
+                wizard.path = target;
+                if let Err(err) = db::with_idle(self.session(), |db| {
+                    wizard.inspect(db);
+                    Ok::<_, String>(())
+                }) {
+                    wizard.error = Some(err);
+                }
+                self.import = Some(wizard);
+                self.input_mode = InputMode::Importing;
+            }
+        }
+    }
 
-        self.input.clear();
-        self.reset_cursor();
+    pub fn open_connections(&mut self) {
+        if self.config.connections.is_empty() {
+            self.status = Some("No connections in the config's [connections]".to_string());
+            return;
+        }
+        self.connections_state.select(Some(0));
+        self.input_mode = InputMode::Connections;
     }
 
-    pub fn data_to_table(&self) -> Result<Table, ArrowError> {
-        let options = FormatOptions::default().with_display_error(true);
+    pub fn select_next_connection(&mut self) {
+        let last = self.config.connections.len().saturating_sub(1);
+        let next = self.connections_state.selected().map_or(0, |i| i + 1);
+        self.connections_state.select(Some(next.min(last)));
+    }
 
-        let mut table = Table::new();
-        table.load_preset("||--+-++|    ++++++");
+    pub fn select_previous_connection(&mut self) {
+        let previous = self
+            .connections_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(1));
+        self.connections_state.select(Some(previous));
+    }
 
-        if self.data.is_empty() {
-            return Ok(table);
+    /// Switches to the selected connection profile: its database is
+    /// attached and made the default catalog, its settings are applied and
+    /// its init statements run.
+    pub fn choose_connection(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let Some((name, profile)) = self
+            .connections_state
+            .selected()
+            .and_then(|i| self.config.connections.iter().nth(i))
+            .map(|(name, profile)| (name.clone(), profile.clone()))
+        else {
+            return;
+        };
+        if profile.kind.is_none() && profile.path.is_none() {
+            self.status = Some(format!(
+                "{name} is in-memory, open it with --profile {name}"
+            ));
+            return;
+        }
+        let settings = profile.options.iter().try_for_each(|(key, value)| {
+            let sql = format!("SET GLOBAL {key} = {}", quote_literal(value));
+            db::with_idle(self.session(), |db| db.execute_batch(&sql))
+        });
+        if let Err(err) = settings {
+            self.status = Some(err);
+            return;
+        }
+        match profile.kind {
+            Some(kind) => self.attach_server(&name, kind, &profile),
+            None => {
+                let target = profile.path.as_deref().unwrap_or_default();
+                self.attach_with(target, profile.read_only, profile.init_sql.as_deref());
+            }
         }
+    }
 
-        let schema = self.data[0].schema();
+    /// Attaches a profile's database server under the profile's name and
+    /// makes it the default catalog, like [`Self::attach_with`] does for
+    /// files.
+    fn attach_server(&mut self, name: &str, kind: RemoteKind, profile: &ConnectionProfile) {
+        let use_it = format!("USE {}", quote_identifier(name));
+        let attached = db::with_idle(self.session(), |db| {
+            let connection = profile.path.as_deref().unwrap_or_default();
+            remote::attach(db, kind, connection, name, profile.read_only)?;
+            db.execute_batch(&use_it)?;
+            if let Some(sql) = &profile.init_sql {
+                db.execute_batch(sql)?;
+            }
+            Ok::<_, duckdb::Error>(())
+        });
+        if let Err(err) = attached {
+            self.status = Some(err);
+            return;
+        }
+        self.cache.clear();
+        let other = &self.sessions[1 - self.active];
+        let followed = db::with_idle(other, |db| db.execute_batch(&use_it));
+        self.status = Some(match followed {
+            Ok(()) => format!("Using {name}"),
+            Err(_) => format!("Using {name} in this pane"),
+        });
+        self.refresh_schema();
+    }
 
-        let mut header = Vec::new();
-        for field in schema.fields() {
-            header.push(Cell::new(field.name()));
+    pub fn open_column_picker(&mut self) {
+        if self.pane().data.is_empty() {
+            self.status = Some("No result columns to pick".to_string());
+            return;
         }
-        table.set_header(header);
+        self.column_picker.select(Some(0));
+        self.input_mode = InputMode::Columns;
+    }
 
-        for batch in self.data.iter() {
-            let formatters = batch
-                .columns()
-                .iter()
-                .map(|c| ArrayFormatter::try_new(c.as_ref(), &options))
-                .collect::<Result<Vec<_>, ArrowError>>()?;
+    pub fn select_next_column(&mut self) {
+        let last = grid::header(&self.pane().data).len().saturating_sub(1);
+        let next = self.column_picker.selected().map_or(0, |i| i + 1);
+        self.column_picker.select(Some(next.min(last)));
+    }
+
+    pub fn select_previous_column(&mut self) {
+        let previous = self
+            .column_picker
+            .selected()
+            .map_or(0, |i| i.saturating_sub(1));
+        self.column_picker.select(Some(previous));
+    }
+
+    /// Hides the selected column of the result, or shows it again.
+    pub fn toggle_column(&mut self) {
+        let pane = &mut self.panes[self.active];
+        let header = grid::header(&pane.data);
+        let Some(name) = self.column_picker.selected().and_then(|i| header.get(i)) else {
+            return;
+        };
+        if !pane.hidden_columns.remove(name) {
+            pane.hidden_columns.insert(name.clone());
+        }
+    }
 
-            for row in 0..batch.num_rows() {
-                let mut cells = Vec::new();
-                for formatter in &formatters {
-                    cells.push(Cell::new(formatter.value(row)));
+    /// Widens or narrows the selected column, starting from its automatic
+    /// width.
+    pub fn resize_column(&mut self, wider: bool) {
+        let pane = &mut self.panes[self.active];
+        let header = grid::header(&pane.data);
+        let Some(name) = header.get(pane.selected_column) else {
+            return;
+        };
+        let width = match pane.column_widths.get(name) {
+            Some(&width) => width,
+            None => match grid::column_widths(&header, self.show_types, &pane.data, &self.config) {
+                Ok(widths) => widths[pane.selected_column],
+                Err(err) => {
+                    self.status = Some(err.to_string());
+                    return;
                 }
-                table.add_row(cells);
+            },
+        };
+        let width = if wider {
+            width.saturating_add(COLUMN_WIDTH_STEP)
+        } else {
+            width.saturating_sub(COLUMN_WIDTH_STEP).max(1)
+        };
+        pane.column_widths.insert(name.clone(), width);
+    }
+
+    /// Starts resizing a column when the mouse is pressed on the separator
+    /// after its header.
+    fn start_column_drag(&mut self, x: u16, y: u16) -> bool {
+        for (i, pane) in self.panes.iter().enumerate() {
+            let Some(edge) = pane.header_edges.iter().find(|e| e.x == x && e.y == y) else {
+                continue;
+            };
+            if let Some(name) = grid::header(&pane.data).get(edge.column) {
+                self.column_drag = Some(ColumnDrag {
+                    pane: i,
+                    column: name.clone(),
+                    from: x,
+                    width: edge.width,
+                });
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Acts on a left click at a screen cell: starts dragging a column
+    /// edge, sorts by a column header, focuses an editor or result, or
+    /// selects a schema node and expands or collapses it.
+    pub fn click(&mut self, x: u16, y: u16) {
+        let focused = matches!(
+            self.input_mode,
+            InputMode::Normal | InputMode::Editing | InputMode::Browsing
+        );
+        if !focused || self.start_column_drag(x, y) {
+            return;
+        }
+
+        for i in 0..self.panes.len() {
+            let pane = &self.panes[i];
+            let header = pane.header_edges.iter().find(|edge| {
+                edge.y == y && (edge.x.saturating_sub(edge.width)..edge.x).contains(&x)
+            });
+            if let Some(edge) = header {
+                let column = edge.column;
+                self.active = i;
+                self.input_mode = InputMode::Normal;
+                self.sort_by_column(column);
+                return;
+            }
+            if contains(pane.editor_area, x, y) {
+                self.active = i;
+                self.input_mode = InputMode::Editing;
+                return;
+            }
+            if contains(pane.result_area, x, y) {
+                self.active = i;
+                self.input_mode = InputMode::Normal;
+                return;
+            }
+        }
+
+        let area = self.schema_area;
+        if contains(area, x, y) && y > area.y {
+            let row = self.schema.state.offset() + (y - area.y - 1) as usize;
+            if row < self.schema.visible_nodes().len() {
+                self.input_mode = InputMode::Browsing;
+                self.schema.state.select(Some(row));
+                self.schema.toggle_expanded();
+                self.preview_selected_table();
+            }
+        }
+    }
+
+    /// Moves the cell cursor a few rows for a turn of the mouse wheel over
+    /// a result, or the selection over the schema browser.
+    pub fn scroll_wheel(&mut self, x: u16, y: u16, down: bool) {
+        const WHEEL_ROWS: usize = 3;
+        if contains(self.schema_area, x, y) {
+            for _ in 0..WHEEL_ROWS {
+                if down {
+                    self.schema.select_next();
+                } else {
+                    self.schema.select_previous();
+                }
+            }
+            return;
+        }
+        let Some(i) = (0..self.panes.len()).find(|&i| contains(self.panes[i].result_area, x, y))
+        else {
+            return;
+        };
+        if !matches!(self.input_mode, InputMode::Normal | InputMode::Editing) {
+            return;
+        }
+        self.active = i;
+        for _ in 0..WHEEL_ROWS {
+            self.move_row(down, false);
+        }
+    }
+
+    /// Sorts the active pane's query by a result column, ascending first,
+    /// then descending, then back to its own order.
+    pub fn sort_by_column(&mut self, column: usize) {
+        let pane = self.pane();
+        let Some(sql) = pane.last_query.clone().filter(|sql| sql::is_query(sql)) else {
+            self.status = Some("Only query results can be sorted".to_string());
+            return;
+        };
+        let Some(name) = grid::header(&pane.data).get(column).cloned() else {
+            return;
+        };
+        let pane = self.pane_mut();
+        pane.sort = match pane.sort.take() {
+            Some(sort) if sort.column == name && !sort.descending => Some(Sort {
+                column: name,
+                descending: true,
+            }),
+            Some(sort) if sort.column == name => None,
+            _ => Some(Sort {
+                column: name,
+                descending: false,
+            }),
+        };
+        pane.selected_column = column;
+        pane.changed_rows.clear();
+        self.execute(&sql, AfterRun::Nothing);
+    }
+
+    /// Opens the form that picks the columns to pivot the result on, with
+    /// the selected one becoming columns.
+    pub fn open_pivot(&mut self) {
+        let pane = self.pane();
+        if !pane.last_query.as_deref().is_some_and(sql::is_query) {
+            self.status = Some("Only query results can be pivoted".to_string());
+            return;
+        }
+        let header = grid::header(&pane.data);
+        if header.len() < 3 {
+            self.status = Some("Pivoting needs a result with three columns or more".to_string());
+            return;
+        }
+        self.pivot = Some(PivotForm::new(header, pane.selected_column));
+        self.input_mode = InputMode::Pivot;
+    }
+
+    pub fn close_pivot(&mut self) {
+        self.pivot = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Runs the pivot picked in the form over the last query, leaving the
+    /// `PIVOT` in the editor.
+    pub fn run_pivot(&mut self) {
+        let Some(form) = self.pivot.take() else {
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+        let Some(source) = self.pane().last_query.clone() else {
+            return;
+        };
+        let values = self
+            .query(self.active, &form.values_sql(&source))
+            .and_then(|data| {
+                let values: Vec<String> = row_cells(&data)
+                    .map_err(|err| err.to_string())?
+                    .into_iter()
+                    .filter_map(|row| row.into_iter().next())
+                    .collect();
+                Ok(values)
+            });
+        let values = match values {
+            Ok(values) if values.len() > pivot::MAX_COLUMNS => {
+                self.status = Some(format!(
+                    "{} has more than {} values to make columns of",
+                    form.fields()[1].1,
+                    pivot::MAX_COLUMNS
+                ));
+                return;
+            }
+            Ok(values) if values.is_empty() => {
+                self.status = Some(format!(
+                    "{} has no values to make columns of",
+                    form.fields()[1].1
+                ));
+                return;
+            }
+            Ok(values) => values,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        self.run_generated(form.sql(&source, &values));
+    }
+
+    /// Counts the values of the selected column in the last query's
+    /// result, most common first.
+    pub fn count_values(&mut self) {
+        let pane = self.pane();
+        let Some(source) = pane.last_query.clone().filter(|sql| sql::is_query(sql)) else {
+            self.status = Some("Only query results can be grouped".to_string());
+            return;
+        };
+        let Some(name) = grid::header(&pane.data).get(pane.selected_column).cloned() else {
+            return;
+        };
+        self.run_generated(sql::value_counts(&source, &name));
+    }
+
+    /// Runs a query civciv wrote for the user, leaving it in the editor to
+    /// tweak and in the history.
+    fn run_generated(&mut self, sql: String) {
+        let pane = self.pane_mut();
+        pane.input = sql.clone();
+        pane.cursor_position = sql.len();
+        self.add_to_history(sql.clone());
+        self.execute(&sql, AfterRun::Nothing);
+    }
+
+    /// Swaps the rows and columns of the result shown, or puts them back.
+    pub fn toggle_transpose(&mut self) {
+        let format = &self.config.format;
+        let pane = &mut self.panes[self.active];
+        let data = match pane.untransposed.take() {
+            Some(original) => {
+                pane.source = original.source;
+                pane.page = original.page;
+                original.data
+            }
+            None => {
+                let rows = row_count(&pane.data);
+                if rows == 0 {
+                    self.status = Some("No result to transpose".to_string());
+                    return;
+                }
+                if rows > MAX_TRANSPOSED_ROWS {
+                    self.status = Some(format!(
+                        "Only results of up to {MAX_TRANSPOSED_ROWS} rows can be transposed"
+                    ));
+                    return;
+                }
+                let transposed = match grid::transpose(
+                    &pane.data,
+                    pane.first_row(),
+                    &pane.hidden_columns,
+                    format,
+                ) {
+                    Ok(transposed) => transposed,
+                    Err(err) => {
+                        self.status = Some(err.to_string());
+                        return;
+                    }
+                };
+                // Like a plugin's result, it didn't come from a statement,
+                // so cells can't be edited through it.
+                pane.untransposed = Some(Untransposed {
+                    data: std::mem::take(&mut pane.data),
+                    source: pane.source.take(),
+                    page: pane.page.take(),
+                });
+                vec![transposed]
+            }
+        };
+        pane.data = data;
+        pane.selected_row = 0;
+        pane.selected_column = 0;
+        pane.vertical_scroll = 0;
+        pane.horizontal_scroll = 0;
+        pane.selection_anchor = None;
+        pane.changed_rows.clear();
+    }
+
+    /// Highlights every cell of the result holding the selected cell's
+    /// value, or stops highlighting it.
+    pub fn toggle_highlight(&mut self) {
+        let format = &self.config.format;
+        let pane = &mut self.panes[self.active];
+        let Ok(rows) = grid::rows(&pane.data, 0, usize::MAX, format) else {
+            return;
+        };
+        let value = match rows
+            .get(pane.selected_row)
+            .and_then(|row| row.get(pane.selected_column))
+        {
+            Some(Some(value)) => value.clone(),
+            Some(None) => {
+                self.status = Some("NULL cells aren't highlighted".to_string());
+                return;
+            }
+            None => return,
+        };
+        if pane.highlight.as_ref() == Some(&value) {
+            pane.highlight = None;
+            return;
+        }
+        let count = rows
+            .iter()
+            .flatten()
+            .filter(|cell| cell.as_ref() == Some(&value))
+            .count();
+        self.status = Some(format!("{count} cells hold {}", quote_literal(&value)));
+        pane.highlight = Some(value);
+    }
+
+    /// Asks for the regex to filter the selected column by, starting from
+    /// the one it has.
+    pub fn prompt_filter(&mut self) {
+        let pane = self.pane();
+        let Some(name) = grid::header(&pane.data).get(pane.selected_column).cloned() else {
+            self.status = Some("No result column to filter".to_string());
+            return;
+        };
+        let regex = pane.filters.get(&name).cloned().unwrap_or_default();
+        self.command_input = format!("filter {regex}");
+        self.input_mode = InputMode::Command;
+    }
+
+    /// Keeps only the rows whose selected column matches `regex`, running
+    /// the query again. An empty regex drops the column's filter.
+    fn filter_column(&mut self, regex: String) {
+        let pane = self.pane();
+        let Some(sql) = pane.last_query.clone().filter(|sql| sql::is_query(sql)) else {
+            self.status = Some("Only query results can be filtered".to_string());
+            return;
+        };
+        let Some(name) = grid::header(&pane.data).get(pane.selected_column).cloned() else {
+            return;
+        };
+        let pane = self.pane_mut();
+        if regex.is_empty() {
+            pane.filters.remove(&name);
+        } else {
+            pane.filters.insert(name, regex);
+        }
+        pane.selected_row = 0;
+        pane.vertical_scroll = 0;
+        pane.changed_rows.clear();
+        self.execute(&sql, AfterRun::Nothing);
+    }
+
+    /// Drops every column filter, running the query again.
+    pub fn clear_filters(&mut self) {
+        let pane = self.pane_mut();
+        if pane.filters.is_empty() {
+            self.status = Some("No column filters".to_string());
+            return;
+        }
+        pane.filters.clear();
+        let Some(sql) = pane.last_query.clone() else {
+            return;
+        };
+        self.execute(&sql, AfterRun::Nothing);
+    }
+
+    /// Sizes the column being dragged so its edge follows the mouse.
+    pub fn drag_column(&mut self, x: u16) {
+        let Some(drag) = self.column_drag.as_ref() else {
+            return;
+        };
+        let width = (drag.width + x).saturating_sub(drag.from).max(1);
+        self.panes[drag.pane]
+            .column_widths
+            .insert(drag.column.clone(), width);
+    }
+
+    /// Opens the insert row form for the table selected in the schema
+    /// browser.
+    pub fn open_insert_form(&mut self) {
+        let Some(Node::Table(c, s, t)) = self.schema.selected() else {
+            self.status = Some("Select a table to insert into".to_string());
+            return;
+        };
+        let catalog = &self.schema.catalogs[c];
+        let schema = &catalog.schemas[s];
+        let table = &schema.tables[t];
+        if table.is_view {
+            self.status = Some(format!("{} is a view", table.name));
+            return;
+        }
+
+        let form = db::with_idle(self.session(), |db| {
+            InsertForm::load(db, &catalog.name, &schema.name, &table.name)
+        });
+        match form {
+            Ok(form) => {
+                self.insert = Some(form);
+                self.input_mode = InputMode::Inserting;
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    /// Asks to confirm the form's `INSERT`. The form stays open behind the
+    /// prompt, so backing out keeps what was typed.
+    pub fn preview_insert(&mut self) {
+        if let Some(form) = &self.insert {
+            self.confirm(form.statement());
+        }
+    }
+
+    pub fn close_insert_form(&mut self) {
+        self.insert = None;
+        self.input_mode = InputMode::Browsing;
+    }
+
+    pub fn select_next_node(&mut self) {
+        self.schema.select_next();
+        self.preview_selected_table();
+    }
+
+    pub fn select_previous_node(&mut self) {
+        self.schema.select_previous();
+        self.preview_selected_table();
+    }
+
+    /// Shows the first rows of the table or view selected in the schema
+    /// browser as the result.
+    fn preview_selected_table(&mut self) {
+        let rows = self.config.preview_rows;
+        let Some(Node::Table(c, s, t)) = self.schema.selected().filter(|_| rows > 0) else {
+            return;
+        };
+        let catalog = &self.schema.catalogs[c];
+        let schema = &catalog.schemas[s];
+        let name = [&catalog.name, &schema.name, &schema.tables[t].name]
+            .map(|part| quote_identifier(part))
+            .join(".");
+        self.execute(
+            &format!("SELECT * FROM {name} LIMIT {rows}"),
+            AfterRun::Nothing,
+        );
+    }
+
+    /// Shows the `CREATE` statement of the table or view selected in the
+    /// schema browser.
+    pub fn open_ddl(&mut self) {
+        let Some(node) = self.schema.selected() else {
+            return;
+        };
+        let ddl = db::with_idle(self.session(), |db| self.schema.ddl(db, node));
+        match ddl {
+            Ok(Some((name, sql))) => {
+                self.ddl = Some(Ddl {
+                    name,
+                    sql,
+                    scroll: 0,
+                });
+                self.input_mode = InputMode::Ddl;
+            }
+            Ok(None) => self.status = Some("Select a table or view".to_string()),
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    /// Draws the tables of the selected catalog or schema, or the selected
+    /// table with the ones it is linked to by foreign keys.
+    pub fn open_diagram(&mut self) {
+        let Some(node) = self.schema.selected() else {
+            return;
+        };
+        let catalogs = &self.schema.catalogs;
+        let (title, tables): (String, Vec<&schema::Table>) = match node {
+            Node::Catalog(c) => (
+                catalogs[c].name.clone(),
+                catalogs[c]
+                    .schemas
+                    .iter()
+                    .flat_map(|schema| &schema.tables)
+                    .collect(),
+            ),
+            Node::Schema(c, s) => {
+                let schema = &catalogs[c].schemas[s];
+                (schema.name.clone(), schema.tables.iter().collect())
+            }
+            Node::Table(c, s, t) | Node::Column(c, s, t, _) | Node::Constraint(c, s, t, _) => {
+                let schema = &catalogs[c].schemas[s];
+                let table = &schema.tables[t];
+                let linked = |other: &schema::Table| {
+                    other.name == table.name
+                        || table.foreign_keys.iter().any(|key| key.table == other.name)
+                        || other.foreign_keys.iter().any(|key| key.table == table.name)
+                };
+                (
+                    table.name.clone(),
+                    schema.tables.iter().filter(|other| linked(other)).collect(),
+                )
+            }
+        };
+        if tables.is_empty() {
+            self.status = Some(format!("{title} has no tables"));
+            return;
+        }
+        self.diagram = Some(Diagram::new(format!("ER diagram: {title}"), &tables));
+        self.input_mode = InputMode::Diagram;
+    }
+
+    pub fn close_diagram(&mut self) {
+        self.diagram = None;
+        self.input_mode = InputMode::Browsing;
+    }
+
+    pub fn copy_ddl(&mut self) {
+        let Some(ddl) = self.ddl.as_ref() else {
+            return;
+        };
+        self.status = Some(format!("Copied the DDL of {}", ddl.name));
+        self.clipboard = Some(ddl.sql.clone());
+    }
+
+    pub fn close_ddl(&mut self) {
+        self.ddl = None;
+        self.input_mode = InputMode::Browsing;
+    }
+
+    /// Opens the fuzzy finder over the tables and views in the schema
+    /// browser.
+    pub fn open_finder(&mut self, editing: bool) {
+        self.refresh_schema();
+        let finder = Finder::new(&self.schema);
+        if finder.matches.is_empty() {
+            self.status = Some("No tables or views".to_string());
+            return;
+        }
+        self.finder = Some(finder);
+        self.finder_editing = editing;
+        self.input_mode = InputMode::Finder;
+    }
+
+    /// Puts the chosen name into the editor at the cursor.
+    pub fn insert_found(&mut self) {
+        let Some(name) = self
+            .finder
+            .take()
+            .and_then(|f| f.selected().map(str::to_string))
+        else {
+            return;
+        };
+        self.pane_mut().insert_text(&name);
+        self.input_mode = InputMode::Editing;
+    }
+
+    /// Shows the first rows of the chosen table or view as the result.
+    pub fn preview_found(&mut self) {
+        let Some(name) = self
+            .finder
+            .take()
+            .and_then(|f| f.selected().map(str::to_string))
+        else {
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+        self.execute(
+            &format!("SELECT * FROM {name} LIMIT {}", finder::PREVIEW_ROWS),
+            AfterRun::Nothing,
+        );
+    }
+
+    pub fn close_finder(&mut self) {
+        self.finder = None;
+        self.input_mode = if self.finder_editing {
+            InputMode::Editing
+        } else {
+            InputMode::Normal
+        };
+    }
+
+    /// Describes the object named under the editor cursor.
+    pub fn describe_at_cursor(&mut self) {
+        let pane = self.pane();
+        let cursor = pane.clamp_cursor(pane.cursor_position);
+        match sql::identifier_at(&pane.input, cursor) {
+            Some(name) => self.describe(name.to_string(), true),
+            None => self.status = Some("No name under the cursor".to_string()),
+        }
+    }
+
+    /// Describes the object named by the cell under the result cursor, such
+    /// as a table listed by `SHOW TABLES`.
+    pub fn describe_cell(&mut self) {
+        let pane = self.pane();
+        let cell = grid::rows(&pane.data, pane.selected_row, 1, &CellFormat::default())
+            .ok()
+            .and_then(|rows| rows.into_iter().next())
+            .and_then(|row| row.into_iter().nth(pane.selected_column).flatten());
+        match cell {
+            Some(name) => {
+                let name = name.split('.').map(quote_identifier).collect::<Vec<_>>();
+                self.describe(name.join("."), false);
+            }
+            None => self.status = Some("No name under the cursor".to_string()),
+        }
+    }
+
+    /// Shows the columns of `name` in a popup, leaving the editor and the
+    /// result alone.
+    fn describe(&mut self, name: String, editing: bool) {
+        let columns = db::with_idle(self.session(), |db| {
+            let mut stmt = db.prepare(&format!("DESCRIBE {name}"))?;
+            let columns = stmt
+                .query_map([], |row| {
+                    let text = |i| {
+                        row.get::<_, Option<String>>(i)
+                            .map(Option::unwrap_or_default)
+                    };
+                    Ok([text(0)?, text(1)?, text(2)?, text(3)?, text(4)?])
+                })?
+                .collect::<duckdb::Result<Vec<_>>>();
+            columns
+        });
+        match columns {
+            Ok(columns) => {
+                self.describe = Some(Describe {
+                    name,
+                    columns,
+                    editing,
+                });
+                self.input_mode = InputMode::Describe;
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    pub fn close_describe(&mut self) {
+        let editing = self
+            .describe
+            .take()
+            .is_some_and(|describe| describe.editing);
+        self.input_mode = if editing {
+            InputMode::Editing
+        } else {
+            InputMode::Normal
+        };
+    }
+
+    /// Reloads the schema browser from whichever session is free; the
+    /// catalog is the same for both. Keeps the old tree while both are busy.
+    pub fn refresh_schema(&mut self) {
+        let loaded = self
+            .sessions
+            .iter()
+            .find_map(|session| db::with_idle(session, SchemaBrowser::load).ok());
+        if let Some(mut schema) = loaded {
+            schema.keep_expanded(&self.schema);
+            self.schema = schema;
+        }
+    }
+
+    /// The session of the active pane.
+    fn session(&self) -> &Mutex<Connection> {
+        &self.sessions[self.active]
+    }
+
+    /// Attaches a database in the active pane's session and makes it the
+    /// default catalog there. The other pane follows unless it is busy.
+    fn attach(&mut self, target: &str) {
+        self.attach_with(target, false, None);
+    }
+
+    /// Attaches a database like [`Self::attach`], then runs `init_sql` in
+    /// the active pane's session.
+    fn attach_with(&mut self, target: &str, read_only: bool, init_sql: Option<&str>) {
+        let attached = db::with_idle(self.session(), |db| {
+            let alias = db::attach(db, target, read_only, &self.config)?;
+            if let Some(sql) = init_sql {
+                db.execute_batch(sql)?;
+            }
+            Ok::<_, duckdb::Error>(alias)
+        });
+        match attached {
+            Ok(alias) => {
+                // Unqualified names may now resolve to other tables.
+                self.cache.clear();
+                let other = &self.sessions[1 - self.active];
+                let followed =
+                    db::with_idle(other, |db| db::attach(db, target, read_only, &self.config));
+                self.status = Some(match followed {
+                    Ok(_) => format!("Using {alias}"),
+                    Err(_) => format!("Using {alias} in this pane"),
+                });
+                self.remember_database(target);
+                self.refresh_schema();
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    pub fn open_remote(&mut self, kind: RemoteKind) {
+        self.remote = Some(RemoteForm::new(kind));
+        self.input_mode = InputMode::Remote;
+    }
+
+    pub fn close_remote(&mut self) {
+        self.remote = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Attaches the database server described in the form. Attached
+    /// databases are shared, so both panes and the schema browser see it.
+    pub fn attach_remote(&mut self) {
+        let Some(form) = self.remote.as_mut() else {
+            return;
+        };
+        match db::with_idle(&self.sessions[self.active], |db| form.attach(db)) {
+            Ok(alias) => {
+                self.status = Some(format!("Attached {} as {alias}", form.kind.name()));
+                self.close_remote();
+                self.refresh_schema();
+            }
+            Err(err) => form.error = Some(err),
+        }
+    }
+
+    /// Loads httpfs and runs a query over the file at `url`, leaving it in
+    /// the editor to refine.
+    fn open_url(&mut self, url: &str) {
+        if let Err(err) = db::with_idle(self.session(), |db| {
+            db.execute_batch("INSTALL httpfs; LOAD httpfs;")
+        }) {
+            self.status = Some(err);
+            return;
+        }
+        self.run_generated(import::preview_sql(url));
+    }
+
+    /// Creates a view over a lakehouse table, which then shows up in the
+    /// schema browser.
+    fn register_table(&mut self, format: TableFormat, path: &str, name: Option<&str>) {
+        let registered = db::with_idle(self.session(), |db| {
+            lakehouse::register(db, format, path, name)
+        });
+        match registered {
+            Ok(name) => {
+                // The view may replace one cached queries read from.
+                self.cache.clear();
+                self.status = Some(format!("Created view {name} over {path}"));
+                self.refresh_schema();
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    /// Types a character in the editor, closing brackets and uppercasing
+    /// the keyword it ends as the config asks.
+    pub fn type_char(&mut self, c: char) {
+        let autoclose = self.config.autoclose;
+        let ends_word = !c.is_alphanumeric() && c != '_';
+        let uppercase = self.config.uppercase_keywords && ends_word;
+        let pane = self.pane_mut();
+        if uppercase {
+            pane.uppercase_keyword();
+        }
+        if autoclose {
+            pane.enter_char_paired(c);
+        } else {
+            pane.enter_char(c);
+        }
+    }
+
+    fn list_variables(&mut self) {
+        self.status = Some(if self.variables.is_empty() {
+            "No variables set, :set <name> <value> sets one".to_string()
+        } else {
+            let variables: Vec<String> = self
+                .variables
+                .iter()
+                .map(|(name, value)| format!("${name} = {}", quote_literal(value)))
+                .collect();
+            variables.join(", ")
+        });
+    }
+
+    /// Pretty-prints the SQL in the editor, leaving the cursor at its end.
+    pub fn format_input(&mut self) {
+        let pane = self.pane_mut();
+        if pane.input.trim().is_empty() {
+            self.status = Some("Nothing to format".to_string());
+            return;
+        }
+        pane.input = sql::format(&pane.input);
+        pane.cursor_position = pane.input.len();
+    }
+
+    /// Starts the guided tour, with the sample tables it queries.
+    pub fn start_tutorial(&mut self) {
+        let loaded = db::with_idle(self.session(), samples::load_missing);
+        if let Err(err) = loaded {
+            self.status = Some(err);
+            return;
+        }
+        self.refresh_schema();
+        self.tutorial = Some(Tutorial::default());
+        if let Err(err) = Tutorial::mark_seen() {
+            tracing::warn!(error = %err, "could not mark the tutorial as seen");
+        }
+    }
+
+    /// Moves the tour on when `step` is the one it is showing.
+    pub fn tutorial_reached(&mut self, step: Step) {
+        let Some(tutorial) = self.tutorial.as_mut() else {
+            return;
+        };
+        if tutorial.reached(step) {
+            self.tutorial = None;
+            let done = "That's the tour, :tutorial takes it again";
+            self.status = Some(match self.status.take() {
+                Some(status) => format!("{status}. {done}"),
+                None => done.to_string(),
+            });
+        }
+    }
+
+    /// Creates the sample tables named, or all of them when none are.
+    fn load_samples(&mut self, names: &[String]) {
+        let chosen: Vec<&Sample> = if names.is_empty() {
+            samples::SAMPLES.iter().collect()
+        } else {
+            let mut chosen = vec![];
+            for name in names {
+                let Some(sample) = samples::find(name) else {
+                    let known: Vec<&str> = samples::SAMPLES.iter().map(|s| s.name).collect();
+                    self.status = Some(format!("Unknown sample: {name}, try {}", known.join(", ")));
+                    return;
+                };
+                chosen.push(sample);
+            }
+            chosen
+        };
+        match db::with_idle(self.session(), |db| samples::load(db, &chosen)) {
+            Ok(()) => {
+                self.cache.clear();
+                let names: Vec<&str> = chosen.iter().map(|sample| sample.name).collect();
+                self.status = Some(format!("Created tables {}", names.join(", ")));
+                self.refresh_schema();
             }
+            Err(err) => self.status = Some(err),
         }
+    }
+
+    /// Generates a benchmark's tables in the background, then lists them
+    /// in the schema browser.
+    fn generate_demo(&mut self, benchmark: Benchmark, scale: f64) {
+        if self.pane().running.is_some() {
+            self.status = Some("A query is still running in this pane".to_string());
+            return;
+        }
+        let extension = benchmark.extension();
+        let loaded = db::with_idle(self.session(), |db| {
+            db.execute_batch(&format!("INSTALL {extension}; LOAD {extension};"))
+        });
+        if let Err(err) = loaded {
+            self.status = Some(err);
+            return;
+        }
+        // The generated tables may replace ones cached queries read from.
+        self.cache.clear();
+        let sql = format!("CALL {}(sf = {scale})", benchmark.generator());
+        let pane = self.pane_mut();
+        pane.sort = None;
+        pane.filters.clear();
+        pane.page = None;
+        pane.params.clear();
+        pane.last_query = Some(sql.clone());
+        // Without a row limit the call isn't wrapped in a paging query.
+        self.start_page(
+            self.active,
+            sql,
+            0,
+            0,
+            Place::Replace,
+            AfterRun::RefreshSchema,
+        );
+        self.status = Some(format!(
+            "Generating {} data at sf={scale}…",
+            benchmark.name()
+        ));
+    }
+
+    pub fn open_settings(&mut self) {
+        match db::with_idle(self.session(), SettingsPanel::load) {
+            Ok(panel) => {
+                self.settings = Some(panel);
+                self.input_mode = InputMode::Settings;
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    pub fn close_settings(&mut self) {
+        self.settings = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Sets the setting being edited in the active pane's session.
+    pub fn apply_setting(&mut self) {
+        if let Some(panel) = self.settings.as_mut() {
+            let applied = db::with_idle(&self.sessions[self.active], |db| {
+                panel.apply(db);
+                Ok::<_, String>(())
+            });
+            if let Err(err) = applied {
+                panel.error = Some(err);
+            }
+        }
+    }
+
+    pub fn open_secrets(&mut self) {
+        match db::with_idle(self.session(), SecretsPanel::load) {
+            Ok(panel) => {
+                self.secrets = Some(panel);
+                self.input_mode = InputMode::Secrets;
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    pub fn close_secrets(&mut self) {
+        self.secrets = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn create_secret(&mut self) {
+        if let Some(panel) = self.secrets.as_mut() {
+            let created = db::with_idle(&self.sessions[self.active], |db| {
+                panel.create(db);
+                Ok::<_, String>(())
+            });
+            if let (Err(err), Some(form)) = (created, panel.form.as_mut()) {
+                form.error = Some(err);
+            }
+        }
+    }
+
+    pub fn drop_secret(&mut self) {
+        let Some(panel) = self.secrets.as_mut() else {
+            return;
+        };
+        match db::with_idle(&self.sessions[self.active], |db| panel.drop_selected(db)) {
+            Ok(Some(name)) => self.status = Some(format!("Dropped secret {name}")),
+            Ok(None) => {}
+            Err(err) => self.status = Some(err),
+        }
+    }
 
-        Ok(table)
+    pub fn open_import(&mut self) {
+        self.import = Some(ImportWizard::new());
+        self.input_mode = InputMode::Importing;
     }
+
+    pub fn close_import(&mut self) {
+        self.import = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn import_next_field(&mut self) {
+        if let Some(wizard) = self.import.as_mut() {
+            let moved = db::with_idle(&self.sessions[self.active], |db| {
+                wizard.next_field(db);
+                Ok::<_, String>(())
+            });
+            if let Err(err) = moved {
+                wizard.error = Some(err);
+            }
+        }
+    }
+
+    pub fn import_previous_field(&mut self) {
+        if let Some(wizard) = self.import.as_mut() {
+            let moved = db::with_idle(&self.sessions[self.active], |db| {
+                wizard.previous_field(db);
+                Ok::<_, String>(())
+            });
+            if let Err(err) = moved {
+                wizard.error = Some(err);
+            }
+        }
+    }
+
+    /// Creates the view or table described by the import wizard and shows
+    /// its preview as the current result.
+    pub fn finish_import(&mut self) {
+        let Some(wizard) = self.import.as_mut() else {
+            return;
+        };
+        let created = db::with_idle(&self.sessions[self.active], |db| wizard.create(db));
+        match created {
+            Ok(_) => {
+                self.cache.clear();
+                let pane = &mut self.panes[self.active];
+                pane.data = std::mem::take(&mut wizard.preview);
+                pane.source = None;
+                pane.page = None;
+                self.recent.add_file(&wizard.path);
+                self.save_recent();
+                self.close_import();
+                self.refresh_schema();
+            }
+            Err(err) => wizard.error = Some(err),
+        }
+    }
+
+    /// Shows the file browser pane, starting in the working directory.
+    pub fn open_files(&mut self) {
+        if self.files.is_none() {
+            match FileBrowser::new() {
+                Ok(browser) => self.files = Some(browser),
+                Err(err) => {
+                    self.status = Some(err.to_string());
+                    return;
+                }
+            }
+        }
+        self.input_mode = InputMode::Files;
+    }
+
+    pub fn close_files(&mut self) {
+        self.files = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn files_parent(&mut self) {
+        if let Some(Err(err)) = self.files.as_mut().map(FileBrowser::parent) {
+            self.status = Some(err.to_string());
+        }
+    }
+
+    /// Opens the selected database file as the default catalog, or inserts
+    /// the selected data file into the query as a string literal.
+    pub fn files_open_selected(&mut self) {
+        let Some(browser) = self.files.as_mut() else {
+            return;
+        };
+
+        let path = match browser.open_selected() {
+            Ok(Some(path)) => path,
+            Ok(None) => return,
+            Err(err) => {
+                self.status = Some(err.to_string());
+                return;
+            }
+        };
+
+        let target = path.to_string_lossy().into_owned();
+        if files::is_database(&path) {
+            self.attach(&target);
+        } else {
+            self.pane_mut()
+                .insert_text(&quote_literal(&files::display_path(&path)));
+            self.recent.add_file(&target);
+            self.save_recent();
+            self.input_mode = InputMode::Editing;
+        }
+    }
+
+    /// Completes the file path in the string literal under the cursor.
+    pub fn complete(&mut self) {
+        self.completions.clear();
+
+        let pane = &mut self.panes[self.active];
+        let cursor = pane.clamp_cursor(pane.cursor_position);
+        let Some(start) = complete::path_literal_start(&pane.input, cursor) else {
+            return;
+        };
+
+        let prefix = &pane.input[start..cursor];
+        let candidates = complete::complete_path(prefix);
+        let completed = complete::common_prefix(&candidates);
+        if completed.len() > prefix.len() {
+            pane.input.replace_range(start..cursor, &completed);
+            pane.cursor_position = start + completed.len();
+        }
+        if candidates.len() > 1 {
+            self.completions = candidates;
+        }
+    }
+
+    pub fn submit_sql(&mut self) {
+        // psql style, `\set region 'EU'` is the same as `:set region 'EU'`.
+        if let Some(command) = self.pane().input.trim().strip_prefix('\\') {
+            self.command_input = command.to_string();
+            self.run_command();
+            if matches!(self.input_mode, InputMode::Normal) {
+                self.input_mode = InputMode::Editing;
+            }
+            let pane = self.pane_mut();
+            pane.input.clear();
+            pane.reset_cursor();
+            return;
+        }
+        let pane = self.pane_mut();
+        let sql = pane.input.clone();
+        pane.changed_rows.clear();
+        pane.history_index = None;
+        self.add_to_history(sql.clone());
+        let mut run = sql.clone();
+        for plugin in &mut self.plugins {
+            match plugin.on_query_submit(&run) {
+                Ok(Some(rewritten)) => run = rewritten,
+                Ok(None) => {}
+                Err(err) => self.status = Some(format!("{}: {err}", plugin.name())),
+            }
+        }
+        self.execute(&run, AfterRun::ClearInput(sql));
+    }
+
+    /// Shows the cached result of a query in the active pane, if there is
+    /// one. Running the query always reads it again; the cache only serves
+    /// results recalled from the history.
+    fn show_cached(&mut self, sql: &str) {
+        let Some(cached) = self.cache.get(sql) else {
+            return;
+        };
+        let pane = &mut self.panes[self.active];
+        pane.untransposed = None;
+        pane.data = cached.data.clone();
+        pane.source = Some(sql.to_string());
+        pane.page = cached.page.clone();
+        pane.running = None;
+        pane.last_query = Some(sql.to_string());
+        self.status = Some("Cached result, r: re-run".to_string());
+    }
+
+    /// Recalls the previous statement into the editor and shows its cached
+    /// result.
+    pub fn history_previous(&mut self) {
+        let index = match self.pane().history_index {
+            Some(i) => i.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.recall(Some(index));
+    }
+
+    /// Steps forward through the history, back to an empty editor at the end.
+    pub fn history_next(&mut self) {
+        let Some(i) = self.pane().history_index else {
+            return;
+        };
+        self.recall((i + 1 < self.history.len()).then_some(i + 1));
+    }
+
+    fn recall(&mut self, index: Option<usize>) {
+        let sql = index
+            .and_then(|i| self.history.get(i))
+            .cloned()
+            .unwrap_or_default();
+        let pane = self.pane_mut();
+        pane.history_index = index;
+        pane.input = sql.clone();
+        pane.cursor_position = sql.len();
+        self.show_cached(&sql);
+    }
+
+    pub fn add_to_history(&mut self, sql: String) {
+        if self.history.last() != Some(&sql) {
+            self.history.push(sql);
+        }
+    }
+
+    /// The statement last run from the active pane.
+    pub fn last_query(&self) -> Option<&str> {
+        self.pane().last_query.as_deref()
+    }
+
+    pub fn rerun_last_query(&mut self) {
+        let Some(sql) = self.last_query().map(str::to_string) else {
+            self.status = Some("No query to re-run".to_string());
+            return;
+        };
+        self.pane_mut().changed_rows.clear();
+        self.execute(&sql, AfterRun::Nothing);
+    }
+
+    /// Runs a statement in the active pane.
+    pub fn execute(&mut self, sql: &str, then: AfterRun) {
+        self.execute_in(self.active, sql, then);
+    }
+
+    /// Runs a statement and shows its result in a pane. Queries are read on
+    /// the background thread, other statements run right away. Failures are
+    /// reported in the status bar. Of several statements, the last one's
+    /// result is shown.
+    fn execute_in(&mut self, pane: usize, sql: &str, then: AfterRun) {
+        // Checked up front so nothing runs when a variable is missing, but
+        // only swapped in for DuckDB, to keep the values out of the history
+        // and logs.
+        if let Err(err) = self.interpolated(sql) {
+            self.status = Some(err);
+            return;
+        }
+        let statements = sql::split_statements(sql);
+        if let [script @ .., last] = statements.as_slice() {
+            if !script.is_empty() {
+                if self.run_script(pane, sql, script) {
+                    self.execute_statement(pane, last, then);
+                }
+                return;
+            }
+        }
+        self.execute_statement(pane, sql, then);
+    }
+
+    /// Runs a single statement once its placeholders have values, asking
+    /// for them when neither the session variables nor the last run give
+    /// them.
+    fn execute_statement(&mut self, pane: usize, sql: &str, then: AfterRun) {
+        let (numbered, names) = sql::placeholders(sql);
+        if names.is_empty() {
+            self.panes[pane].params.clear();
+        } else if let Some(values) = names
+            .iter()
+            .map(|name| self.variables.get(name).cloned().map(Some))
+            .collect::<Option<Vec<_>>>()
+        {
+            // Session variables fill them all in.
+            self.panes[pane].params = values;
+        } else {
+            // Running it again keeps the values it was run with.
+            let last = &self.panes[pane];
+            let again =
+                last.last_query.as_deref() == Some(&numbered) && last.params.len() == names.len();
+            if !again {
+                self.prompt_params(pane, numbered, names, Submit::Run(then));
+                return;
+            }
+        }
+        self.execute_bound(pane, &numbered, then);
+    }
+
+    /// Asks for the values of a query's placeholders, filled in from the
+    /// session variables or else with the ones typed last time.
+    fn prompt_params(&mut self, pane: usize, sql: String, names: Vec<String>, submit: Submit) {
+        let params = names
+            .into_iter()
+            .map(|name| Param {
+                value: self
+                    .variables
+                    .get(&name)
+                    .or_else(|| self.param_values.get(&name))
+                    .cloned()
+                    .unwrap_or_default(),
+                name,
+            })
+            .collect();
+        self.params = Some(ParamsForm {
+            pane,
+            sql,
+            params,
+            selected: 0,
+            submit,
+        });
+        self.input_mode = InputMode::Params;
+    }
+
+    /// Runs the query from the placeholder prompt with the values typed, or
+    /// puts the template filled in with them in the editor.
+    pub fn run_params(&mut self) {
+        let Some(form) = self.params.take() else {
+            return;
+        };
+        self.input_mode = InputMode::Normal;
+        for param in &form.params {
+            self.param_values
+                .insert(param.name.clone(), param.value.clone());
+        }
+        let values = form.values();
+        match form.submit {
+            Submit::Run(then) => {
+                self.panes[form.pane].params = values;
+                self.execute_bound(form.pane, &form.sql, then);
+            }
+            Submit::Fill => self.edit_query(form.pane, form.fill()),
+        }
+    }
+
+    /// The queries and background statements running in the panes, then
+    /// the ones that finished lately.
+    pub fn jobs(&self) -> Vec<JobEntry> {
+        let running = self.panes.iter().enumerate().filter_map(|(i, pane)| {
+            let (sql, state) = match (&pane.running, &pane.job, &pane.cancelling) {
+                (Some(running), _, _) => (&running.sql, JobState::Running),
+                (None, Some(job), _) => (&job.sql, JobState::Running),
+                (None, None, Some(cancelling)) => (&cancelling.sql, JobState::Cancelling),
+                (None, None, None) => return None,
+            };
+            Some(JobEntry {
+                pane: i,
+                sql: sql.clone(),
+                elapsed: pane.busy_since()?.elapsed(),
+                state,
+            })
+        });
+        running.chain(self.job_history.iter().cloned()).collect()
+    }
+
+    pub fn open_jobs(&mut self) {
+        if self.jobs().is_empty() {
+            self.status = Some("No queries have run yet".to_string());
+            return;
+        }
+        self.jobs_state.select(Some(0));
+        self.input_mode = InputMode::Jobs;
+    }
+
+    pub fn select_next_job(&mut self) {
+        let last = self.jobs().len().saturating_sub(1);
+        let next = self.jobs_state.selected().map_or(0, |i| i + 1);
+        self.jobs_state.select(Some(next.min(last)));
+    }
+
+    pub fn select_previous_job(&mut self) {
+        let previous = self
+            .jobs_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(1));
+        self.jobs_state.select(Some(previous));
+    }
+
+    /// Makes the pane the selected job ran in the active one.
+    pub fn choose_job(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let jobs = self.jobs();
+        if let Some(job) = self.jobs_state.selected().and_then(|i| jobs.get(i)) {
+            self.active = job.pane;
+        }
+    }
+
+    /// Stops the selected job if it is still running. DuckDB's Rust API
+    /// has no handle to interrupt a connection with, so the query thread is
+    /// let go and stops at its next batch, which frees the session. A
+    /// statement that sends no rows, like an export, still runs to its end.
+    pub fn cancel_job(&mut self) {
+        let jobs = self.jobs();
+        let Some(job) = self.jobs_state.selected().and_then(|i| jobs.get(i)) else {
+            return;
+        };
+        if job.state != JobState::Running {
+            self.status = Some("Only running jobs can be cancelled".to_string());
+            return;
+        }
+        let pane = &mut self.panes[job.pane];
+        let stream = if let Some(running) = pane.running.take() {
+            // What arrived so far stays, but can't be paged on from.
+            pane.page = None;
+            running.stream
+        } else if let Some(job) = pane.job.take() {
+            job.stream
+        } else {
+            return;
+        };
+        stream.cancel();
+        tracing::info!(pane = job.pane, sql = %job.sql, "job cancelling");
+        pane.cancelling = Some(Cancelling {
+            stream,
+            sql: job.sql.clone(),
+        });
+        self.status = Some(format!(
+            "Cancelling the job in pane {}, it stops once DuckDB hands back its session",
+            job.pane + 1
+        ));
+    }
+
+    pub fn open_templates(&mut self) {
+        if self.config.templates.is_empty() {
+            self.status = Some("No templates in the config's [templates]".to_string());
+            return;
+        }
+        self.templates_state.select(Some(0));
+        self.input_mode = InputMode::Templates;
+    }
+
+    pub fn select_next_template(&mut self) {
+        let last = self.config.templates.len().saturating_sub(1);
+        let next = self.templates_state.selected().map_or(0, |i| i + 1);
+        self.templates_state.select(Some(next.min(last)));
+    }
+
+    pub fn select_previous_template(&mut self) {
+        let previous = self
+            .templates_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(1));
+        self.templates_state.select(Some(previous));
+    }
+
+    pub fn choose_template(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let name = self
+            .templates_state
+            .selected()
+            .and_then(|i| self.config.templates.keys().nth(i))
+            .cloned();
+        if let Some(name) = name {
+            self.use_template(&name);
+        }
+    }
+
+    /// Asks for the values of a template's placeholders, then leaves the
+    /// query in the editor to look over and run.
+    fn use_template(&mut self, name: &str) {
+        let Some(template) = self.config.templates.get(name) else {
+            self.status = Some(format!("No template named {name}"));
+            return;
+        };
+        let (numbered, names) = sql::placeholders(template);
+        if names.is_empty() {
+            let sql = template.clone();
+            self.edit_query(self.active, sql);
+        } else {
+            self.prompt_params(self.active, numbered, names, Submit::Fill);
+        }
+    }
+
+    /// Replaces the SQL in a pane's editor and starts editing it.
+    fn edit_query(&mut self, pane: usize, sql: String) {
+        self.active = pane;
+        let pane = &mut self.panes[pane];
+        pane.cursor_position = sql.len();
+        pane.input = sql;
+        self.input_mode = InputMode::Editing;
+    }
+
+    pub fn close_params(&mut self) {
+        self.params = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Runs a single statement whose placeholders have their values bound
+    /// in the pane already.
+    fn execute_bound(&mut self, pane: usize, sql: &str, then: AfterRun) {
+        // The new result won't be transposed.
+        self.panes[pane].untransposed = None;
+        // A sort or filter only sticks to the query it was picked for.
+        if self.panes[pane].last_query.as_deref() != Some(sql) {
+            self.panes[pane].sort = None;
+            self.panes[pane].filters.clear();
+        }
+        self.panes[pane].last_query = Some(sql.to_string());
+        if sql::is_query(sql) {
+            let limit = self.config.row_limit;
+            self.start_page(pane, sql.to_string(), 0, limit, Place::Replace, then);
+            return;
+        }
+
+        self.panes[pane].running = None;
+        self.panes[pane].page = None;
+        tracing::info!(pane, sql = %sql, "running statement");
+        let started = Instant::now();
+        match self.query(pane, sql) {
+            Ok(data) => {
+                tracing::info!(pane, elapsed = ?started.elapsed(), "statement done");
+                // The statement may have changed what cached queries return.
+                self.cache.clear();
+                if sql::changes_schema(sql) {
+                    self.refresh_schema();
+                }
+                self.panes[pane].data = data;
+                self.panes[pane].source = Some(sql.to_string());
+                self.record(pane, sql, started.elapsed(), None);
+                self.finish(pane, sql, then);
+            }
+            Err(err) => {
+                tracing::error!(pane, sql = %sql, error = %err, "statement failed");
+                self.record(pane, sql, started.elapsed(), Some(&err));
+                self.show_error(pane, sql, err);
+            }
+        }
+    }
+
+    /// Runs statements one after the other, stopping at the first that
+    /// fails. `sql` is the script they came from, for pointing at the
+    /// failure in the editor. Returns whether they all ran.
+    fn run_script(&mut self, pane: usize, sql: &str, statements: &[&str]) -> bool {
+        self.panes[pane].running = None;
+        self.panes[pane].params.clear();
+        // Any of them may change what cached queries return.
+        self.cache.clear();
+        let mut changed_schema = false;
+        for statement in statements {
+            changed_schema |= sql::changes_schema(statement);
+            let started = Instant::now();
+            match self.query(pane, statement) {
+                Ok(_) => self.record(pane, statement, started.elapsed(), None),
+                Err(err) => {
+                    tracing::error!(pane, sql = %statement, error = %err, "statement failed");
+                    self.record(pane, statement, started.elapsed(), Some(&err));
+                    self.show_error(pane, sql, err);
+                    // The ones before it may have changed the schema.
+                    if changed_schema {
+                        self.refresh_schema();
+                    }
+                    return false;
+                }
+            }
+        }
+        if changed_schema {
+            self.refresh_schema();
+        }
+        true
+    }
+
+    /// Appends the next page of a query that was cut off by the row limit.
+    pub fn fetch_more(&mut self) {
+        let pane = self.pane();
+        if pane.running.is_some() {
+            self.status = Some("Still fetching rows".to_string());
+            return;
+        }
+        let Some(page) = pane.page.as_ref().filter(|page| page.more) else {
+            self.status = Some("No more rows to fetch".to_string());
+            return;
+        };
+        self.start_page(
+            self.active,
+            page.sql.clone(),
+            page.fetched,
+            self.config.row_limit,
+            Place::Append,
+            AfterRun::Nothing,
+        );
+    }
+
+    /// Reads back the page before the window, dropped while paging forward.
+    pub fn fetch_previous(&mut self) {
+        let pane = self.pane();
+        if pane.running.is_some() {
+            return;
+        }
+        let Some(page) = pane.page.as_ref().filter(|page| page.start > 0) else {
+            return;
+        };
+        let offset = page.start.saturating_sub(self.config.row_limit);
+        self.start_page(
+            self.active,
+            page.sql.clone(),
+            offset,
+            page.start - offset,
+            Place::Prepend,
+            AfterRun::Nothing,
+        );
+    }
+
+    /// Moves the cell cursor a row, paging in more of the result at either
+    /// end of the window.
+    pub fn move_row(&mut self, forward: bool, extend: bool) {
+        self.tutorial_reached(Step::Scroll);
+        let pane = self.pane();
+        if pane.running.is_none() {
+            let last = row_count(&pane.data).saturating_sub(1);
+            let page = pane.page.as_ref();
+            if forward && pane.selected_row >= last && page.is_some_and(|page| page.more) {
+                self.fetch_more();
+            } else if !forward && pane.selected_row == 0 && page.is_some_and(|page| page.start > 0)
+            {
+                self.fetch_previous();
+            }
+        }
+        self.pane_mut().select_row(forward, extend);
+    }
+
+    /// Starts reading up to `limit` rows from `offset`, asking for one extra
+    /// row to learn whether the result goes on. A `limit` of 0 reads all of
+    /// them.
+    fn start_page(
+        &mut self,
+        pane: usize,
+        sql: String,
+        offset: usize,
+        limit: usize,
+        place: Place,
+        then: AfterRun,
+    ) {
+        let filtered = sql::filtered(&sql, &self.panes[pane].filters);
+        let ordered = match &self.panes[pane].sort {
+            Some(sort) => sql::ordered(&filtered, &sort.column, sort.descending),
+            None => filtered,
+        };
+        let paged = if limit > 0 {
+            sql::limited(&ordered, limit + 1, offset)
+        } else {
+            ordered
+        };
+        let paged = match self.interpolated(&paged) {
+            Ok(paged) => paged,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        tracing::info!(pane, sql = %sql, offset, limit, "running query");
+        self.panes[pane].running = Some(Running {
+            stream: QueryStream::start(
+                Arc::clone(&self.sessions[pane]),
+                paged,
+                self.panes[pane].params.clone(),
+            ),
+            sql,
+            offset,
+            limit,
+            place,
+            incoming: Vec::new(),
+            rows: 0,
+            bytes: 0,
+            then,
+        });
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.panes.iter().any(|pane| pane.busy_since().is_some())
+    }
+
+    /// Moves the batches that arrived from the background thread into the
+    /// results, and wraps up queries that are done.
+    pub fn poll_queries(&mut self) {
+        for pane in 0..self.panes.len() {
+            self.poll_query(pane);
+            self.poll_job(pane);
+            self.poll_cancelling(pane);
+        }
+    }
+
+    /// Records a cancelled job as such once its thread is done with the
+    /// pane's session.
+    fn poll_cancelling(&mut self, i: usize) {
+        let Some(cancelling) = self.panes[i].cancelling.as_ref() else {
+            return;
+        };
+        loop {
+            match cancelling.stream.try_next() {
+                None => return,
+                Some(Fetch::Batch(_) | Fetch::Report(_)) => {}
+                Some(Fetch::Done | Fetch::Failed(_)) => break,
+            }
+        }
+        let Some(cancelling) = self.panes[i].cancelling.take() else {
+            return;
+        };
+        let elapsed = cancelling.stream.started.elapsed();
+        tracing::info!(pane = i, sql = %cancelling.sql, ?elapsed, "job cancelled");
+        self.job_history.push(JobEntry {
+            pane: i,
+            sql: cancelling.sql,
+            elapsed,
+            state: JobState::Cancelled,
+        });
+        self.status = Some(format!("Cancelled the job in pane {}", i + 1));
+    }
+
+    fn poll_job(&mut self, i: usize) {
+        let Some(job) = self.panes[i].job.as_mut() else {
+            return;
+        };
+        let outcome = loop {
+            match job.stream.try_next() {
+                None => return,
+                Some(Fetch::Batch(batch)) => job.rows += batch.num_rows(),
+                Some(Fetch::Report(report)) => job.report = Some(report),
+                Some(Fetch::Done) => break Ok(()),
+                Some(Fetch::Failed(err)) => break Err(err),
+            }
+        };
+        let Some(job) = self.panes[i].job.take() else {
+            return;
+        };
+        let elapsed = job.stream.started.elapsed();
+        self.notify_finished(i, elapsed, outcome.is_err());
+        self.job_history.push(JobEntry {
+            pane: i,
+            sql: job.sql.clone(),
+            elapsed,
+            state: match outcome {
+                Ok(()) => JobState::Done,
+                Err(_) => JobState::Failed,
+            },
+        });
+        match outcome {
+            Ok(()) => {
+                tracing::info!(pane = i, sql = %job.sql, ?elapsed, "job done");
+                match job.then {
+                    JobDone::Status(done) => {
+                        self.status = Some(done);
+                        if i == self.active {
+                            self.tutorial_reached(Step::Export);
+                        }
+                    }
+                    JobDone::Profile(output) => match ProfileView::load(&output) {
+                        Ok(profile) => {
+                            self.status = Some(format!("Profiled {} rows", job.rows));
+                            self.profile = Some(profile);
+                            self.input_mode = InputMode::Profile;
+                        }
+                        Err(err) => self.status = Some(err.to_string()),
+                    },
+                    JobDone::Report => self.status = job.report,
+                }
+            }
+            Err(err) => {
+                tracing::error!(pane = i, sql = %job.sql, error = %err, "job failed");
+                if let JobDone::Profile(output) = job.then {
+                    let _ = fs::remove_file(output);
+                }
+                self.status = Some(err);
+            }
+        }
+    }
+
+    fn poll_query(&mut self, i: usize) {
+        let pane = &mut self.panes[i];
+        let Some(running) = pane.running.as_mut() else {
+            return;
+        };
+        let limit = running.limit;
+
+        let outcome = loop {
+            match running.stream.try_next() {
+                None => return,
+                Some(Fetch::Batch(batch)) => {
+                    // A new result replaces the previous one once it has
+                    // something to show.
+                    if running.place == Place::Replace && running.rows == 0 {
+                        pane.data.clear();
+                        pane.source = Some(running.sql.clone());
+                    }
+                    let keep = if limit > 0 {
+                        batch.num_rows().min(limit.saturating_sub(running.rows))
+                    } else {
+                        batch.num_rows()
+                    };
+                    running.rows += batch.num_rows();
+                    running.bytes += batch.get_array_memory_size();
+                    if keep > 0 {
+                        let batch = batch.slice(0, keep);
+                        match running.place {
+                            Place::Prepend => running.incoming.push(batch),
+                            _ => pane.data.push(batch),
+                        }
+                    }
+                    let max = self.config.max_result_bytes;
+                    if max > 0 && result_bytes(&pane.data) > max {
+                        break Ok(true);
+                    }
+                }
+                Some(Fetch::Report(_)) => {}
+                Some(Fetch::Done) => break Ok(false),
+                Some(Fetch::Failed(err)) => break Err(err),
+            }
+        };
+
+        let Some(running) = pane.running.take() else {
+            return;
+        };
+        match outcome {
+            // Dropping the stream stops the background thread at its next
+            // batch.
+            Ok(true) => {
+                tracing::warn!(
+                    pane = i,
+                    rows = running.rows,
+                    elapsed = ?running.stream.started.elapsed(),
+                    "query stopped at max_result_bytes"
+                );
+                pane.page = None;
+                self.status = Some(format!(
+                    "Stopped after {} rows, the result is over max_result_bytes ({} bytes). :export <file> writes all of it",
+                    row_count(&pane.data),
+                    self.config.max_result_bytes
+                ));
+                let elapsed = running.stream.started.elapsed();
+                self.record(i, &running.sql, elapsed, None);
+                self.finish(i, &running.sql, running.then);
+            }
+            Ok(false) => {
+                tracing::info!(
+                    pane = i,
+                    rows = running.rows,
+                    elapsed = ?running.stream.started.elapsed(),
+                    "query done"
+                );
+                let held = running.rows.min(limit);
+                match (running.place, pane.page.as_mut()) {
+                    (Place::Prepend, Some(page)) => {
+                        let mut data = running.incoming;
+                        data.append(&mut pane.data);
+                        pane.data = data;
+                        page.start = running.offset;
+                        pane.selected_row += held;
+                        pane.vertical_scroll += held;
+                        pane.selection_anchor = None;
+                        pane.changed_rows.clear();
+                    }
+                    (Place::Append, Some(page)) => {
+                        page.fetched = running.offset + held;
+                        page.more = running.rows > limit;
+                    }
+                    _ => {
+                        if running.rows == 0 {
+                            pane.data.clear();
+                        }
+                        pane.source = Some(running.sql.clone());
+                        pane.page = (limit > 0).then(|| Page {
+                            sql: running.sql.clone(),
+                            start: running.offset,
+                            fetched: running.offset + held,
+                            more: running.rows > limit,
+                        });
+                    }
+                }
+                self.trim_window(i, running.place);
+                // The cache is keyed by the query text alone, so runs whose
+                // rows also depend on a sort, a filter or values bound to
+                // placeholders stay out of it.
+                let pane = &self.panes[i];
+                if pane.sort.is_none() && pane.filters.is_empty() && pane.params.is_empty() {
+                    self.cache.insert(
+                        &running.sql,
+                        CachedResult {
+                            data: pane.data.clone(),
+                            page: pane.page.clone(),
+                        },
+                    );
+                }
+                // Pages read later on belong to the query already recorded.
+                if running.place == Place::Replace {
+                    let elapsed = running.stream.started.elapsed();
+                    self.record(i, &running.sql, elapsed, None);
+                }
+                self.finish(i, &running.sql, running.then);
+            }
+            Err(err) => {
+                tracing::error!(pane = i, sql = %running.sql, error = %err, "query failed");
+                if running.place == Place::Replace {
+                    let elapsed = running.stream.started.elapsed();
+                    self.record(i, &running.sql, elapsed, Some(&err));
+                }
+                self.show_error(i, &running.sql, err);
+            }
+        }
+    }
+
+    /// Reports a failed statement, and points at where it went wrong in the
+    /// editor while that still holds it.
+    fn show_error(&mut self, i: usize, sql: &str, err: String) {
+        let pane = &mut self.panes[i];
+        if pane.input == sql {
+            pane.lints = vec![lint::error(&err, sql)];
+            pane.linted = pane.input.clone();
+        }
+        self.status = Some(err);
+    }
+
+    /// Drops the rows furthest from the page just read once the window is
+    /// over `max_window_rows`. They are read again when paged back to.
+    fn trim_window(&mut self, i: usize, place: Place) {
+        let max = match self.config.max_window_rows {
+            0 => return,
+            max => max.max(self.config.row_limit),
+        };
+        let pane = &mut self.panes[i];
+        let Some(page) = pane.page.as_mut() else {
+            return;
+        };
+        let total = row_count(&pane.data);
+        if total <= max {
+            return;
+        }
+        let excess = total - max;
+        if place == Place::Prepend {
+            pane.data = slice_rows(&pane.data, 0, max);
+            page.fetched = page.start + max;
+            page.more = true;
+        } else {
+            pane.data = slice_rows(&pane.data, excess, max);
+            page.start += excess;
+            pane.selected_row = pane.selected_row.saturating_sub(excess);
+            pane.vertical_scroll = pane.vertical_scroll.saturating_sub(excess);
+        }
+        pane.selection_anchor = None;
+        pane.changed_rows.clear();
+    }
+
+    fn finish(&mut self, i: usize, sql: &str, then: AfterRun) {
+        if i == self.active {
+            self.tutorial_reached(Step::Run);
+        }
+        if self.diff.is_some() && i == self.active {
+            self.refresh_diff();
+        }
+        for plugin in &mut self.plugins {
+            match plugin.transform_result(sql, &self.panes[i].data) {
+                Ok(Some(data)) => {
+                    let pane = &mut self.panes[i];
+                    pane.data = data;
+                    pane.page = None;
+                    pane.source = None;
+                    pane.selected_row = pane
+                        .selected_row
+                        .min(row_count(&pane.data).saturating_sub(1));
+                    pane.vertical_scroll = 0;
+                }
+                Ok(None) => {}
+                Err(err) => self.status = Some(format!("{}: {err}", plugin.name())),
+            }
+        }
+        for plugin in &mut self.plugins {
+            if let Err(err) = plugin.on_result(sql, &self.panes[i].data) {
+                self.status = Some(format!("{}: {err}", plugin.name()));
+            }
+        }
+        if self.inline && matches!(then, AfterRun::ClearInput(_)) {
+            self.print_result(i, sql);
+        }
+        let pane = &mut self.panes[i];
+        match then {
+            AfterRun::Nothing => {}
+            // Keep whatever was typed while the query ran.
+            AfterRun::ClearInput(submitted) if pane.input == submitted => {
+                pane.input.clear();
+                pane.reset_cursor();
+            }
+            AfterRun::ClearInput(_) => {}
+            AfterRun::MarkChanges(previous) => {
+                let current = row_strings(&pane.data).unwrap_or_default();
+                pane.changed_rows = current
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, row)| previous.get(*i) != Some(*row))
+                    .map(|(i, _)| i)
+                    .collect();
+            }
+            AfterRun::RefreshSchema => self.refresh_schema(),
+        }
+    }
+
+    /// Lets the user know a long query in pane `i` finished, unless they
+    /// were looking at it.
+    fn notify_finished(&mut self, i: usize, elapsed: Duration, failed: bool) {
+        let after = self.config.notify_after_secs;
+        if after == 0 || elapsed < Duration::from_secs(after) {
+            return;
+        }
+        if i == self.active && self.focused {
+            return;
+        }
+        let outcome = if failed { "failed" } else { "finished" };
+        self.notifications.push(format!(
+            "Query in pane {} {outcome} after {:.0?}",
+            i + 1,
+            elapsed
+        ));
+    }
+
+    /// Queues the statement and its result for the scrollback, the way a
+    /// REPL prints them.
+    fn print_result(&mut self, i: usize, sql: &str) {
+        match result_text(&self.panes[i]) {
+            Ok(text) => self.printed.push(format!("> {}\n{text}\n", sql.trim())),
+            Err(err) => self.status = Some(err.to_string()),
+        }
+    }
+
+    /// Adds a statement that ran in pane `i` to the audit log and the
+    /// transcript, where they are kept.
+    fn record(&mut self, i: usize, sql: &str, elapsed: Duration, error: Option<&str>) {
+        self.notify_finished(i, elapsed, error.is_some());
+        self.job_history.push(JobEntry {
+            pane: i,
+            sql: sql.to_string(),
+            elapsed,
+            state: match error {
+                Some(_) => JobState::Failed,
+                None => JobState::Done,
+            },
+        });
+        if let Some(audit) = self.audit.as_mut() {
+            let rows = row_count(&self.panes[i].data);
+            if let Err(err) = audit.record(sql, elapsed, rows, error) {
+                self.status = Some(format!("Audit log: {err}"));
+            }
+        }
+        let Some(transcript) = self.transcript.as_mut() else {
+            return;
+        };
+        let text = match error {
+            Some(err) => Err(err.to_string()),
+            None => result_text(&self.panes[i]).map_err(|err| err.to_string()),
+        };
+        let text = text.as_deref().map_err(String::as_str);
+        if let Err(err) = transcript.record(sql, elapsed, text) {
+            self.status = Some(format!("Transcript: {err}"));
+        }
+    }
+
+    fn query(&self, pane: usize, sql: &str) -> Result<Vec<RecordBatch>, String> {
+        let sql = self.interpolated(sql)?;
+        let params = &self.panes[pane].params;
+        db::with_idle(&self.sessions[pane], |db| {
+            let mut stmt = db.prepare(&sql)?;
+            let data = stmt.query_arrow(params_from_iter(params))?.collect();
+            Ok::<_, duckdb::Error>(data)
+        })
+    }
+
+    /// The query being edited, or the last one run when the editor is empty.
+    pub fn current_query(&self) -> Option<String> {
+        let input = &self.pane().input;
+        if input.trim().is_empty() {
+            self.last_query().map(str::to_string)
+        } else {
+            Some(input.clone())
+        }
+    }
+
+    pub fn explain_query(&mut self) {
+        let Some(sql) = self.current_query() else {
+            self.status = Some("No query to explain".to_string());
+            return;
+        };
+        let plan = self
+            .interpolated(&sql)
+            .and_then(|sql| db::with_idle(self.session(), |db| PlanView::explain(db, &sql)));
+        match plan {
+            Ok(plan) => {
+                self.plan = Some(plan);
+                self.input_mode = InputMode::Plan;
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    pub fn close_plan(&mut self) {
+        self.plan = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Runs the current query in the background with profiling, then shows
+    /// where the time went. Its rows are read to the end but not kept, so
+    /// the result shown stays as it was. Its placeholders take the session
+    /// variables, or the values it last ran with.
+    pub fn profile_query(&mut self) {
+        let Some(sql) = self.current_query() else {
+            self.status = Some("No query to profile".to_string());
+            return;
+        };
+        let text = match self.interpolated(&sql) {
+            Ok(text) => text,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        let (numbered, names) = sql::placeholders(&text);
+        let Some(params) = self.known_params(&numbered, &names) else {
+            self.status = Some("Run the query first to give its placeholders values".to_string());
+            return;
+        };
+        let pane = &mut self.panes[self.active];
+        if pane.busy_since().is_some() {
+            self.status = Some("A query is still running in this pane".to_string());
+            return;
+        }
+        tracing::info!(pane = self.active, sql = %sql, "starting profile");
+        let output = ProfileView::output_path();
+        let stream = QueryStream::spawn(Arc::clone(&self.sessions[self.active]), {
+            let output = output.clone();
+            move |db, emit| {
+                ProfileView::run(db, &numbered, &params, &output, |batch| {
+                    emit(Fetch::Batch(batch))
+                })
+            }
+        });
+        pane.job = Some(Job {
+            stream,
+            sql,
+            label: "Profiling".to_string(),
+            rows: 0,
+            report: None,
+            then: JobDone::Profile(output),
+        });
+        self.status = Some("Profiling…".to_string());
+    }
+
+    /// The query with `${NAME}` swapped for environment variables when
+    /// `env_interpolation` is on.
+    fn interpolated(&self, sql: &str) -> Result<String, String> {
+        if self.config.env_interpolation {
+            sql::interpolate_env(sql)
+        } else {
+            Ok(sql.to_string())
+        }
+    }
+
+    /// Values for a query's placeholders from the session variables, or
+    /// else the ones the pane last ran it with.
+    fn known_params(&self, numbered: &str, names: &[String]) -> Option<Vec<Option<String>>> {
+        let pane = self.pane();
+        names
+            .iter()
+            .map(|name| self.variables.get(name).cloned().map(Some))
+            .collect::<Option<Vec<_>>>()
+            .or_else(|| {
+                (pane.last_query.as_deref() == Some(numbered) && pane.params.len() == names.len())
+                    .then(|| pane.params.clone())
+            })
+    }
+
+    /// Runs the current query over and over in the background and reports
+    /// how long it took. Its placeholders take the session variables, or
+    /// the values it last ran with.
+    fn bench_query(&mut self, runs: usize, warmup: usize) {
+        let Some(sql) = self.current_query() else {
+            self.status = Some("No query to benchmark".to_string());
+            return;
+        };
+        if !sql::is_query(&sql) {
+            self.status = Some("Only queries can be benchmarked".to_string());
+            return;
+        }
+        let text = match self.interpolated(&sql) {
+            Ok(text) => text,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        let (numbered, names) = sql::placeholders(&text);
+        let Some(params) = self.known_params(&numbered, &names) else {
+            self.status = Some("Run the query first to give its placeholders values".to_string());
+            return;
+        };
+        let pane = &mut self.panes[self.active];
+        if pane.busy_since().is_some() {
+            self.status = Some("A query is still running in this pane".to_string());
+            return;
+        }
+        tracing::info!(pane = self.active, sql = %sql, runs, warmup, "starting benchmark");
+        let stream =
+            QueryStream::spawn(Arc::clone(&self.sessions[self.active]), move |db, emit| {
+                let bench = Bench::run(db, &numbered, &params, runs, warmup, |done| {
+                    emit(Fetch::Report(format!("run {done} of {}", warmup + runs)))
+                })?;
+                emit(Fetch::Report(bench.summary()));
+                Ok::<_, duckdb::Error>(())
+            });
+        pane.job = Some(Job {
+            stream,
+            sql,
+            label: "Benchmarking".to_string(),
+            rows: 0,
+            report: None,
+            then: JobDone::Report,
+        });
+        self.status = Some("Benchmarking…".to_string());
+    }
+
+    pub fn close_profile(&mut self) {
+        self.profile = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn open_chart(&mut self, kind: ChartKind) {
+        self.chart = Some(kind);
+        self.input_mode = InputMode::Chart;
+    }
+
+    /// Opens a scatter plot of the first two numeric columns.
+    pub fn open_scatter(&mut self) {
+        match chart::numeric_columns(&self.pane().data)[..] {
+            [x, y, ..] => self.open_chart(ChartKind::Scatter { x, y }),
+            _ => self.status = Some("A scatter plot needs two numeric columns".to_string()),
+        }
+    }
+
+    /// Opens a histogram of the selected column, or of the first numeric
+    /// column when the selected one isn't numeric.
+    pub fn open_histogram(&mut self) {
+        let columns = chart::numeric_columns(&self.pane().data);
+        let selected = self.pane().selected_column;
+        let column = columns.iter().find(|&&c| c == selected).or(columns.first());
+        match column {
+            Some(&column) => {
+                self.open_chart(ChartKind::Histogram { column });
+                self.refresh_histogram();
+            }
+            None => self.status = Some("A histogram needs a numeric column".to_string()),
+        }
+    }
+
+    pub fn refresh_histogram(&mut self) {
+        let Some(ChartKind::Histogram { column }) = self.chart else {
+            return;
+        };
+        let Some(query) = self.last_query().map(str::to_string) else {
+            self.status = Some("No query to bucket".to_string());
+            return;
+        };
+        let Some(name) = self
+            .pane()
+            .data
+            .first()
+            .map(|batch| batch.schema().field(column).name().clone())
+        else {
+            return;
+        };
+
+        let histogram = self.interpolated(&query).and_then(|query| {
+            db::with_idle(self.session(), |db| {
+                Histogram::compute(db, &query, &name, self.histogram_bins)
+            })
+        });
+        match histogram {
+            Ok(histogram) => self.histogram = Some(histogram),
+            Err(err) => {
+                self.histogram = None;
+                self.status = Some(err);
+            }
+        }
+    }
+
+    pub fn resize_histogram(&mut self, more: bool) {
+        self.histogram_bins = if more {
+            self.histogram_bins + 1
+        } else {
+            self.histogram_bins.saturating_sub(1).max(1)
+        };
+        self.refresh_histogram();
+    }
+
+    pub fn close_chart(&mut self) {
+        self.chart = None;
+        self.histogram = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Moves the scatter plot's x or y axis, or the histogram's column, to the
+    /// next or previous numeric column.
+    pub fn cycle_chart_column(&mut self, x_axis: bool, forward: bool) {
+        let columns = chart::numeric_columns(&self.pane().data);
+        if columns.is_empty() {
+            return;
+        }
+        let axis = match self.chart.as_mut() {
+            Some(ChartKind::Scatter { x, .. }) if x_axis => x,
+            Some(ChartKind::Scatter { y, .. }) => y,
+            Some(ChartKind::Histogram { column }) if x_axis => column,
+            _ => return,
+        };
+        let current = columns.iter().position(|c| c == axis).unwrap_or(0);
+        let next = if forward {
+            current + 1
+        } else {
+            current + columns.len() - 1
+        };
+        *axis = columns[next % columns.len()];
+        self.refresh_histogram();
+    }
+
+    /// Shows summary statistics for a column of the last result.
+    pub fn open_stats(&mut self, column: usize) {
+        let Some(query) = self.last_query().map(str::to_string) else {
+            self.status = Some("No query to summarize".to_string());
+            return;
+        };
+        let Some(schema) = self.pane().data.first().map(|batch| batch.schema()) else {
+            self.status = Some("No result to summarize".to_string());
+            return;
+        };
+        let Some(field) = schema.fields().get(column) else {
+            return;
+        };
+
+        let numeric = field.data_type().is_numeric();
+        let stats = self.interpolated(&query).and_then(|query| {
+            db::with_idle(self.session(), |db| {
+                ColumnStats::compute(db, &query, column, field.name(), numeric)
+            })
+        });
+        match stats {
+            Ok(stats) => {
+                self.stats = Some(stats);
+                self.input_mode = InputMode::Stats;
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    /// Moves the statistics panel to the next or previous column.
+    pub fn cycle_stats_column(&mut self, forward: bool) {
+        let Some(stats) = self.stats.as_ref() else {
+            return;
+        };
+        let columns = self
+            .pane()
+            .data
+            .first()
+            .map_or(0, |batch| batch.num_columns())
+            .max(1);
+        let next = if forward {
+            stats.column + 1
+        } else {
+            stats.column + columns - 1
+        };
+        self.open_stats(next % columns);
+    }
+
+    pub fn close_stats(&mut self) {
+        self.stats = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn open_command(&mut self) {
+        self.command_input.clear();
+        self.input_mode = InputMode::Command;
+    }
+
+    /// Opens the command line with `goto ` typed in, ready for a row number.
+    pub fn prompt_goto(&mut self) {
+        self.command_input = "goto ".to_string();
+        self.input_mode = InputMode::Command;
+    }
+
+    pub fn run_command(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let input = std::mem::take(&mut self.command_input);
+
+        let parsed = Command::parse(&input);
+        if parsed.is_err() && self.run_plugin_command(&input) {
+            return;
+        }
+        match parsed {
+            Ok(Command::Watch(interval)) => self.start_watch(interval),
+            Ok(Command::Unwatch) => {
+                if let Some(watch) = self.watch.take() {
+                    self.panes[watch.pane].changed_rows.clear();
+                }
+            }
+            Ok(Command::Export(path)) => self.export(&path),
+            Ok(Command::ClearCache) => {
+                self.cache.clear();
+                self.status = Some("Result cache cleared".to_string());
+            }
+            Ok(Command::Goto(row)) => self.goto_row(row),
+            Ok(Command::Pipe { command, table }) => self.pipe_result(command, table),
+            Ok(Command::Attach(kind)) => self.open_remote(kind),
+            Ok(Command::Demo { benchmark, scale }) => self.generate_demo(benchmark, scale),
+            Ok(Command::Bench { runs, warmup }) => self.bench_query(runs, warmup),
+            Ok(Command::Samples(names)) => self.load_samples(&names),
+            Ok(Command::Set { name, value }) => {
+                self.status = Some(format!("{name} = {}", quote_literal(&value)));
+                self.variables.insert(name, value);
+            }
+            Ok(Command::Unset(name)) => {
+                self.status = Some(match self.variables.remove(&name) {
+                    Some(_) => format!("Unset {name}"),
+                    None => format!("No variable named {name}"),
+                });
+            }
+            Ok(Command::Variables) => self.list_variables(),
+            Ok(Command::Format) => self.format_input(),
+            Ok(Command::Settings) => self.open_settings(),
+            Ok(Command::Tutorial(true)) => self.start_tutorial(),
+            Ok(Command::Tutorial(false)) => self.tutorial = None,
+            Ok(Command::Secrets) => self.open_secrets(),
+            Ok(Command::Jobs) => self.open_jobs(),
+            Ok(Command::Pivot) => self.open_pivot(),
+            Ok(Command::Filter(regex)) => self.filter_column(regex),
+            Ok(Command::Template(None)) => self.open_templates(),
+            Ok(Command::Template(Some(name))) => self.use_template(&name),
+            Ok(Command::OpenUrl(url)) => self.open_url(&url),
+            Ok(Command::Register { format, path, name }) => {
+                self.register_table(format, &path, name.as_deref())
+            }
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    /// Registers a plugin, whose hooks run after those of the plugins
+    /// registered before it.
+    pub fn add_plugin(&mut self, plugin: Box<dyn Plugin>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Runs a `:` command added by a plugin, if one added it.
+    fn run_plugin_command(&mut self, input: &str) -> bool {
+        let mut words = input.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        let Some(plugin) = self
+            .plugins
+            .iter_mut()
+            .find(|plugin| plugin.commands().iter().any(|command| command == name))
+        else {
+            return false;
+        };
+        match plugin.run_command(name, &args, &self.panes[self.active].data) {
+            Ok(Output::Status(status)) => self.status = Some(status),
+            Ok(Output::Pane(pane)) => {
+                self.plugin_pane = Some(pane);
+                self.input_mode = InputMode::PluginPane;
+            }
+            Err(err) => self.status = Some(format!("{}: {err}", plugin.name())),
+        }
+        true
+    }
+
+    /// Runs the plugin command bound to `key`, if a plugin bound one.
+    pub fn run_bound_key(&mut self, key: &str) {
+        let command = self.plugins.iter().find_map(|plugin| {
+            plugin
+                .key_bindings()
+                .into_iter()
+                .find(|(bound, _)| bound.eq_ignore_ascii_case(key))
+                .map(|(_, command)| command)
+        });
+        if let Some(command) = command {
+            self.run_plugin_command(&command);
+        }
+    }
+
+    /// Shows or hides the overlay with frame timings and result memory.
+    pub fn toggle_perf(&mut self) {
+        self.perf = match self.perf {
+            Some(_) => None,
+            None => Some(PerfStats::default()),
+        };
+    }
+
+    pub fn close_plugin_pane(&mut self) {
+        self.plugin_pane = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Moves to the next or previous page of the result. Past the rows
+    /// fetched, the next page of the query is read when there is one.
+    pub fn page_result(&mut self, forward: bool) {
+        let pane = self.pane();
+        let size = pane.page_rows.max(1);
+        let (current, pages) = pane.page_position();
+        if !forward {
+            if current > 1 {
+                self.goto_row((current - 2) * size + 1);
+            }
+        } else if current < pages {
+            self.goto_row(current * size + 1);
+        } else if pane.page.as_ref().is_some_and(|page| page.more) {
+            self.fetch_more();
+        }
+    }
+
+    pub fn first_page(&mut self) {
+        self.goto_row(1);
+    }
+
+    /// Moves to the last page of the rows fetched so far.
+    pub fn last_page(&mut self) {
+        let pane = self.pane();
+        let size = pane.page_rows.max(1);
+        let (_, pages) = pane.page_position();
+        let more = pane.page.as_ref().is_some_and(|page| page.more);
+        self.goto_row((pages - 1) * size + 1);
+        if more {
+            self.status = Some("Last page fetched so far, m: fetch more".to_string());
+        }
+    }
+
+    /// Scrolls the result so `row`, counting from 1, is at the top. Rows
+    /// outside the window are read from the query again.
+    fn goto_row(&mut self, row: usize) {
+        let limit = self.config.row_limit;
+        let pane = self.pane_mut();
+        let start = pane.first_row();
+        let total = row_count(&pane.data);
+        if let Some(page) = pane.page.as_ref() {
+            let outside = row <= start || (row > start + total && page.more);
+            if outside && pane.running.is_some() {
+                self.status = Some("Still fetching rows".to_string());
+                return;
+            }
+            if outside {
+                let sql = page.sql.clone();
+                pane.selected_row = 0;
+                pane.vertical_scroll = 0;
+                self.start_page(
+                    self.active,
+                    sql,
+                    row - 1,
+                    limit,
+                    Place::Replace,
+                    AfterRun::Nothing,
+                );
+                return;
+            }
+        }
+        if row > start + total {
+            let more = if pane.page.as_ref().is_some_and(|page| page.more) {
+                ", m: fetch more"
+            } else {
+                ""
+            };
+            self.status = Some(format!("The result has {} rows{more}", start + total));
+            return;
+        }
+        let row = row - start;
+        pane.selected_row = row - 1;
+        pane.vertical_scroll = row - 1;
+    }
+
+    /// Copies the selected cells, or the one under the cursor, to the
+    /// clipboard as TSV or CSV. Values are copied as DuckDB gave them, without
+    /// the display formatting.
+    pub fn copy_selection(&mut self, csv: bool) {
+        let pane = self.pane();
+        let (rows, columns) = pane.selection();
+        let header = grid::header(&pane.data);
+        let columns: Vec<usize> = columns
+            .filter(|&c| c < header.len() && !pane.hidden_columns.contains(&header[c]))
+            .collect();
+        let count = rows.end() - rows.start() + 1;
+        let cells = match grid::rows(&pane.data, *rows.start(), count, &CellFormat::default()) {
+            Ok(cells) => cells,
+            Err(err) => {
+                self.status = Some(err.to_string());
+                return;
+            }
+        };
+        if cells.is_empty() || columns.is_empty() {
+            self.status = Some("Nothing to copy".to_string());
+            return;
+        }
+
+        let separator = if csv { "," } else { "\t" };
+        let mut text = String::new();
+        for row in &cells {
+            let fields: Vec<String> = columns
+                .iter()
+                .map(|&c| {
+                    let cell = row[c].as_deref().unwrap_or_default();
+                    if csv {
+                        csv_field(cell)
+                    } else {
+                        cell.replace(['\t', '\n'], " ")
+                    }
+                })
+                .collect();
+            text.push_str(&fields.join(separator));
+            text.push('\n');
+        }
+
+        self.status = Some(format!("Copied {} × {} cells", cells.len(), columns.len()));
+        self.clipboard = Some(text);
+    }
+
+    /// Starts editing the cell under the cursor, if the result maps onto the
+    /// rows of a table.
+    pub fn edit_cell(&mut self) {
+        let pane = self.pane();
+        let Some(sql) = pane.source.as_deref() else {
+            self.status = Some("This result can't be edited".to_string());
+            return;
+        };
+        let header = grid::header(&pane.data);
+        let (row, column) = (pane.selected_row, pane.selected_column);
+        let found = self
+            .interpolated(sql)
+            .and_then(|sql| db::with_idle(self.session(), |db| Target::find(db, &sql, &header)));
+        let target = match found {
+            Ok(target) => target,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        let row = match grid::rows(&pane.data, row, 1, &CellFormat::default()) {
+            Ok(mut rows) if !rows.is_empty() => rows.remove(0),
+            Ok(_) => return,
+            Err(err) => {
+                self.status = Some(err.to_string());
+                return;
+            }
+        };
+
+        let value = row.get(column).cloned().flatten().unwrap_or_default();
+        self.cell_edit = Some(CellEdit {
+            target,
+            row,
+            column,
+            value,
+        });
+        self.input_mode = InputMode::CellEdit;
+    }
+
+    /// Asks to confirm a `DELETE` of the selected rows, or of the row under
+    /// the cursor, matched by the key of the table the result came from.
+    pub fn delete_rows(&mut self) {
+        let pane = self.pane();
+        let Some(sql) = pane.source.as_deref() else {
+            self.status = Some("Rows can't be deleted from this result".to_string());
+            return;
+        };
+        let header = grid::header(&pane.data);
+        let (rows, _) = pane.selection();
+        let found = self
+            .interpolated(sql)
+            .and_then(|sql| db::with_idle(self.session(), |db| Target::find(db, &sql, &header)));
+        let target = match found {
+            Ok(target) => target,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        let count = rows.end() - rows.start() + 1;
+        match grid::rows(&pane.data, *rows.start(), count, &CellFormat::default()) {
+            Ok(rows) if !rows.is_empty() => match target.delete(&rows) {
+                Ok(sql) => self.confirm(sql),
+                Err(err) => self.status = Some(err),
+            },
+            Ok(_) => self.status = Some("No rows to delete".to_string()),
+            Err(err) => self.status = Some(err.to_string()),
+        }
+    }
+
+    /// Turns the edited cell into an `UPDATE` and asks to confirm it. Typing
+    /// `NULL` sets the cell to NULL.
+    pub fn preview_cell_edit(&mut self) {
+        let Some(edit) = self.cell_edit.take() else {
+            return;
+        };
+        let header = grid::header(&self.pane().data);
+        let value = Some(edit.value.as_str()).filter(|value| !value.eq_ignore_ascii_case("null"));
+        match edit.target.update(&header, &edit.row, edit.column, value) {
+            Ok(sql) => self.confirm(sql),
+            Err(err) => {
+                self.status = Some(err);
+                self.input_mode = InputMode::Normal;
+            }
+        }
+    }
+
+    pub fn cancel_cell_edit(&mut self) {
+        self.cell_edit = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    fn confirm(&mut self, sql: String) {
+        self.pending = Some(sql);
+        self.input_mode = InputMode::Confirm;
+    }
+
+    /// Runs the confirmed statement and reloads the shown result, if that
+    /// came from a query. A failed `INSERT` goes back to its form.
+    pub fn run_pending(&mut self) {
+        let Some(sql) = self.pending.take() else {
+            return;
+        };
+        tracing::info!(sql = %sql, "running change");
+        let changed = db::with_idle(self.session(), |db| db.execute(&sql, []));
+        match changed {
+            Ok(rows) => {
+                tracing::info!(rows, "change done");
+                self.cache.clear();
+                self.status = Some(format!("{rows} rows changed"));
+                if self.insert.take().is_some() {
+                    self.input_mode = InputMode::Browsing;
+                } else {
+                    self.input_mode = InputMode::Normal;
+                }
+                let pane = self.pane_mut();
+                pane.selection_anchor = None;
+                pane.changed_rows.clear();
+                if let Some(source) = pane.source.clone().filter(|source| sql::is_query(source)) {
+                    self.execute(&source, AfterRun::Nothing);
+                }
+            }
+            Err(err) => {
+                tracing::error!(sql = %sql, error = %err, "change failed");
+                self.status = Some(err);
+                self.input_mode = self.after_pending();
+            }
+        }
+    }
+
+    pub fn cancel_pending(&mut self) {
+        self.pending = None;
+        self.input_mode = self.after_pending();
+    }
+
+    /// Where backing out of a confirmation returns to.
+    fn after_pending(&self) -> InputMode {
+        if self.insert.is_some() {
+            InputMode::Inserting
+        } else {
+            InputMode::Normal
+        }
+    }
+
+    /// Pins the current result for diffing, or unpins it.
+    pub fn toggle_pin(&mut self) {
+        if self.pinned.take().is_some() {
+            self.diff = None;
+            self.status = Some("Unpinned result".to_string());
+        } else {
+            self.pinned = Some(self.pane().data.clone());
+            self.status = Some("Pinned result, d: diff later runs against it".to_string());
+        }
+    }
+
+    pub fn toggle_diff(&mut self) {
+        if self.diff.take().is_none() {
+            self.refresh_diff();
+        }
+    }
+
+    fn refresh_diff(&mut self) {
+        let Some(pinned) = self.pinned.as_ref() else {
+            self.diff = None;
+            self.status = Some("Pin a result first with P".to_string());
+            return;
+        };
+        match ResultDiff::new(pinned, &self.panes[self.active].data) {
+            Ok(diff) => self.diff = Some(diff),
+            Err(err) => {
+                self.diff = None;
+                self.status = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Writes the whole result of the last query to a file with `COPY`, in
+    /// the format DuckDB picks from the file extension, while the pane's
+    /// result stays in view.
+    pub fn export(&mut self, path: &str) {
+        let Some(sql) = self.last_query() else {
+            self.status = Some("No query to export".to_string());
+            return;
+        };
+        let sql = format!("COPY {} TO {}", sql::subquery(sql), quote_literal(path));
+        let text = match self.interpolated(&sql) {
+            Ok(text) => text,
+            Err(err) => {
+                self.status = Some(err);
+                return;
+            }
+        };
+        let pane = &mut self.panes[self.active];
+        if pane.busy_since().is_some() {
+            self.status = Some("A query is still running in this pane".to_string());
+            return;
+        }
+        // It runs on the pane's session in the background, so the other
+        // pane stays free for queries meanwhile.
+        tracing::info!(pane = self.active, sql = %sql, "starting job");
+        let label = format!("Exporting to {path}");
+        pane.job = Some(Job {
+            stream: QueryStream::start(
+                Arc::clone(&self.sessions[self.active]),
+                text,
+                pane.params.clone(),
+            ),
+            sql,
+            label: label.clone(),
+            rows: 0,
+            report: None,
+            then: JobDone::Status(format!("Exported to {path}")),
+        });
+        self.status = Some(format!("{label}…"));
+    }
+
+    /// Hands the result to the frontend to pipe into `command`.
+    fn pipe_result(&mut self, command: String, table: bool) {
+        let data = &self.panes[self.active].data;
+        if data.is_empty() {
+            self.status = Some("No result to pipe".to_string());
+            return;
+        }
+        let input = if table {
+            batches_to_table(data)
+                .map(|table| format!("{table}\n").into_bytes())
+                .map_err(|err| err.to_string())
+        } else {
+            to_csv(data)
+        };
+        match input {
+            Ok(input) => self.pipe = Some(Pipe { command, input }),
+            Err(err) => self.status = Some(err),
+        }
+    }
+
+    pub fn start_watch(&mut self, interval: Duration) {
+        if self.last_query().is_none() {
+            self.status = Some("Nothing to watch, run a query first".to_string());
+            return;
+        }
+        self.watch = Some(Watch {
+            interval,
+            next_run: Instant::now() + interval,
+            pane: self.active,
+        });
+    }
+
+    /// How long the event loop may wait for input before the watched query
+    /// is due again.
+    pub fn watch_timeout(&self) -> Option<Duration> {
+        self.watch
+            .as_ref()
+            .map(|watch| watch.next_run.saturating_duration_since(Instant::now()))
+    }
+
+    /// Re-runs the watched query if it is due and marks the rows that
+    /// changed since the previous run.
+    pub fn watch_tick(&mut self) {
+        let Some(watch) = self.watch.as_mut() else {
+            return;
+        };
+        if Instant::now() < watch.next_run {
+            return;
+        }
+        watch.next_run = Instant::now() + watch.interval;
+
+        let i = watch.pane;
+        let pane = &self.panes[i];
+        // Skip a beat rather than pile up runs of a slow query.
+        if pane.running.is_some() {
+            return;
+        }
+        let Some(sql) = pane.last_query.clone() else {
+            return;
+        };
+        let previous = row_strings(&pane.data).unwrap_or_default();
+        self.execute_in(i, &sql, AfterRun::MarkChanges(previous));
+    }
+}
+
+pub fn batches_to_table(data: &[RecordBatch]) -> Result<Table, ArrowError> {
+    let options = FormatOptions::default().with_display_error(true);
+
+    let mut table = Table::new();
+    table.load_preset("||--+-++|    ++++++");
+
+    if data.is_empty() {
+        return Ok(table);
+    }
+
+    let schema = data[0].schema();
+
+    let mut header = Vec::new();
+    for field in schema.fields() {
+        header.push(Cell::new(field.name()));
+    }
+    table.set_header(header);
+
+    for batch in data.iter() {
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|c| ArrayFormatter::try_new(c.as_ref(), &options))
+            .collect::<Result<Vec<_>, ArrowError>>()?;
+
+        for row in 0..batch.num_rows() {
+            let mut cells = Vec::new();
+            for formatter in &formatters {
+                cells.push(Cell::new(formatter.value(row)));
+            }
+            table.add_row(cells);
+        }
+    }
+
+    Ok(table)
+}
+
+/// Loads the scripts listed in the config, each on a session of its own, and
+/// reports the first that failed.
+#[cfg(feature = "scripting")]
+fn load_scripts(config: &Config, db: &Mutex<Connection>) -> (Vec<Box<dyn Plugin>>, Option<String>) {
+    let dir = Config::path().and_then(|path| path.parent().map(Path::to_path_buf));
+    let mut scripts: Vec<Box<dyn Plugin>> = vec![];
+    let mut failed = None;
+    for path in &config.scripts {
+        // Joining keeps absolute paths as they are.
+        let path = dir
+            .as_ref()
+            .map_or_else(|| path.clone(), |dir| dir.join(path));
+        let loaded = db::lock(db)
+            .try_clone()
+            .map_err(|err| err.to_string())
+            .and_then(|db| {
+                db::create_macros(&db, &config.macros)?;
+                Script::load(&path, db)
+            });
+        match loaded {
+            Ok(script) => scripts.push(Box::new(script)),
+            Err(err) => failed = failed.or(Some(format!("Script {}: {err}", path.display()))),
+        }
+    }
+    (scripts, failed)
+}
+
+#[cfg(not(feature = "scripting"))]
+fn load_scripts(
+    config: &Config,
+    _db: &Mutex<Connection>,
+) -> (Vec<Box<dyn Plugin>>, Option<String>) {
+    let status = (!config.scripts.is_empty())
+        .then(|| "Scripts need civciv built with the scripting feature".to_string());
+    (vec![], status)
+}
+
+fn contains(area: Rect, x: u16, y: u16) -> bool {
+    (area.x..area.x + area.width).contains(&x) && (area.y..area.y + area.height).contains(&y)
+}
+
+/// A pane's result as a table followed by its row count.
+fn result_text(pane: &Pane) -> Result<String, ArrowError> {
+    let rows = row_count(&pane.data);
+    let table = batches_to_table(&pane.data)?;
+    let count = match &pane.page {
+        Some(page) if page.more => format!("first {rows} rows"),
+        _ if rows == 1 => "1 row".to_string(),
+        _ => format!("{rows} rows"),
+    };
+    Ok(format!("{table}\n({count})"))
+}
+
+pub fn row_count(data: &[RecordBatch]) -> usize {
+    data.iter().map(|batch| batch.num_rows()).sum()
+}
+
+/// `len` rows of the result from `start`, sharing the batches' buffers.
+fn slice_rows(data: &[RecordBatch], start: usize, len: usize) -> Vec<RecordBatch> {
+    let mut skip = start;
+    let mut left = len;
+    let mut rows = Vec::new();
+    for batch in data {
+        if left == 0 {
+            break;
+        }
+        if skip >= batch.num_rows() {
+            skip -= batch.num_rows();
+            continue;
+        }
+        let take = (batch.num_rows() - skip).min(left);
+        rows.push(batch.slice(skip, take));
+        left -= take;
+        skip = 0;
+    }
+    rows
+}
+
+/// Memory held by the batches' buffers.
+pub fn result_bytes(data: &[RecordBatch]) -> usize {
+    data.iter().map(RecordBatch::get_array_memory_size).sum()
+}
+
+/// The formatted cells of every row of the result, with NULLs left empty.
+fn row_cells(data: &[RecordBatch]) -> Result<Vec<Vec<String>>, ArrowError> {
+    Ok(grid::rows(data, 0, usize::MAX, &CellFormat::default())?
+        .into_iter()
+        .map(|cells| cells.into_iter().map(Option::unwrap_or_default).collect())
+        .collect())
+}
+
+/// Quotes a CSV field when it holds a separator, quote or line break.
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Every row of the result rendered as one string, used to compare runs.
+pub fn row_strings(data: &[RecordBatch]) -> Result<Vec<String>, ArrowError> {
+    Ok(row_cells(data)?
+        .into_iter()
+        .map(|cells| cells.join("\t"))
+        .collect())
 }
@@ -0,0 +1,58 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+use serde::Serialize;
+
+/// Statements run on a database, appended to a file one JSON object per
+/// line as they finish.
+pub struct AuditLog {
+    file: File,
+    database: String,
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    time: String,
+    database: &'a str,
+    sql: &'a str,
+    duration_ms: u128,
+    /// Rows read back, which are only the first page for a paged query.
+    rows: usize,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+impl AuditLog {
+    /// Opens the log at `path` for statements run on `database`.
+    pub fn open(path: &Path, database: String) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file, database })
+    }
+
+    /// Appends a statement that returned `rows` rows or failed with `error`.
+    pub fn record(
+        &mut self,
+        sql: &str,
+        elapsed: Duration,
+        rows: usize,
+        error: Option<&str>,
+    ) -> io::Result<()> {
+        let entry = Entry {
+            time: chrono::Local::now().to_rfc3339(),
+            database: &self.database,
+            sql: sql.trim(),
+            duration_ms: elapsed.as_millis(),
+            rows,
+            success: error.is_none(),
+            error,
+        };
+        serde_json::to_writer(&mut self.file, &entry)?;
+        writeln!(self.file)?;
+        self.file.flush()
+    }
+}
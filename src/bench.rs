@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+use duckdb::{params_from_iter, Connection};
+
+/// Wall times of a query run over and over, for comparing rewrites of it.
+pub struct Bench {
+    /// One per timed run, fastest first.
+    pub times: Vec<Duration>,
+    /// Rows in the result, which every run reads to the end.
+    pub rows: usize,
+}
+
+impl Bench {
+    /// Runs the query `warmup` times untimed, so caches are filled, then
+    /// `runs` times with each run timed from preparing it to its last row.
+    /// `progress` is told how many runs are done after each one, and stops
+    /// the benchmark early by returning false.
+    pub fn run(
+        db: &Connection,
+        sql: &str,
+        params: &[Option<String>],
+        runs: usize,
+        warmup: usize,
+        progress: impl Fn(usize) -> bool,
+    ) -> duckdb::Result<Self> {
+        let mut times = vec![];
+        let mut rows = 0;
+        for i in 0..warmup + runs {
+            let started = Instant::now();
+            let mut stmt = db.prepare(sql)?;
+            rows = stmt
+                .query_arrow(params_from_iter(params))?
+                .map(|batch| batch.num_rows())
+                .sum();
+            if i >= warmup {
+                times.push(started.elapsed());
+            }
+            if !progress(i + 1) {
+                break;
+            }
+        }
+        times.sort();
+        Ok(Self { times, rows })
+    }
+
+    pub fn min(&self) -> Duration {
+        self.percentile(0.0)
+    }
+
+    pub fn median(&self) -> Duration {
+        self.percentile(50.0)
+    }
+
+    pub fn p95(&self) -> Duration {
+        self.percentile(95.0)
+    }
+
+    /// The time at or under which `percent` of the runs finished.
+    fn percentile(&self, percent: f64) -> Duration {
+        let rank = (percent / 100.0 * self.times.len() as f64).ceil() as usize;
+        self.times
+            .get(rank.saturating_sub(1))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Rows read per second at the median time.
+    pub fn rows_per_second(&self) -> f64 {
+        self.rows as f64 / self.median().as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} runs: min {:.1?}, median {:.1?}, p95 {:.1?}, {} rows, {:.0} rows/s",
+            self.times.len(),
+            self.min(),
+            self.median(),
+            self.p95(),
+            self.rows,
+            self.rows_per_second()
+        )
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+
+use arrow::array::RecordBatch;
+
+use crate::{app::result_bytes, pane::Page};
+
+pub struct CachedResult {
+    pub data: Vec<RecordBatch>,
+    pub page: Option<Page>,
+}
+
+/// Results of recent queries by their text, most recently used first. The
+/// oldest entries are evicted once the cached batches go over the limit.
+pub struct ResultCache {
+    entries: VecDeque<(String, CachedResult)>,
+    max_bytes: usize,
+}
+
+impl ResultCache {
+    /// A limit of 0 disables the cache.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_bytes,
+        }
+    }
+
+    pub fn get(&mut self, sql: &str) -> Option<&CachedResult> {
+        let i = self.entries.iter().position(|(key, _)| key == sql)?;
+        let entry = self.entries.remove(i)?;
+        self.entries.push_front(entry);
+        self.entries.front().map(|(_, result)| result)
+    }
+
+    pub fn insert(&mut self, sql: &str, result: CachedResult) {
+        self.entries.retain(|(key, _)| key != sql);
+        if result_bytes(&result.data) > self.max_bytes {
+            return;
+        }
+        self.entries.push_front((sql.to_string(), result));
+
+        while self.bytes() > self.max_bytes {
+            self.entries.pop_back();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Memory held by the cached batches.
+    pub fn bytes(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(_, result)| result_bytes(&result.data))
+            .sum()
+    }
+}
@@ -0,0 +1,282 @@
+use arrow::{
+    array::{ArrayRef, AsArray, RecordBatch},
+    compute::cast,
+    datatypes::{DataType, Float64Type},
+    util::display::{ArrayFormatter, FormatOptions},
+};
+use duckdb::Connection;
+
+use crate::sql::{quote_identifier, subquery};
+
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Line,
+    /// Column indices of the x and y values.
+    Scatter {
+        x: usize,
+        y: usize,
+    },
+    /// Column index of the values to bucket.
+    Histogram {
+        column: usize,
+    },
+}
+
+/// Row counts of equally wide buckets between the column's min and max.
+pub struct Histogram {
+    pub column: String,
+    pub low: f64,
+    pub high: f64,
+    pub counts: Vec<u64>,
+}
+
+impl Histogram {
+    /// Buckets a column of the query's result with a SQL aggregation, so the
+    /// distribution covers every row rather than only the fetched ones.
+    pub fn compute(
+        db: &Connection,
+        query: &str,
+        column: &str,
+        bins: usize,
+    ) -> duckdb::Result<Self> {
+        let query = subquery(query);
+        let column_sql = quote_identifier(column);
+        let sql = format!(
+            "WITH source AS (
+                SELECT {column_sql}::DOUBLE AS v FROM {query} WHERE {column_sql} IS NOT NULL
+            ), bounds AS (
+                SELECT min(v) AS low, max(v) AS high FROM source
+            )
+            SELECT
+                coalesce(least(floor((v - low) / nullif(high - low, 0) * {bins}), {bins} - 1), 0)::BIGINT AS bucket,
+                count(*),
+                any_value(low),
+                any_value(high)
+            FROM source, bounds
+            GROUP BY bucket
+            ORDER BY bucket"
+        );
+
+        let mut histogram = Self {
+            column: column.to_string(),
+            low: 0.0,
+            high: 0.0,
+            counts: vec![0; bins],
+        };
+        let mut stmt = db.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, f64>(3)?,
+            ))
+        })?;
+        for row in rows {
+            let (bucket, count, low, high) = row?;
+            histogram.low = low;
+            histogram.high = high;
+            if let Some(slot) = histogram.counts.get_mut(bucket as usize) {
+                *slot = count as u64;
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// The lower edge of each bucket, for labelling the bars.
+    pub fn bucket_labels(&self) -> Vec<String> {
+        let width = (self.high - self.low) / self.counts.len() as f64;
+        (0..self.counts.len())
+            .map(|i| format_number(self.low + width * i as f64))
+            .collect()
+    }
+}
+
+/// Points and axes ready to be handed to the chart widget.
+pub struct Plot {
+    pub series: Vec<Series>,
+    pub x_bounds: [f64; 2],
+    pub y_bounds: [f64; 2],
+    pub x_labels: Vec<String>,
+}
+
+pub fn is_temporal(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(..)
+    )
+}
+
+/// Builds the line chart, or explains why the result does not fit one.
+pub fn line_chart(data: &[RecordBatch]) -> Result<Plot, String> {
+    let Some(schema) = data.first().map(|batch| batch.schema()) else {
+        return Err("No result to chart".to_string());
+    };
+
+    let fields = schema.fields();
+    if fields.len() < 2 {
+        return Err("A line chart needs a temporal column followed by numeric ones".to_string());
+    }
+    if !is_temporal(fields[0].data_type()) {
+        return Err(format!(
+            "The first column {} is not a date or timestamp",
+            fields[0].name()
+        ));
+    }
+    let value_columns: Vec<usize> = (1..fields.len())
+        .filter(|&i| fields[i].data_type().is_numeric())
+        .collect();
+    if value_columns.is_empty() {
+        return Err("No numeric columns to plot".to_string());
+    }
+
+    let mut series: Vec<Series> = value_columns
+        .iter()
+        .map(|&i| Series {
+            name: fields[i].name().clone(),
+            points: vec![],
+        })
+        .collect();
+
+    // The labels show the formatted temporal values at the left and right
+    // edge of the x axis.
+    let mut first_x: Option<(f64, String)> = None;
+    let mut last_x: Option<(f64, String)> = None;
+    let options = FormatOptions::default();
+
+    for batch in data {
+        let x_column = batch.column(0);
+        let xs = to_f64(&cast(x_column, &DataType::Int64).map_err(|e| e.to_string())?)?;
+        let formatter =
+            ArrayFormatter::try_new(x_column.as_ref(), &options).map_err(|e| e.to_string())?;
+
+        let ys = value_columns
+            .iter()
+            .map(|&i| to_f64(batch.column(i)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for row in 0..batch.num_rows() {
+            let Some(x) = xs[row] else {
+                continue;
+            };
+            if first_x.as_ref().is_none_or(|(min, _)| x < *min) {
+                first_x = Some((x, formatter.value(row).to_string()));
+            }
+            if last_x.as_ref().is_none_or(|(max, _)| x > *max) {
+                last_x = Some((x, formatter.value(row).to_string()));
+            }
+            for (series, ys) in series.iter_mut().zip(&ys) {
+                if let Some(y) = ys[row] {
+                    series.points.push((x, y));
+                }
+            }
+        }
+    }
+
+    let (Some((x_min, x_min_label)), Some((x_max, x_max_label))) = (first_x, last_x) else {
+        return Err("No rows to plot".to_string());
+    };
+
+    for series in &mut series {
+        series.points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    let (y_min, y_max) = series
+        .iter()
+        .flat_map(|series| series.points.iter().map(|(_, y)| *y))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| {
+            (min.min(y), max.max(y))
+        });
+
+    Ok(Plot {
+        series,
+        x_bounds: padded_bounds(x_min, x_max),
+        y_bounds: padded_bounds(y_min, y_max),
+        x_labels: vec![x_min_label, x_max_label],
+    })
+}
+
+/// Indices of the numeric columns of the result.
+pub fn numeric_columns(data: &[RecordBatch]) -> Vec<usize> {
+    data.first().map_or_else(Vec::new, |batch| {
+        let schema = batch.schema();
+        (0..schema.fields().len())
+            .filter(|&i| schema.field(i).data_type().is_numeric())
+            .collect()
+    })
+}
+
+/// Plots one numeric column against another.
+pub fn scatter_plot(data: &[RecordBatch], x: usize, y: usize) -> Result<Plot, String> {
+    let Some(schema) = data.first().map(|batch| batch.schema()) else {
+        return Err("No result to plot".to_string());
+    };
+    if numeric_columns(data).len() < 2 {
+        return Err("A scatter plot needs two numeric columns".to_string());
+    }
+
+    let mut points = Vec::new();
+    for batch in data {
+        let xs = to_f64(batch.column(x))?;
+        let ys = to_f64(batch.column(y))?;
+        points.extend(xs.into_iter().zip(ys).filter_map(|(x, y)| Some((x?, y?))));
+    }
+
+    let bounds = |values: &mut dyn Iterator<Item = f64>| {
+        let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+        padded_bounds(min, max)
+    };
+    let x_bounds = bounds(&mut points.iter().map(|(x, _)| *x));
+    let y_bounds = bounds(&mut points.iter().map(|(_, y)| *y));
+
+    Ok(Plot {
+        series: vec![Series {
+            name: format!("{} × {}", schema.field(x).name(), schema.field(y).name()),
+            points,
+        }],
+        x_bounds,
+        y_bounds,
+        x_labels: axis_labels(x_bounds),
+    })
+}
+
+/// Widens a range so flat series and single points still get an axis.
+pub fn padded_bounds(min: f64, max: f64) -> [f64; 2] {
+    if !min.is_finite() || !max.is_finite() {
+        [0.0, 1.0]
+    } else if min == max {
+        [min - 1.0, max + 1.0]
+    } else {
+        [min, max]
+    }
+}
+
+pub fn to_f64(column: &ArrayRef) -> Result<Vec<Option<f64>>, String> {
+    let floats = cast(column, &DataType::Float64).map_err(|e| e.to_string())?;
+    Ok(floats.as_primitive::<Float64Type>().iter().collect())
+}
+
+pub fn axis_labels(bounds: [f64; 2]) -> Vec<String> {
+    let [min, max] = bounds;
+    [min, (min + max) / 2.0, max]
+        .iter()
+        .map(|v| format_number(*v))
+        .collect()
+}
+
+fn format_number(value: f64) -> String {
+    if value.abs() >= 1e6 || (value != 0.0 && value.abs() < 1e-2) {
+        format!("{value:.2e}")
+    } else if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    }
+}
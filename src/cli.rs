@@ -0,0 +1,62 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::Parser;
+
+use civciv_core::command;
+
+/// A terminal UI for DuckDB.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Database file or `md:` MotherDuck connection string. Opens an in-memory
+    /// database when omitted.
+    pub database: Option<String>,
+
+    /// Open a connection from the config's `[connections]` by name.
+    #[arg(long, value_name = "NAME", conflicts_with = "database")]
+    pub profile: Option<String>,
+
+    /// Threads DuckDB runs queries on.
+    #[arg(long, value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// Most memory DuckDB may use, such as `4GB`.
+    #[arg(long, value_name = "SIZE")]
+    pub memory_limit: Option<String>,
+
+    /// Where DuckDB spills to disk when it runs out of memory.
+    #[arg(long, value_name = "PATH")]
+    pub temp_dir: Option<PathBuf>,
+
+    /// Read environment variables from a `.env` file, `.env` in the working
+    /// directory unless another is given. Variables already set are kept.
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = ".env")]
+    pub env_file: Option<PathBuf>,
+
+    /// Query to run right after startup.
+    #[arg(short, long, value_name = "SQL")]
+    pub execute: Option<String>,
+
+    /// Re-run the startup query on an interval such as `5s` or `1m`.
+    #[arg(long, value_name = "INTERVAL", value_parser = command::parse_duration, requires = "execute")]
+    pub watch: Option<Duration>,
+
+    /// Append a log of the statements run, their timings and errors to this
+    /// file.
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Append every statement run, its timing and its result to this file.
+    #[arg(long, value_name = "PATH")]
+    pub transcript: Option<PathBuf>,
+
+    /// Draw below the shell prompt instead of on the alternate screen, and
+    /// print each submitted query with its table above, where it stays in
+    /// the terminal's scrollback after exiting.
+    #[arg(long)]
+    pub inline: bool,
+
+    /// Also log every key press.
+    #[arg(short, long, requires = "log_file")]
+    pub verbose: bool,
+}
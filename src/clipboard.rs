@@ -0,0 +1,12 @@
+use std::io::{self, Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Puts `text` on the system clipboard through the terminal, using the
+/// OSC 52 escape sequence. This works over SSH too, as long as the terminal
+/// supports it.
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
@@ -0,0 +1,299 @@
+use std::time::Duration;
+
+use crate::{lakehouse::TableFormat, remote::RemoteKind};
+
+/// A benchmark whose data DuckDB can generate.
+#[derive(Clone, Copy)]
+pub enum Benchmark {
+    Tpch,
+    Tpcds,
+}
+
+impl Benchmark {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "tpch" | "tpc-h" => Some(Self::Tpch),
+            "tpcds" | "tpc-ds" => Some(Self::Tpcds),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Tpch => "TPC-H",
+            Self::Tpcds => "TPC-DS",
+        }
+    }
+
+    /// The extension that generates its data.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Tpch => "tpch",
+            Self::Tpcds => "tpcds",
+        }
+    }
+
+    /// The procedure that generates its data.
+    pub fn generator(self) -> &'static str {
+        match self {
+            Self::Tpch => "dbgen",
+            Self::Tpcds => "dsdgen",
+        }
+    }
+}
+
+/// A `:` command typed in the command line.
+pub enum Command {
+    Watch(Duration),
+    Unwatch,
+    /// Write the last query's full result to this file.
+    Export(String),
+    ClearCache,
+    /// Scroll the result to this row, counting from 1.
+    Goto(usize),
+    /// Feed the result to a shell command, as CSV or as an aligned table.
+    Pipe {
+        command: String,
+        table: bool,
+    },
+    /// Open the form that attaches a database server of this kind.
+    Attach(RemoteKind),
+    /// Generate the TPC-H or TPC-DS tables at a scale factor.
+    Demo {
+        benchmark: Benchmark,
+        scale: f64,
+    },
+    /// Time the current query over this many runs, after untimed warmup
+    /// runs.
+    Bench {
+        runs: usize,
+        warmup: usize,
+    },
+    /// Create the bundled sample tables, or only the ones named.
+    Samples(Vec<String>),
+    /// Set a session variable, bound to the `$name` placeholders of the
+    /// queries run after.
+    Set {
+        name: String,
+        value: String,
+    },
+    Unset(String),
+    /// List the session variables.
+    Variables,
+    /// Pretty-print the SQL in the editor.
+    Format,
+    /// Open the panel that lists and changes DuckDB's settings.
+    Settings,
+    /// Start the guided tour, or end it with `false`.
+    Tutorial(bool),
+    /// Open the panel that lists, creates and drops secrets.
+    Secrets,
+    /// Keep the rows whose selected column matches this regex, or all of
+    /// them when it is empty.
+    Filter(String),
+    /// Open the form that builds a `PIVOT` of the result.
+    Pivot,
+    /// List the queries running and lately finished in the panes.
+    Jobs,
+    /// Fill in the named template from the config, or pick one.
+    Template(Option<String>),
+    /// Query a CSV, Parquet or JSON file over HTTP.
+    OpenUrl(String),
+    /// Create a view over an Iceberg or Delta Lake table.
+    Register {
+        format: TableFormat,
+        path: String,
+        name: Option<String>,
+    },
+}
+
+impl Command {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut words = input.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+
+        match (name, args.as_slice()) {
+            ("watch", ["off"]) | ("unwatch", []) => Ok(Command::Unwatch),
+            ("watch", [interval]) => Ok(Command::Watch(parse_duration(interval)?)),
+            ("watch", _) => Err("Usage: watch <interval>, e.g. watch 5s".to_string()),
+            ("cache", ["clear"]) => Ok(Command::ClearCache),
+            ("cache", _) => Err("Usage: cache clear".to_string()),
+            ("goto", [row]) => match row.parse() {
+                Ok(row) if row > 0 => Ok(Command::Goto(row)),
+                _ => Err(format!("Invalid row number: {row}")),
+            },
+            ("goto", _) => Err("Usage: goto <row>, e.g. goto 4817".to_string()),
+            ("export", []) => Err("Usage: export <file>, e.g. export out.parquet".to_string()),
+            ("export", path) => Ok(Command::Export(path.join(" "))),
+            ("pipe", [] | ["-t"]) => {
+                Err("Usage: pipe [-t] <command>, e.g. pipe less -S".to_string())
+            }
+            // The command goes to the shell as typed, quotes and all.
+            ("pipe", [first, ..]) => {
+                let command = input.trim_start()[name.len()..].trim_start();
+                let table = *first == "-t";
+                let command = if table {
+                    command["-t".len()..].trim()
+                } else {
+                    command.trim()
+                };
+                Ok(Command::Pipe {
+                    command: command.to_string(),
+                    table,
+                })
+            }
+            ("attach", [kind]) => RemoteKind::parse(kind)
+                .map(Command::Attach)
+                .ok_or_else(|| format!("Can't attach {kind}, only postgres or mysql")),
+            ("attach", _) => Err("Usage: attach <kind>, e.g. attach postgres".to_string()),
+            ("demo", [benchmark, options @ ..]) if options.len() <= 1 => {
+                let benchmark = Benchmark::parse(benchmark)
+                    .ok_or_else(|| format!("Unknown benchmark: {benchmark}, only tpch or tpcds"))?;
+                let scale = match options.first() {
+                    None => 1.0,
+                    Some(option) => parse_scale(option)?,
+                };
+                Ok(Command::Demo { benchmark, scale })
+            }
+            ("demo", _) => {
+                Err("Usage: demo <tpch|tpcds> [sf=<scale>], e.g. demo tpch sf=1".to_string())
+            }
+            ("bench", []) => Ok(Command::Bench {
+                runs: 10,
+                warmup: 0,
+            }),
+            ("bench", [runs, options @ ..]) if options.len() <= 1 => {
+                let runs = match runs.parse::<usize>() {
+                    Ok(runs) if runs > 0 => runs,
+                    _ => return Err(format!("Invalid number of runs: {runs}")),
+                };
+                let warmup = match options.first() {
+                    None => 0,
+                    Some(option) => parse_warmup(option)?,
+                };
+                Ok(Command::Bench { runs, warmup })
+            }
+            ("bench", _) => Err("Usage: bench [runs] [warmup=<runs>], e.g. bench 10 warmup=2".to_string()),
+            ("samples", names) => Ok(Command::Samples(
+                names.iter().map(|name| name.to_string()).collect(),
+            )),
+            ("set" | "vars", []) => Ok(Command::Variables),
+            ("set", [variable, ..]) => {
+                if !variable.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return Err(format!("Invalid variable name: {variable}"));
+                }
+                // The value is taken as typed, spaces and all.
+                let value = input.trim_start()[name.len()..].trim_start()[variable.len()..].trim();
+                if value.is_empty() {
+                    return Err(format!("Usage: set <name> <value>, e.g. set {variable} 'EU'"));
+                }
+                Ok(Command::Set {
+                    name: variable.to_string(),
+                    value: unquote(value),
+                })
+            }
+            ("unset", [name]) => Ok(Command::Unset(name.to_string())),
+            ("unset", _) => Err("Usage: unset <name>".to_string()),
+            ("format", []) => Ok(Command::Format),
+            ("settings", []) => Ok(Command::Settings),
+            ("tutorial", []) => Ok(Command::Tutorial(true)),
+            ("tutorial", ["off"]) => Ok(Command::Tutorial(false)),
+            ("tutorial", _) => Err("Usage: tutorial [off]".to_string()),
+            ("secrets", []) => Ok(Command::Secrets),
+            ("jobs", []) => Ok(Command::Jobs),
+            ("pivot", []) => Ok(Command::Pivot),
+            // A regex may have spaces in it.
+            ("filter", _) => Ok(Command::Filter(unquote(
+                input.trim_start()[name.len()..].trim(),
+            ))),
+            ("template", []) => Ok(Command::Template(None)),
+            // Names may have spaces in them.
+            ("template", _) => Ok(Command::Template(Some(
+                input.trim_start()[name.len()..].trim().to_string(),
+            ))),
+            ("open", [url]) if url.starts_with("https://") || url.starts_with("http://") => {
+                Ok(Command::OpenUrl(url.to_string()))
+            }
+            ("open", _) => {
+                Err("Usage: open <url>, e.g. open https://example.com/data.parquet".to_string())
+            }
+            ("register", [format, path, name @ ..]) if name.len() <= 1 => {
+                let format = TableFormat::parse(format)
+                    .ok_or_else(|| format!("Unknown table format: {format}"))?;
+                Ok(Command::Register {
+                    format,
+                    path: path.to_string(),
+                    name: name.first().map(|name| name.to_string()),
+                })
+            }
+            ("register", _) => Err(
+                "Usage: register <iceberg|delta> <path> [name], e.g. register delta s3://bucket/events"
+                    .to_string(),
+            ),
+            ("", _) => Err("Empty command".to_string()),
+            _ => Err(format!("Unknown command: {name}")),
+        }
+    }
+}
+
+/// A value as typed after `set`, with the quotes around a string literal
+/// taken off.
+fn unquote(value: &str) -> String {
+    match value
+        .strip_prefix('\'')
+        .and_then(|value| value.strip_suffix('\''))
+    {
+        Some(inner) => inner.replace("''", "'"),
+        None => value.to_string(),
+    }
+}
+
+/// Parses a scale factor such as `sf=0.1`.
+fn parse_scale(input: &str) -> Result<f64, String> {
+    match input.strip_prefix("sf=").map(str::parse::<f64>) {
+        Some(Ok(scale)) if scale > 0.0 => Ok(scale),
+        _ => Err(format!("Invalid scale factor: {input}, e.g. sf=0.1")),
+    }
+}
+
+/// Parses a number of warmup runs such as `warmup=2`.
+fn parse_warmup(input: &str) -> Result<usize, String> {
+    match input.strip_prefix("warmup=").map(str::parse::<usize>) {
+        Some(Ok(warmup)) => Ok(warmup),
+        _ => Err(format!("Invalid warmup: {input}, e.g. warmup=2")),
+    }
+}
+
+/// Shortest interval a query can be watched at, so it doesn't re-run on
+/// every pass of the event loop.
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Parses intervals such as `500ms`, `5s`, `2m` or a bare number of seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("Invalid interval: {input}"))?;
+    let seconds = match unit {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(format!("Invalid interval unit: {unit}")),
+    };
+
+    let interval =
+        Duration::try_from_secs_f64(seconds).map_err(|_| format!("Invalid interval: {input}"))?;
+    if interval < MIN_INTERVAL {
+        return Err(format!(
+            "Interval must be at least {}ms",
+            MIN_INTERVAL.as_millis()
+        ));
+    }
+    Ok(interval)
+}
@@ -0,0 +1,99 @@
+use std::{fs, path::PathBuf};
+
+/// Words that take a file path as the string literal right after them.
+const PATH_CONTEXTS: &[&str] = &[
+    "read_csv",
+    "read_csv_auto",
+    "read_parquet",
+    "parquet_scan",
+    "read_json",
+    "read_json_auto",
+    "read_ndjson",
+    "read_text",
+    "read_blob",
+    "attach",
+    "from",
+    "to",
+];
+
+/// If `cursor` is inside a string literal that holds a file path, returns the
+/// byte offset where the literal's contents start.
+pub fn path_literal_start(input: &str, cursor: usize) -> Option<usize> {
+    let mut start = None;
+    for (i, c) in input[..cursor].char_indices() {
+        if c == '\'' {
+            // A doubled quote closes and immediately reopens the literal, so
+            // it does not need special handling here.
+            start = match start {
+                Some(_) => None,
+                None => Some(i + 1),
+            };
+        }
+    }
+    let start = start?;
+
+    let before = input[..start - 1]
+        .trim_end()
+        .trim_end_matches('(')
+        .trim_end();
+    let word = before
+        .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or_default();
+
+    PATH_CONTEXTS
+        .contains(&word.to_ascii_lowercase().as_str())
+        .then_some(start)
+}
+
+/// Lists the filesystem entries matching a partial path, with directories
+/// suffixed by `/`.
+pub fn complete_path(prefix: &str) -> Vec<String> {
+    let (dir, file_prefix) = match prefix.rfind('/') {
+        Some(i) => (&prefix[..=i], &prefix[i + 1..]),
+        None => ("", prefix),
+    };
+
+    let lookup_dir = match dir.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map_or_else(|| PathBuf::from(dir), |home| home.join(rest)),
+        None if dir.is_empty() => PathBuf::from("."),
+        None => PathBuf::from(dir),
+    };
+
+    let Ok(entries) = fs::read_dir(lookup_dir) else {
+        return vec![];
+    };
+
+    let mut candidates: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(file_prefix) || (name.starts_with('.') && file_prefix.is_empty()) {
+                return None;
+            }
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            Some(format!("{dir}{name}{}", if is_dir { "/" } else { "" }))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// The longest prefix shared by all candidates.
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+    first[..len].to_string()
+}
@@ -0,0 +1,190 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{plugin::PluginConfig, remote::RemoteKind};
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Token used for `md:` connection strings when the `motherduck_token`
+    /// environment variable is not set.
+    pub motherduck_token: Option<String>,
+    /// Number of buckets histograms start with.
+    pub histogram_bins: usize,
+    /// Rows fetched per page for interactive queries, or 0 to fetch
+    /// everything at once.
+    pub row_limit: usize,
+    /// Stop fetching once the result holds this many bytes, or 0 for no cap.
+    pub max_result_bytes: usize,
+    /// Rows held while paging through a result. Rows furthest from the
+    /// latest page are dropped past this and read again when paged back
+    /// to. 0 keeps every row fetched.
+    pub max_window_rows: usize,
+    /// Memory kept for results of recent queries, or 0 to disable the cache.
+    pub result_cache_bytes: usize,
+    /// Rows shown when a table is selected in the schema browser, or 0 to
+    /// not preview tables.
+    pub preview_rows: usize,
+    /// Widest a column is sized automatically; longer values are cut off
+    /// with `…` unless their column wraps. 0 sizes columns to fit.
+    pub max_cell_width: usize,
+    /// Shown, dimmed, for NULL cells so they stand apart from empty strings.
+    pub null_display: String,
+    /// Whether the result header starts out showing column types.
+    pub show_column_types: bool,
+    /// Whether the result grid starts out with a row number gutter.
+    pub show_row_numbers: bool,
+    /// Whether typing a bracket or quote in the editor adds its closer too.
+    pub autoclose: bool,
+    /// Whether SQL keywords in the editor are uppercased as they are typed.
+    /// Formatting always uppercases them.
+    pub uppercase_keywords: bool,
+    /// Whether `${NAME}` in a statement is replaced with the environment
+    /// variable `NAME` when it runs. The history keeps it as typed.
+    pub env_interpolation: bool,
+    /// Queries that take at least this many seconds say so when they finish
+    /// in a pane that isn't active, or while the terminal is in the
+    /// background. 0 never does.
+    pub notify_after_secs: u64,
+    /// How a long query says it finished.
+    pub notify: Notify,
+    /// How values are written in the result grid, under `[format]`.
+    pub format: CellFormat,
+    /// Macros created in every session, under `[macros]`: each key is a
+    /// name with its parameters, such as `clean_phone(p)`, and each value
+    /// the expression it stands for, or `TABLE SELECT ...` for table
+    /// macros.
+    pub macros: BTreeMap<String, String>,
+    /// Programs hooked into civciv, each under `[[plugins]]`.
+    pub plugins: Vec<PluginConfig>,
+    /// Rhai scripts run at startup, relative to the config file's folder
+    /// unless absolute. Only read when civciv is built with the `scripting`
+    /// feature.
+    pub scripts: Vec<PathBuf>,
+    /// JSON Lines files every statement is logged to, under `[audit_logs]`.
+    /// Each key is a database as given on the command line, `:memory:` for
+    /// the in-memory one or `*` for any other, and each value a file
+    /// relative to the config file's folder unless absolute.
+    pub audit_logs: BTreeMap<String, PathBuf>,
+    /// Databases opened by name with `--profile` or from the connection
+    /// switcher, each under `[connections.<name>]`.
+    pub connections: BTreeMap<String, ConnectionProfile>,
+    /// Queries filled in with `:template`, under `[templates]`: each key is
+    /// a name and each value SQL whose `$name` placeholders are asked for
+    /// and written in, leaving the query in the editor.
+    pub templates: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ConnectionProfile {
+    /// Database file or `md:` connection string, or in-memory when unset.
+    /// For a database server, its connection string.
+    pub path: Option<String>,
+    /// `postgres` or `mysql` for a database server, which is attached under
+    /// the profile's name.
+    #[serde(rename = "type")]
+    pub kind: Option<RemoteKind>,
+    /// Opens the database without allowing changes to it.
+    pub read_only: bool,
+    /// DuckDB settings, such as `threads = "4"` or `memory_limit = "2GB"`.
+    pub options: BTreeMap<String, String>,
+    /// Statements run once the database is open, such as `LOAD spatial`.
+    pub init_sql: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Notify {
+    /// Rings the terminal bell.
+    #[default]
+    Bell,
+    /// Asks the terminal for a desktop notification with OSC 9.
+    Desktop,
+    Both,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CellFormat {
+    /// Put between groups of three digits, such as `,` or `_`. Empty leaves
+    /// numbers ungrouped.
+    pub thousands_separator: String,
+    /// Digits after the point for floats and decimals, or as many as needed
+    /// when unset.
+    pub decimals: Option<usize>,
+    /// Numbers at least this large are written in scientific notation, or
+    /// never when 0.
+    pub scientific_above: f64,
+    /// `strftime` pattern for timestamps, such as `%Y-%m-%d %H:%M:%S`.
+    pub timestamp_format: Option<String>,
+    /// `strftime` pattern for dates.
+    pub date_format: Option<String>,
+    /// Zone timestamps with a time zone are shown in: `local`, `UTC` or an
+    /// offset like `+02:00`. Zone names such as `Europe/Berlin` aren't
+    /// supported and, like leaving it unset, keep the zone the value
+    /// carries.
+    pub timezone: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            motherduck_token: None,
+            histogram_bins: 20,
+            row_limit: 1000,
+            max_result_bytes: 256 * 1024 * 1024,
+            max_window_rows: 10_000,
+            result_cache_bytes: 64 * 1024 * 1024,
+            preview_rows: 50,
+            max_cell_width: 40,
+            null_display: "∅".to_string(),
+            show_column_types: false,
+            show_row_numbers: false,
+            autoclose: true,
+            uppercase_keywords: false,
+            env_interpolation: false,
+            notify_after_secs: 30,
+            notify: Notify::default(),
+            format: CellFormat::default(),
+            macros: BTreeMap::new(),
+            plugins: vec![],
+            scripts: vec![],
+            audit_logs: BTreeMap::new(),
+            connections: BTreeMap::new(),
+            templates: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("civciv").join("config.toml"))
+    }
+
+    /// The audit log for statements run on `database`, or the in-memory
+    /// database when `None`.
+    pub fn audit_log(&self, database: Option<&str>) -> Option<PathBuf> {
+        let path = self
+            .audit_logs
+            .get(database.unwrap_or(":memory:"))
+            .or_else(|| self.audit_logs.get("*"))?;
+        let dir = Self::path().and_then(|path| path.parent().map(PathBuf::from));
+        // Joining keeps absolute paths as they are.
+        Some(dir.map_or_else(|| path.clone(), |dir| dir.join(path)))
+    }
+
+    /// Loads the config file, falling back to the defaults when it is missing.
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::File,
+    io::Read,
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError, TryLockError},
+};
+
+use duckdb::{AccessMode, Connection};
+
+use crate::{
+    config::{Config, ConnectionProfile},
+    remote,
+    sql::{quote_identifier, quote_literal},
+};
+
+const MOTHERDUCK_PREFIX: &str = "md:";
+
+/// How every SQLite database file starts.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+pub fn is_motherduck(target: &str) -> bool {
+    target.starts_with(MOTHERDUCK_PREFIX)
+}
+
+/// Whether the file is a SQLite database, going by its header rather than
+/// its extension, since `.db` is used for both.
+pub fn is_sqlite(path: &str) -> bool {
+    let mut header = [0; 16];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| &header == SQLITE_HEADER)
+}
+
+/// Locks the connection shared with the query thread. A panic on that thread
+/// leaves the connection itself intact, so poisoning is ignored.
+pub fn lock(db: &Mutex<Connection>) -> MutexGuard<'_, Connection> {
+    db.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs `f` on a pane's session from the UI thread, failing right away
+/// instead of freezing the screen while that pane's query is still running.
+pub fn with_idle<T, E: ToString>(
+    db: &Mutex<Connection>,
+    f: impl FnOnce(&Connection) -> Result<T, E>,
+) -> Result<T, String> {
+    let db = match db.try_lock() {
+        Ok(db) => db,
+        Err(TryLockError::Poisoned(err)) => err.into_inner(),
+        Err(TryLockError::WouldBlock) => {
+            return Err("A query is still running in this pane".to_string())
+        }
+    };
+    f(&db).map_err(|err| err.to_string())
+}
+
+/// Opens the database named on the command line, or an in-memory one. A
+/// SQLite file is attached to an in-memory database instead.
+pub fn open(target: Option<&str>, config: &Config) -> duckdb::Result<Connection> {
+    match target {
+        None => Connection::open_in_memory(),
+        Some(path) if is_sqlite(path) => {
+            let db = Connection::open_in_memory()?;
+            attach(&db, path, false, config)?;
+            Ok(db)
+        }
+        Some(target) if is_motherduck(target) => {
+            Connection::open(motherduck_connection_string(target, config))
+        }
+        Some(path) => Connection::open(path),
+    }
+}
+
+/// Opens a connection profile's database with its settings and runs its
+/// init statements. A database server is attached under the profile's
+/// `name`.
+pub fn open_profile(
+    name: &str,
+    profile: &ConnectionProfile,
+    config: &Config,
+) -> duckdb::Result<Connection> {
+    let mut flags = duckdb::Config::default();
+    for (key, value) in &profile.options {
+        flags = flags.with(key, value)?;
+    }
+    // An in-memory database can't be read-only, so what is attached to one
+    // is instead.
+    let read_only = profile.read_only;
+    let db = match (profile.kind, profile.path.as_deref()) {
+        (Some(kind), connection) => {
+            let db = Connection::open_in_memory_with_flags(flags)?;
+            remote::attach(&db, kind, connection.unwrap_or_default(), name, read_only)?;
+            db.execute_batch(&format!("USE {}", quote_identifier(name)))?;
+            db
+        }
+        (None, None) => Connection::open_in_memory_with_flags(flags)?,
+        (None, Some(path)) if is_sqlite(path) => {
+            let db = Connection::open_in_memory_with_flags(flags)?;
+            attach(&db, path, read_only, config)?;
+            db
+        }
+        (None, Some(target)) => {
+            if read_only {
+                flags = flags.access_mode(AccessMode::ReadOnly)?;
+            }
+            let target = if is_motherduck(target) {
+                motherduck_connection_string(target, config)
+            } else {
+                target.to_string()
+            };
+            Connection::open_with_flags(target, flags)?
+        }
+    };
+    if let Some(sql) = &profile.init_sql {
+        db.execute_batch(sql)?;
+    }
+    Ok(db)
+}
+
+/// Another session on the same database, using the same default catalog.
+pub fn clone_session(db: &Connection) -> duckdb::Result<Connection> {
+    let clone = db.try_clone()?;
+    let current: String = db.query_row("SELECT current_database()", [], |row| row.get(0))?;
+    clone.execute_batch(&format!("USE {}", quote_identifier(&current)))?;
+    Ok(clone)
+}
+
+/// Creates the macros from the config on a session. They are temporary, so
+/// the database file is left untouched and every session needs its own.
+pub fn create_macros(db: &Connection, macros: &BTreeMap<String, String>) -> Result<(), String> {
+    for (signature, body) in macros {
+        let signature = if signature.contains('(') {
+            signature.clone()
+        } else {
+            format!("{signature}()")
+        };
+        db.execute_batch(&format!(
+            "CREATE OR REPLACE TEMP MACRO {signature} AS {body}"
+        ))
+        .map_err(|err| format!("Macro {signature}: {err}"))?;
+    }
+    Ok(())
+}
+
+/// The MotherDuck extension picks the token up from the environment on its
+/// own, so the configured token is only passed along when that is missing.
+fn motherduck_connection_string(target: &str, config: &Config) -> String {
+    let token_in_env = ["motherduck_token", "MOTHERDUCK_TOKEN"]
+        .iter()
+        .any(|name| env::var_os(name).is_some());
+
+    match &config.motherduck_token {
+        Some(token) if !token_in_env && !target.contains("motherduck_token=") => {
+            let separator = if target.contains('?') { '&' } else { '?' };
+            format!("{target}{separator}motherduck_token={token}")
+        }
+        _ => target.to_string(),
+    }
+}
+
+/// Attaches a database file or `md:` database to the open connection, unless
+/// it already is, and makes it the default catalog. SQLite files are read
+/// through the sqlite extension, installed on first use. Returns its alias.
+pub fn attach(
+    db: &Connection,
+    target: &str,
+    read_only: bool,
+    config: &Config,
+) -> duckdb::Result<String> {
+    let attached: Option<String> = db
+        .query_row(
+            "SELECT database_name FROM duckdb_databases() WHERE path = ?",
+            [target],
+            |row| row.get(0),
+        )
+        .ok();
+
+    let alias = match attached {
+        Some(alias) => alias,
+        None => {
+            let alias = attach_alias(target);
+            let mut options = vec![];
+            if is_sqlite(target) {
+                db.execute_batch("INSTALL sqlite; LOAD sqlite;")?;
+                options.push("TYPE SQLITE");
+            }
+            if read_only {
+                options.push("READ_ONLY");
+            }
+            let target = if is_motherduck(target) {
+                motherduck_connection_string(target, config)
+            } else {
+                target.to_string()
+            };
+            let options = if options.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", options.join(", "))
+            };
+            db.execute_batch(&format!(
+                "ATTACH {} AS {}{options}",
+                quote_literal(&target),
+                quote_identifier(&alias),
+            ))?;
+            alias
+        }
+    };
+
+    db.execute_batch(&format!("USE {}", quote_identifier(&alias)))?;
+    Ok(alias)
+}
+
+fn attach_alias(target: &str) -> String {
+    let name = match target.strip_prefix(MOTHERDUCK_PREFIX) {
+        Some(rest) => rest.split('?').next().unwrap_or_default(),
+        None => Path::new(target)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default(),
+    };
+
+    if name.is_empty() {
+        "attached".to_string()
+    } else {
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use arrow::{array::RecordBatch, error::ArrowError};
+
+use crate::{config::CellFormat, grid};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+pub struct DiffRow {
+    pub kind: DiffKind,
+    /// The row's values, with `None` for NULL.
+    pub cells: Vec<Option<String>>,
+    /// The pinned values of a changed row.
+    pub before: Option<Vec<Option<String>>>,
+}
+
+/// Differences between a pinned result and the current one. Identical rows
+/// match regardless of their order; the remaining rows are paired up by
+/// their first column to tell changed rows from added and removed ones.
+pub struct ResultDiff {
+    pub header: Vec<String>,
+    pub rows: Vec<DiffRow>,
+    pub unchanged: usize,
+    /// Whether the two results have different columns.
+    pub columns_differ: bool,
+}
+
+impl ResultDiff {
+    pub fn new(pinned: &[RecordBatch], current: &[RecordBatch]) -> Result<Self, ArrowError> {
+        let format = CellFormat::default();
+        let old = grid::rows(pinned, 0, usize::MAX, &format)?;
+        let new = grid::rows(current, 0, usize::MAX, &format)?;
+
+        let mut counts: HashMap<&[Option<String>], usize> = HashMap::new();
+        for row in &old {
+            *counts.entry(row.as_slice()).or_default() += 1;
+        }
+        let mut unchanged = 0;
+        let mut unmatched_new = Vec::new();
+        for row in &new {
+            match counts.get_mut(row.as_slice()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    unchanged += 1;
+                }
+                _ => unmatched_new.push(row),
+            }
+        }
+        let mut unmatched_old: Vec<Option<&Vec<Option<String>>>> = Vec::new();
+        for row in &old {
+            if let Some(count) = counts.get_mut(row.as_slice()).filter(|count| **count > 0) {
+                *count -= 1;
+                unmatched_old.push(Some(row));
+            }
+        }
+
+        let mut rows = Vec::new();
+        for row in unmatched_new {
+            let before = unmatched_old
+                .iter_mut()
+                .find(|old| old.is_some_and(|old| old.first() == row.first()))
+                .and_then(Option::take);
+            rows.push(DiffRow {
+                kind: if before.is_some() {
+                    DiffKind::Changed
+                } else {
+                    DiffKind::Added
+                },
+                cells: row.clone(),
+                before: before.cloned(),
+            });
+        }
+        rows.extend(unmatched_old.into_iter().flatten().map(|row| DiffRow {
+            kind: DiffKind::Removed,
+            cells: row.clone(),
+            before: None,
+        }));
+
+        let (pinned_header, current_header) = (grid::header(pinned), grid::header(current));
+        Ok(Self {
+            columns_differ: pinned_header != current_header,
+            header: current_header,
+            rows,
+            unchanged,
+        })
+    }
+
+    pub fn count(&self, kind: DiffKind) -> usize {
+        self.rows.iter().filter(|row| row.kind == kind).count()
+    }
+}
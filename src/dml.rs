@@ -0,0 +1,321 @@
+use duckdb::Connection;
+
+use crate::sql::{quote_identifier, quote_literal};
+
+/// Words after `FROM` that break the one to one mapping between result rows
+/// and table rows.
+const UNMAPPABLE: &[&str] = &[
+    "join",
+    "group",
+    "having",
+    "union",
+    "intersect",
+    "except",
+    "qualify",
+    "window",
+    "pivot",
+    "unpivot",
+    "using",
+    "sample",
+];
+
+/// The table a result was read from, along with the key that identifies its
+/// rows.
+pub struct Target {
+    /// The table as the query names it.
+    table: String,
+    /// Names of the key columns and their positions in the result.
+    key: Vec<(String, usize)>,
+}
+
+/// A cell being edited, before its `UPDATE` is previewed.
+pub struct CellEdit {
+    pub target: Target,
+    pub row: Vec<Option<String>>,
+    pub column: usize,
+    pub value: String,
+}
+
+impl Target {
+    /// Works out which table `sql` reads. Only a plain `SELECT` of columns
+    /// from one table qualifies, and the table needs a primary key or unique
+    /// constraint whose columns are all in `header`.
+    pub fn find(db: &Connection, sql: &str, header: &[String]) -> Result<Self, String> {
+        let table = source_table(sql).ok_or_else(|| {
+            "Only results of a plain SELECT from one table can be changed".to_string()
+        })?;
+
+        let mut parts = table.rsplitn(3, '.').map(unquote);
+        let name = parts.next().unwrap_or_default();
+        let schema = parts
+            .next()
+            .map_or("lower(current_schema())".to_string(), |schema| {
+                format!("lower({})", quote_literal(&schema))
+            });
+        let database = parts
+            .next()
+            .map_or("lower(current_database())".to_string(), |database| {
+                format!("lower({})", quote_literal(&database))
+            });
+        let mut stmt = db
+            .prepare(&format!(
+                "SELECT constraint_index, unnest(constraint_column_names)
+                 FROM duckdb_constraints()
+                 WHERE constraint_type IN ('PRIMARY KEY', 'UNIQUE')
+                   AND lower(table_name) = lower({})
+                   AND lower(schema_name) = {schema}
+                   AND lower(database_name) = {database}
+                 ORDER BY constraint_type <> 'PRIMARY KEY', constraint_index",
+                quote_literal(&name)
+            ))
+            .map_err(|err| err.to_string())?;
+        let columns = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .and_then(Iterator::collect::<duckdb::Result<Vec<_>>>)
+            .map_err(|err| err.to_string())?;
+
+        let mut constraints: Vec<(i64, Vec<String>)> = vec![];
+        for (index, column) in columns {
+            match constraints.last_mut() {
+                Some((last, key)) if *last == index => key.push(column),
+                _ => constraints.push((index, vec![column])),
+            }
+        }
+        let key = constraints
+            .into_iter()
+            .find_map(|(_, key)| {
+                key.into_iter()
+                    .map(|column| {
+                        let position = header
+                            .iter()
+                            .position(|name| name.eq_ignore_ascii_case(&column))?;
+                        Some((column, position))
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| {
+                format!("{table} has no primary key or unique columns in this result")
+            })?;
+
+        Ok(Self { table, key })
+    }
+
+    pub fn update(
+        &self,
+        header: &[String],
+        row: &[Option<String>],
+        column: usize,
+        value: Option<&str>,
+    ) -> Result<String, String> {
+        Ok(format!(
+            "UPDATE {} SET {} = {} WHERE {}",
+            self.table,
+            quote_identifier(&header[column]),
+            value.map_or("NULL".to_string(), quote_literal),
+            self.key_condition(row)?
+        ))
+    }
+
+    pub fn delete(&self, rows: &[Vec<Option<String>>]) -> Result<String, String> {
+        let conditions = rows
+            .iter()
+            .map(|row| Ok(format!("({})", self.key_condition(row)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(format!(
+            "DELETE FROM {} WHERE {}",
+            self.table,
+            conditions.join(" OR ")
+        ))
+    }
+
+    /// Matches `row` by its key. Values are compared as text and left to
+    /// DuckDB to cast to the column types. A unique key allows several rows
+    /// with NULL in it, so such a row can't be told apart and is refused.
+    fn key_condition(&self, row: &[Option<String>]) -> Result<String, String> {
+        let conditions = self
+            .key
+            .iter()
+            .map(|(name, position)| match &row[*position] {
+                Some(value) => Ok(format!(
+                    "{} = {}",
+                    quote_identifier(name),
+                    quote_literal(value)
+                )),
+                None => Err(format!("{name} is NULL, so the row can't be identified")),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(conditions.join(" AND "))
+    }
+}
+
+/// The table named by a query of the form `SELECT <columns> FROM <table>
+/// [WHERE ...] [ORDER BY ...] [LIMIT ...]`, where the columns are `*` or
+/// plain names.
+fn source_table(sql: &str) -> Option<String> {
+    let sql: Vec<&str> = sql
+        .lines()
+        .map(|line| line.split_once("--").map_or(line, |(code, _)| code))
+        .collect();
+    let sql = sql.join("\n");
+    let sql = sql.trim().trim_end_matches(';');
+    let words = words(sql);
+    if !words.first()?.eq_ignore_ascii_case("select") {
+        return None;
+    }
+    let from = words
+        .iter()
+        .position(|word| word.eq_ignore_ascii_case("from"))?;
+
+    let is_name = |word: &str| {
+        let mut quoted = false;
+        word.starts_with(|c: char| !c.is_ascii_digit())
+            && word.chars().all(|c| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                quoted || c.is_alphanumeric() || matches!(c, '_' | '"' | '.')
+            })
+    };
+    let columns = words[1..from].join(" ");
+    if !columns
+        .split(',')
+        .map(str::trim)
+        .all(|column| column == "*" || is_name(column))
+    {
+        return None;
+    }
+
+    let table = words.get(from + 1)?;
+    let rest = &words[from + 2..];
+    let maps_to_rows = !rest.first().is_some_and(|word| word.starts_with(','))
+        && rest.iter().all(|word| {
+            let word = word
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase();
+            !UNMAPPABLE.contains(&word.as_str())
+        });
+    (is_name(table) && maps_to_rows).then(|| table.to_string())
+}
+
+/// Splits `sql` at whitespace outside double quoted names.
+fn words(sql: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut start = None;
+    let mut quoted = false;
+    for (i, c) in sql.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        if c.is_whitespace() && !quoted {
+            if let Some(start) = start.take() {
+                words.push(&sql[start..i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    words.extend(start.map(|start| &sql[start..]));
+    words
+}
+
+fn unquote(name: &str) -> String {
+    match name
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+    {
+        Some(name) => name.replace("\"\"", "\""),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(key: &[(&str, usize)]) -> Target {
+        Target {
+            table: "t".to_string(),
+            key: key
+                .iter()
+                .map(|(name, position)| (name.to_string(), *position))
+                .collect(),
+        }
+    }
+
+    fn row(cells: &[Option<&str>]) -> Vec<Option<String>> {
+        cells.iter().map(|cell| cell.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn source_table_accepts_plain_selects() {
+        for (sql, table) in [
+            ("SELECT * FROM t", "t"),
+            ("select a, b from t where a > 1 order by b limit 10;", "t"),
+            ("SELECT \"a b\" FROM main.t", "main.t"),
+            ("SELECT * FROM t -- join them later", "t"),
+            ("SELECT *\nFROM t -- note\nWHERE a = 1", "t"),
+        ] {
+            assert_eq!(source_table(sql).as_deref(), Some(table), "{sql}");
+        }
+    }
+
+    #[test]
+    fn source_table_rejects_other_shapes() {
+        for sql in [
+            "SELECT * FROM t JOIN u USING (id)",
+            "SELECT * FROM t, u",
+            "SELECT a, count(*) FROM t GROUP BY a",
+            "SELECT *, 1 FROM t",
+            "SELECT a AS b FROM t",
+            "SELECT a + 1 FROM t",
+            "SELECT * FROM (SELECT * FROM t)",
+            "WITH x AS (SELECT 1) SELECT * FROM x",
+            "SELECT 1",
+        ] {
+            assert_eq!(source_table(sql), None, "{sql}");
+        }
+    }
+
+    #[test]
+    fn key_condition_quotes_names_and_values() {
+        let target = target(&[("i\"d", 0), ("name", 1)]);
+        assert_eq!(
+            target.key_condition(&row(&[Some("1"), Some("O'Brien")])),
+            Ok("\"i\"\"d\" = '1' AND \"name\" = 'O''Brien'".to_string())
+        );
+    }
+
+    #[test]
+    fn key_condition_refuses_null_keys() {
+        let target = target(&[("id", 0)]);
+        assert!(target.key_condition(&row(&[None, Some("a")])).is_err());
+    }
+
+    #[test]
+    fn update_sets_value_or_null() {
+        let target = target(&[("id", 0)]);
+        let header = ["id".to_string(), "note".to_string()];
+        let row = row(&[Some("7"), None]);
+        assert_eq!(
+            target.update(&header, &row, 1, Some("it's")),
+            Ok("UPDATE t SET \"note\" = 'it''s' WHERE \"id\" = '7'".to_string())
+        );
+        assert_eq!(
+            target.update(&header, &row, 1, None),
+            Ok("UPDATE t SET \"note\" = NULL WHERE \"id\" = '7'".to_string())
+        );
+    }
+
+    #[test]
+    fn delete_matches_each_row() {
+        let target = target(&[("id", 0)]);
+        let rows = [row(&[Some("1")]), row(&[Some("2")])];
+        assert_eq!(
+            target.delete(&rows),
+            Ok("DELETE FROM t WHERE (\"id\" = '1') OR (\"id\" = '2')".to_string())
+        );
+        assert!(target.delete(&[row(&[None])]).is_err());
+    }
+}
@@ -0,0 +1,54 @@
+use std::{env, fs, io, path::Path};
+
+/// Sets the variables in a `.env` file that aren't set already, so the
+/// environment civciv was started with wins. Lines are `NAME=value`, maybe
+/// after `export`, with the value maybe in quotes; `#` starts a comment.
+pub fn load(path: &Path) -> io::Result<()> {
+    let contents = fs::read_to_string(path)?;
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: expected NAME=value", path.display(), number + 1),
+            ));
+        };
+        let name = name.trim();
+        if env::var_os(name).is_none() {
+            env::set_var(name, value_of(value.trim()));
+        }
+    }
+    Ok(())
+}
+
+/// The value with its quotes taken off, or up to a ` #` comment when it
+/// isn't quoted. Double quotes understand `\n` and `\"`.
+fn value_of(value: &str) -> String {
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.split_once('\'')) {
+        return inner.0.to_string();
+    }
+    if let Some(inner) = value.strip_prefix('"') {
+        let mut unescaped = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => unescaped.push('\n'),
+                    Some(c) => unescaped.push(c),
+                    None => {}
+                },
+                c => unescaped.push(c),
+            }
+        }
+        return unescaped;
+    }
+    match value.find(" #") {
+        Some(comment) => value[..comment].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
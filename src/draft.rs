@@ -0,0 +1,44 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// What was typed in each pane's editor and not run yet, saved every few
+/// seconds so it survives a crash or an accidental quit.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Draft {
+    pub inputs: Vec<String>,
+}
+
+impl Draft {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("civciv").join("draft.json"))
+    }
+
+    /// Loads the saved draft, treating a missing or unreadable file as
+    /// empty.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes the draft next to the old one and then moves it over, so a
+    /// crash halfway through leaves the old one whole.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        fs::rename(partial, path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.iter().all(|input| input.trim().is_empty())
+    }
+}
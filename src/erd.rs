@@ -0,0 +1,137 @@
+use unicode_width::UnicodeWidthStr;
+
+use crate::schema::Table;
+
+/// Space between boxes laid out side by side.
+const GAP: usize = 3;
+
+/// A text diagram of tables and the foreign keys between them. Tables are
+/// drawn as boxes listing their columns, packed into rows as wide as the
+/// view, with the relationships listed underneath.
+pub struct Diagram {
+    pub title: String,
+    boxes: Vec<Vec<String>>,
+    relations: Vec<String>,
+    pub scroll: (u16, u16),
+}
+
+impl Diagram {
+    pub fn new(title: String, tables: &[&Table]) -> Self {
+        let boxes = tables.iter().map(|table| table_box(table)).collect();
+        let relations = tables
+            .iter()
+            .flat_map(|table| {
+                table.foreign_keys.iter().map(|key| {
+                    format!(
+                        "{}({}) ──▶ {}({})",
+                        table.name,
+                        key.columns.join(", "),
+                        key.table,
+                        key.referenced_columns.join(", ")
+                    )
+                })
+            })
+            .collect();
+        Self {
+            title,
+            boxes,
+            relations,
+            scroll: (0, 0),
+        }
+    }
+
+    /// The diagram as lines of text, with rows of boxes at most `width`
+    /// columns wide unless a single box is wider.
+    pub fn lines(&self, width: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut row: Vec<&Vec<String>> = Vec::new();
+        let mut row_width = 0;
+        for table in &self.boxes {
+            let box_width = table[0].width();
+            if !row.is_empty() && row_width + GAP + box_width > width {
+                lay_out_row(&row, &mut lines);
+                row.clear();
+                row_width = 0;
+            }
+            row_width += if row.is_empty() { 0 } else { GAP } + box_width;
+            row.push(table);
+        }
+        lay_out_row(&row, &mut lines);
+
+        if !self.relations.is_empty() {
+            lines.push(String::new());
+            lines.push("Relationships".to_string());
+            lines.extend(
+                self.relations
+                    .iter()
+                    .map(|relation| format!("  {relation}")),
+            );
+        }
+        lines
+    }
+}
+
+/// Appends boxes side by side, padding shorter ones with blank lines.
+fn lay_out_row(row: &[&Vec<String>], lines: &mut Vec<String>) {
+    let height = row.iter().map(|table| table.len()).max().unwrap_or(0);
+    for i in 0..height {
+        let parts: Vec<String> = row
+            .iter()
+            .map(|table| match table.get(i) {
+                Some(line) => line.clone(),
+                None => " ".repeat(table[0].width()),
+            })
+            .collect();
+        lines.push(parts.join(&" ".repeat(GAP)).trim_end().to_string());
+    }
+    if height > 0 {
+        lines.push(String::new());
+    }
+}
+
+/// A table as a box with its name on top and a line per column, marking
+/// key columns and where foreign keys point.
+fn table_box(table: &Table) -> Vec<String> {
+    let kind = if table.is_view { " (view)" } else { "" };
+    let title = format!("{}{kind}", table.name);
+    let rows: Vec<String> = table
+        .columns
+        .iter()
+        .map(|column| {
+            let referenced = table.foreign_keys.iter().find_map(|key| {
+                let i = key.columns.iter().position(|name| *name == column.name)?;
+                Some(format!(
+                    " → {}.{}",
+                    key.table,
+                    key.referenced_columns.get(i).map_or("", String::as_str)
+                ))
+            });
+            let marker = if table.primary_key.contains(&column.name) {
+                "PK "
+            } else if referenced.is_some() {
+                "FK "
+            } else {
+                "   "
+            };
+            format!(
+                "{marker}{} {}{}",
+                column.name,
+                column.data_type,
+                referenced.unwrap_or_default()
+            )
+        })
+        .collect();
+
+    let inner = rows
+        .iter()
+        .map(|row| row.width())
+        .chain([title.width()])
+        .max()
+        .unwrap_or(0);
+    let line = |text: &str| format!("│ {text}{} │", " ".repeat(inner - text.width()));
+    let rule = "─".repeat(inner + 2);
+    let mut lines = vec![format!("┌{rule}┐"), line(&title), format!("├{rule}┤")];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines.push(format!("└{rule}┘"));
+    lines
+}
@@ -0,0 +1,126 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use ratatui::widgets::ListState;
+
+/// Extensions opened as a database rather than inserted as a data file path.
+const DATABASE_EXTENSIONS: &[&str] = &["duckdb", "ddb", "db", "sqlite", "sqlite3"];
+
+pub struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+pub struct FileBrowser {
+    pub dir: PathBuf,
+    pub entries: Vec<Entry>,
+    pub state: ListState,
+    /// Comma separated extensions files must have to be listed, e.g. `csv,parquet`.
+    pub filter: String,
+    pub editing_filter: bool,
+}
+
+impl FileBrowser {
+    pub fn new() -> io::Result<Self> {
+        let mut browser = Self {
+            dir: env::current_dir()?,
+            entries: vec![],
+            state: ListState::default(),
+            filter: String::new(),
+            editing_filter: false,
+        };
+        browser.refresh()?;
+        Ok(browser)
+    }
+
+    pub fn refresh(&mut self) -> io::Result<()> {
+        let extensions: Vec<String> = self
+            .filter
+            .split(',')
+            .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+
+        let mut entries: Vec<Entry> = fs::read_dir(&self.dir)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                if name.starts_with('.') {
+                    return None;
+                }
+                if !is_dir && !extensions.is_empty() && !extensions.contains(&extension(&name)) {
+                    return None;
+                }
+                Some(Entry { name, is_dir })
+            })
+            .collect();
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+        self.entries = entries;
+        self.state.select(Some(0));
+        Ok(())
+    }
+
+    pub fn select_next(&mut self) {
+        let last = self.entries.len().saturating_sub(1);
+        let next = self.state.selected().map_or(0, |i| i + 1);
+        self.state.select(Some(next.min(last)));
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(previous));
+    }
+
+    pub fn parent(&mut self) -> io::Result<()> {
+        if let Some(parent) = self.dir.parent() {
+            self.dir = parent.to_path_buf();
+            self.refresh()?;
+        }
+        Ok(())
+    }
+
+    /// Enters the selected directory, or returns the selected file's path.
+    pub fn open_selected(&mut self) -> io::Result<Option<PathBuf>> {
+        let Some(entry) = self.state.selected().and_then(|i| self.entries.get(i)) else {
+            return Ok(None);
+        };
+
+        let path = self.dir.join(&entry.name);
+        if entry.is_dir {
+            self.dir = path;
+            self.refresh()?;
+            Ok(None)
+        } else {
+            Ok(Some(path))
+        }
+    }
+}
+
+pub fn is_database(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| DATABASE_EXTENSIONS.contains(&extension(name).as_str()))
+}
+
+/// The path as it should be written into a query: relative to the working
+/// directory when it is below it.
+pub fn display_path(path: &Path) -> String {
+    env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn extension(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
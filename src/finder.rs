@@ -0,0 +1,115 @@
+use ratatui::widgets::ListState;
+
+use crate::{schema::SchemaBrowser, sql::quote_identifier};
+
+/// Rows the preview of a table reads.
+pub const PREVIEW_ROWS: usize = 100;
+
+/// State of the fuzzy finder over every table and view of the attached
+/// catalogs.
+pub struct Finder {
+    pub query: String,
+    /// Qualified names of all tables and views.
+    names: Vec<String>,
+    /// Names matching the query, best first.
+    pub matches: Vec<String>,
+    pub state: ListState,
+}
+
+impl Finder {
+    pub fn new(schema: &SchemaBrowser) -> Self {
+        let names = schema
+            .catalogs
+            .iter()
+            .flat_map(|catalog| {
+                catalog.schemas.iter().flat_map(move |schema| {
+                    schema.tables.iter().map(move |table| {
+                        [&catalog.name, &schema.name, &table.name]
+                            .map(|part| name_part(part))
+                            .join(".")
+                    })
+                })
+            })
+            .collect();
+        let mut finder = Self {
+            query: String::new(),
+            names,
+            matches: vec![],
+            state: ListState::default(),
+        };
+        finder.refilter();
+        finder
+    }
+
+    pub fn enter_char(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    pub fn delete_char(&mut self) {
+        self.query.pop();
+        self.refilter();
+    }
+
+    pub fn select_next(&mut self) {
+        let last = self.matches.len().saturating_sub(1);
+        let next = self.state.selected().map_or(0, |i| (i + 1).min(last));
+        self.state.select(Some(next));
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(previous));
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.matches.get(self.state.selected()?).map(String::as_str)
+    }
+
+    fn refilter(&mut self) {
+        let mut scored: Vec<(i64, &String)> = self
+            .names
+            .iter()
+            .filter_map(|name| Some((score(&self.query, name)?, name)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.len().cmp(&b.1.len())));
+        self.matches = scored.into_iter().map(|(_, name)| name.clone()).collect();
+        self.state.select((!self.matches.is_empty()).then_some(0));
+    }
+}
+
+/// How well `name` matches `query` when the query's characters appear in it
+/// in order, ignoring case. Runs of consecutive characters and matches at
+/// the start of a name part count for more.
+fn score(query: &str, name: &str) -> Option<i64> {
+    let name: Vec<char> = name.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..name.len()).find(|&i| name[i].eq_ignore_ascii_case(&wanted))?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || matches!(name[found - 1], '.' | '_' | '"') {
+            score += 3;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+/// Writes a name bare when DuckDB reads it back unchanged, quoted otherwise.
+fn name_part(name: &str) -> String {
+    let bare = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if bare {
+        name.to_string()
+    } else {
+        quote_identifier(name)
+    }
+}
@@ -0,0 +1,464 @@
+use std::{collections::HashSet, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, AsArray, Int64Array, RecordBatch, StringArray},
+    compute::cast,
+    datatypes::{DataType, Field, Int64Type, Schema, TimeUnit},
+    error::ArrowError,
+    util::display::{ArrayFormatter, FormatOptions},
+};
+use chrono::{DateTime, FixedOffset, Local, TimeZone};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    config::{CellFormat, Config},
+    pane::Pane,
+};
+
+/// Rows from the top of the result used to size the columns, so the widths
+/// stay put while scrolling.
+const WIDTH_SAMPLE_ROWS: usize = 200;
+/// Space ratatui's table leaves between columns.
+pub const COLUMN_SPACING: u16 = 1;
+
+/// The result turned on its side: a `column` column naming the columns
+/// not in `hidden`, then one column per row, named by its number in the
+/// whole result, holding the values as they are shown.
+pub fn transpose(
+    data: &[RecordBatch],
+    first_row: usize,
+    hidden: &HashSet<String>,
+    format: &CellFormat,
+) -> Result<RecordBatch, ArrowError> {
+    let rows = rows(data, 0, usize::MAX, format)?;
+    let shown: Vec<(usize, String)> = header(data)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, name)| !hidden.contains(name))
+        .collect();
+
+    let mut fields = vec![Field::new("column", DataType::Utf8, false)];
+    let mut arrays: Vec<ArrayRef> = vec![Arc::new(
+        shown
+            .iter()
+            .map(|(_, name)| Some(name.as_str()))
+            .collect::<StringArray>(),
+    )];
+    for (i, row) in rows.iter().enumerate() {
+        fields.push(Field::new(
+            (first_row + i + 1).to_string(),
+            DataType::Utf8,
+            true,
+        ));
+        arrays.push(Arc::new(
+            shown
+                .iter()
+                .map(|(c, _)| row.get(*c).cloned().flatten())
+                .collect::<StringArray>(),
+        ));
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+}
+
+pub fn header(data: &[RecordBatch]) -> Vec<String> {
+    data.first().map_or_else(Vec::new, |batch| {
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect()
+    })
+}
+
+/// Each column's type, as Arrow names it.
+pub fn types(data: &[RecordBatch]) -> Vec<String> {
+    data.first().map_or_else(Vec::new, |batch| {
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.data_type().to_string())
+            .collect()
+    })
+}
+
+/// Formats `count` rows from `start` on, with `None` for NULLs. Only these
+/// rows are formatted, so drawing a window of a large result stays cheap.
+pub fn rows(
+    data: &[RecordBatch],
+    start: usize,
+    count: usize,
+    format: &CellFormat,
+) -> Result<Vec<Vec<Option<String>>>, ArrowError> {
+    let options = FormatOptions::default()
+        .with_display_error(true)
+        .with_timestamp_format(format.timestamp_format.as_deref())
+        .with_timestamp_tz_format(format.timestamp_format.as_deref())
+        .with_date_format(format.date_format.as_deref());
+    let zone = format.timezone.as_deref().and_then(display_timezone);
+
+    let mut rows = Vec::new();
+    let mut skip = start;
+    for batch in data {
+        if rows.len() == count {
+            break;
+        }
+        if skip >= batch.num_rows() {
+            skip -= batch.num_rows();
+            continue;
+        }
+
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|c| in_timezone(c, zone.as_ref()))
+            .collect::<Result<Vec<_>, ArrowError>>()?;
+        let formatters = columns
+            .iter()
+            .map(|c| Ok((c, ArrayFormatter::try_new(c.as_ref(), &options)?)))
+            .collect::<Result<Vec<_>, ArrowError>>()?;
+        for row in skip..batch.num_rows() {
+            if rows.len() == count {
+                break;
+            }
+            rows.push(
+                formatters
+                    .iter()
+                    .map(|(column, f)| {
+                        if column.is_null(row) {
+                            return None;
+                        }
+                        let text = f.value(row).to_string();
+                        Some(match column.data_type() {
+                            data_type if data_type.is_numeric() => {
+                                format_number(text, data_type, format)
+                            }
+                            _ => text,
+                        })
+                    })
+                    .collect(),
+            );
+        }
+        skip = 0;
+    }
+
+    Ok(rows)
+}
+
+/// Display widths of the columns, fitting the header, along with the types
+/// when they are shown, and the first rows but no wider than
+/// `max_cell_width`, unless that is 0.
+pub fn column_widths(
+    header: &[String],
+    with_types: bool,
+    data: &[RecordBatch],
+    config: &Config,
+) -> Result<Vec<u16>, ArrowError> {
+    let max_width = match config.max_cell_width {
+        0 => u16::MAX as usize,
+        width => width,
+    };
+    let sample = rows(data, 0, WIDTH_SAMPLE_ROWS, &config.format)?;
+    let types = if with_types { types(data) } else { vec![] };
+    Ok(header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let label = types.get(i).map_or(0, |t| t.width()).max(name.width());
+            let widest = sample
+                .iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| cell.as_deref().unwrap_or(&config.null_display).width())
+                .fold(label, usize::max);
+            widest.min(max_width) as u16
+        })
+        .collect())
+}
+
+/// A zone to show timestamps in.
+enum Zone {
+    /// This machine's, at the time of each timestamp.
+    Local,
+    Offset(FixedOffset),
+}
+
+/// The zone `timezone` names. Arrow is built without the zone database, so
+/// names other than `local` and `UTC` aren't known.
+fn display_timezone(timezone: &str) -> Option<Zone> {
+    if timezone.eq_ignore_ascii_case("local") {
+        Some(Zone::Local)
+    } else if timezone.eq_ignore_ascii_case("utc") || timezone == "Z" {
+        FixedOffset::east_opt(0).map(Zone::Offset)
+    } else {
+        timezone.parse().ok().map(Zone::Offset)
+    }
+}
+
+/// Moves timestamps that carry a zone to `zone`. This only changes how they
+/// are displayed, not the instant they stand for.
+fn in_timezone(column: &ArrayRef, zone: Option<&Zone>) -> Result<ArrayRef, ArrowError> {
+    match (column.data_type().clone(), zone) {
+        (DataType::Timestamp(unit, Some(_)), Some(Zone::Offset(offset))) => cast(
+            column,
+            &DataType::Timestamp(unit, Some(offset.to_string().into())),
+        ),
+        (DataType::Timestamp(unit, Some(_)), Some(Zone::Local)) => in_local_time(column, unit),
+        _ => Ok(column.clone()),
+    }
+}
+
+/// Timestamps as this machine's wall clock showed them, each with the
+/// offset in effect then, so ones across a daylight saving change are
+/// right too. They lose their zone, as one offset can't fit them all.
+fn in_local_time(column: &ArrayRef, unit: TimeUnit) -> Result<ArrayRef, ArrowError> {
+    let per_second = match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    };
+    let raw = cast(column, &DataType::Int64)?;
+    let local: Int64Array = raw.as_primitive::<Int64Type>().unary(|value| {
+        let offset = DateTime::from_timestamp(value.div_euclid(per_second), 0).map_or(0, |utc| {
+            Local
+                .offset_from_utc_datetime(&utc.naive_utc())
+                .local_minus_utc()
+        });
+        value + i64::from(offset) * per_second
+    });
+    cast(&local, &DataType::Timestamp(unit, None))
+}
+
+/// Applies the precision, scientific notation and digit grouping settings to
+/// a number as Arrow wrote it. Integers and decimals are rounded on their
+/// digits, since going through `f64` would lose some of a wide value's.
+fn format_number(text: String, data_type: &DataType, format: &CellFormat) -> String {
+    let Ok(value) = text.parse::<f64>() else {
+        return text;
+    };
+    let scientific = format.scientific_above > 0.0 && value.abs() >= format.scientific_above;
+    let text = if data_type.is_floating() {
+        match (scientific, format.decimals) {
+            (true, Some(decimals)) => return format!("{value:.decimals$e}"),
+            (true, None) => return format!("{value:e}"),
+            (false, Some(decimals)) if value.is_finite() => format!("{value:.decimals$}"),
+            _ => text,
+        }
+    } else {
+        let decimal = matches!(
+            data_type,
+            DataType::Decimal128(..) | DataType::Decimal256(..)
+        );
+        match (scientific, format.decimals) {
+            (true, decimals) => return scientific_digits(&text, decimals),
+            (false, Some(decimals)) if decimal => round_digits(&text, decimals),
+            _ => text,
+        }
+    };
+    if format.thousands_separator.is_empty() {
+        return text;
+    }
+
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", text.as_str()),
+    };
+    let digits = unsigned
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(unsigned.len());
+    let (integer, rest) = unsigned.split_at(digits);
+    let mut grouped = String::from(sign);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push_str(&format.thousands_separator);
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(rest);
+    grouped
+}
+
+/// Rounds a number written as plain digits to `decimals` places, half away
+/// from zero.
+fn round_digits(text: &str, decimals: usize) -> String {
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", text),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let mut digits: Vec<u8> = integer.bytes().collect();
+    digits.extend(
+        fraction
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(decimals),
+    );
+    if fraction
+        .as_bytes()
+        .get(decimals)
+        .is_some_and(|&digit| digit >= b'5')
+    {
+        let mut carry = true;
+        for digit in digits.iter_mut().rev() {
+            if *digit == b'9' {
+                *digit = b'0';
+            } else {
+                *digit += 1;
+                carry = false;
+                break;
+            }
+        }
+        if carry {
+            digits.insert(0, b'1');
+        }
+    }
+    let point = digits.len() - decimals;
+    let mut rounded = String::from(sign);
+    rounded.push_str(std::str::from_utf8(&digits[..point]).unwrap_or_default());
+    if decimals > 0 {
+        rounded.push('.');
+        rounded.push_str(std::str::from_utf8(&digits[point..]).unwrap_or_default());
+    }
+    rounded
+}
+
+/// Writes a number given as plain digits in scientific notation the way
+/// `{:e}` does, with `decimals` places or as many as it has.
+fn scientific_digits(text: &str, decimals: Option<usize>) -> String {
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", text),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let integer = integer.trim_start_matches('0');
+    if integer.is_empty() {
+        // Only reached with a threshold below 1, where f64 is precise enough.
+        let value = text.parse::<f64>().unwrap_or_default();
+        return match decimals {
+            Some(decimals) => format!("{value:.decimals$e}"),
+            None => format!("{value:e}"),
+        };
+    }
+    let mut exponent = integer.len() - 1;
+    let mantissa = format!("{}.{}{fraction}", &integer[..1], &integer[1..]);
+    let mantissa = match decimals {
+        Some(decimals) => {
+            let rounded = round_digits(&mantissa, decimals);
+            // Rounding 9.99… up carries into a second digit.
+            match rounded.strip_prefix("10") {
+                Some(rest) => {
+                    exponent += 1;
+                    round_digits(&format!("1.{}", rest.replace('.', "")), decimals)
+                }
+                None => rounded,
+            }
+        }
+        None => mantissa
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string(),
+    };
+    format!("{sign}{mantissa}e{exponent}")
+}
+
+/// Cuts `cell` to its first line and `width` display columns, ending it
+/// with `…` when something was left out.
+pub fn truncate(cell: &str, width: usize) -> String {
+    let first_line = cell.lines().next().unwrap_or("");
+    if first_line.len() == cell.len() && cell.width() <= width {
+        return cell.to_string();
+    }
+    let mut used = 0;
+    let mut truncated = String::new();
+    for c in first_line.chars() {
+        let c_width = c.width().unwrap_or(0);
+        if used + c_width + 1 > width {
+            break;
+        }
+        used += c_width;
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Breaks `cell` into lines of at most `width` display columns.
+pub fn wrap(cell: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for text in cell.lines() {
+        let mut line = String::new();
+        let mut used = 0;
+        for c in text.chars() {
+            let c_width = c.width().unwrap_or(0);
+            if used + c_width > width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                used = 0;
+            }
+            used += c_width;
+            line.push(c);
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+/// A column as drawn: its index in the result and its width.
+pub struct Column {
+    pub index: usize,
+    pub width: u16,
+}
+
+/// Picks the columns to draw. The first shown column stays frozen while the
+/// rest start after `pane.horizontal_scroll`, which is moved as needed to
+/// keep the selected column in view. Widths set by hand win over `widths`.
+pub fn visible_columns(
+    pane: &mut Pane,
+    header: &[String],
+    widths: &[u16],
+    available: u16,
+) -> Vec<Column> {
+    let shown: Vec<Column> = header
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .filter(|(_, (name, _))| !pane.hidden_columns.contains(*name))
+        .map(|(index, (name, width))| Column {
+            index,
+            width: pane.column_widths.get(name).copied().unwrap_or(*width),
+        })
+        .collect();
+    let Some(frozen) = shown.first() else {
+        return vec![];
+    };
+
+    let selected = match shown.iter().position(|c| c.index == pane.selected_column) {
+        Some(position) => position,
+        None => {
+            pane.selected_column = frozen.index;
+            0
+        }
+    };
+    let mut scroll = pane.horizontal_scroll.min(shown.len().saturating_sub(2));
+    if selected >= 1 {
+        scroll = scroll.min(selected - 1);
+        let fits = |scroll: usize| {
+            let used: u16 = std::iter::once(frozen)
+                .chain(&shown[1 + scroll..=selected])
+                .map(|c| c.width + COLUMN_SPACING)
+                .sum();
+            used <= available
+        };
+        while scroll < selected - 1 && !fits(scroll) {
+            scroll += 1;
+        }
+    }
+    pane.horizontal_scroll = scroll;
+
+    let mut drawn = shown;
+    drawn.drain(1..(1 + scroll).min(drawn.len()));
+    drawn
+}
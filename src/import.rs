@@ -0,0 +1,274 @@
+use std::path::Path;
+
+use arrow::array::RecordBatch;
+use duckdb::Connection;
+
+use crate::sql::{quote_identifier, quote_literal};
+
+const PREVIEW_ROWS: usize = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Parquet,
+    Json,
+}
+
+impl Format {
+    fn from_path(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("parquet") => Format::Parquet,
+            Some("json" | "ndjson" | "jsonl") => Format::Json,
+            _ => Format::Csv,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Path,
+    Name,
+    Delimiter,
+    Header,
+    Kind,
+    Column(usize),
+}
+
+pub struct ImportColumn {
+    pub name: String,
+    pub inferred_type: String,
+    pub data_type: String,
+}
+
+/// State of the "open data file" dialog, which builds a `read_*` call from
+/// the options and creates a view or table over it.
+pub struct ImportWizard {
+    pub path: String,
+    pub name: String,
+    pub delimiter: String,
+    pub header: bool,
+    pub as_table: bool,
+    pub columns: Vec<ImportColumn>,
+    pub field: Field,
+    pub preview: Vec<RecordBatch>,
+    pub error: Option<String>,
+    /// Set when an option changed since the preview was last run.
+    dirty: bool,
+}
+
+impl Default for ImportWizard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImportWizard {
+    pub fn new() -> Self {
+        Self {
+            path: String::new(),
+            name: String::new(),
+            delimiter: String::new(),
+            header: true,
+            as_table: false,
+            columns: vec![],
+            field: Field::Path,
+            preview: vec![],
+            error: None,
+            dirty: false,
+        }
+    }
+
+    pub fn format(&self) -> Format {
+        Format::from_path(&self.path)
+    }
+
+    /// The fields the user can move between, which depend on the file format.
+    pub fn fields(&self) -> Vec<Field> {
+        let mut fields = vec![Field::Path, Field::Name];
+        if self.format() == Format::Csv {
+            fields.extend([Field::Delimiter, Field::Header]);
+        }
+        fields.push(Field::Kind);
+        if self.format() == Format::Csv {
+            fields.extend((0..self.columns.len()).map(Field::Column));
+        }
+        fields
+    }
+
+    pub fn next_field(&mut self, db: &Connection) {
+        self.move_field(db, 1);
+    }
+
+    pub fn previous_field(&mut self, db: &Connection) {
+        let len = self.fields().len();
+        self.move_field(db, len - 1);
+    }
+
+    fn move_field(&mut self, db: &Connection, offset: usize) {
+        if self.dirty {
+            self.inspect(db);
+        }
+        let fields = self.fields();
+        let current = fields.iter().position(|f| *f == self.field).unwrap_or(0);
+        self.field = fields[(current + offset) % fields.len()];
+    }
+
+    pub fn enter_char(&mut self, c: char) {
+        match self.field {
+            Field::Path => self.path.push(c),
+            Field::Name => self.name.push(c),
+            Field::Delimiter => self.delimiter.push(c),
+            Field::Header if c == ' ' => self.header = !self.header,
+            Field::Kind if c == ' ' => self.as_table = !self.as_table,
+            Field::Column(i) => self.columns[i].data_type.push(c),
+            Field::Header | Field::Kind => return,
+        }
+        self.dirty |= !matches!(self.field, Field::Name | Field::Kind);
+    }
+
+    pub fn delete_char(&mut self) {
+        match self.field {
+            Field::Path => self.path.pop(),
+            Field::Name => self.name.pop(),
+            Field::Delimiter => self.delimiter.pop(),
+            Field::Column(i) => self.columns[i].data_type.pop(),
+            Field::Header | Field::Kind => return,
+        };
+        self.dirty |= self.field != Field::Name;
+    }
+
+    /// The `read_*` table function call for the current options.
+    pub fn source_sql(&self) -> String {
+        let path = quote_literal(&self.path);
+        match self.format() {
+            Format::Parquet => format!("read_parquet({path})"),
+            Format::Json => format!("read_json_auto({path})"),
+            Format::Csv => {
+                let mut options = vec![format!("header={}", self.header)];
+                if !self.delimiter.is_empty() {
+                    options.push(format!("delim={}", quote_literal(&self.delimiter)));
+                }
+                let types: Vec<String> = self
+                    .columns
+                    .iter()
+                    .filter(|c| !c.data_type.is_empty() && c.data_type != c.inferred_type)
+                    .map(|c| {
+                        format!(
+                            "{}: {}",
+                            quote_literal(&c.name),
+                            quote_literal(&c.data_type)
+                        )
+                    })
+                    .collect();
+                if !types.is_empty() {
+                    options.push(format!("types={{{}}}", types.join(", ")));
+                }
+                format!("read_csv_auto({path}, {})", options.join(", "))
+            }
+        }
+    }
+
+    /// Re-runs type inference and the preview for the current options.
+    pub fn inspect(&mut self, db: &Connection) {
+        self.dirty = false;
+        if self.name.is_empty() {
+            self.name = default_name(&self.path);
+        }
+
+        match self.load_columns(db).and_then(|_| self.load_preview(db)) {
+            Ok(()) => self.error = None,
+            Err(err) => {
+                self.preview.clear();
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    fn load_columns(&mut self, db: &Connection) -> duckdb::Result<()> {
+        // Describe the source without the type overrides, so the inferred
+        // types stay visible next to the chosen ones.
+        let overrides = std::mem::take(&mut self.columns);
+        let mut stmt = db.prepare(&format!("DESCRIBE SELECT * FROM {}", self.source_sql()))?;
+        let described = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<duckdb::Result<Vec<_>>>();
+
+        let described = match described {
+            Ok(described) => described,
+            Err(err) => {
+                self.columns = overrides;
+                return Err(err);
+            }
+        };
+
+        self.columns = described
+            .into_iter()
+            .map(|(name, inferred_type)| {
+                let data_type = overrides
+                    .iter()
+                    .find(|c| c.name == name)
+                    .map_or_else(|| inferred_type.clone(), |c| c.data_type.clone());
+                ImportColumn {
+                    name,
+                    inferred_type,
+                    data_type,
+                }
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    fn load_preview(&mut self, db: &Connection) -> duckdb::Result<()> {
+        let sql = format!("SELECT * FROM {} LIMIT {PREVIEW_ROWS}", self.source_sql());
+        let mut stmt = db.prepare(&sql)?;
+        self.preview = stmt.query_arrow([])?.collect();
+        Ok(())
+    }
+
+    /// Creates the view or table and returns its name.
+    pub fn create(&mut self, db: &Connection) -> duckdb::Result<String> {
+        if self.dirty || self.columns.is_empty() {
+            self.inspect(db);
+        }
+        let kind = if self.as_table { "TABLE" } else { "VIEW" };
+        let sql = format!(
+            "CREATE {kind} {} AS SELECT * FROM {}",
+            quote_identifier(&self.name),
+            self.source_sql()
+        );
+        db.execute_batch(&sql)?;
+        Ok(self.name.clone())
+    }
+}
+
+/// A query over a file with the default options, read as its extension
+/// says. The query string and fragment of a URL are not part of that.
+pub fn preview_sql(path: &str) -> String {
+    let without_query = path.split(['?', '#']).next().unwrap_or(path);
+    let source = quote_literal(path);
+    let source = match Format::from_path(without_query) {
+        Format::Parquet => format!("read_parquet({source})"),
+        Format::Json => format!("read_json_auto({source})"),
+        Format::Csv => format!("read_csv_auto({source})"),
+    };
+    format!("SELECT * FROM {source}")
+}
+
+fn default_name(path: &str) -> String {
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("import");
+
+    stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,149 @@
+use duckdb::Connection;
+
+use crate::sql::{quote_identifier, quote_literal};
+
+pub struct FormField {
+    pub name: String,
+    pub data_type: String,
+    pub default: Option<String>,
+    pub nullable: bool,
+    pub value: String,
+}
+
+/// State of the insert row form, which builds an `INSERT` from the values
+/// typed for the columns of a table.
+pub struct InsertForm {
+    /// The table as `catalog.schema.table`, quoted.
+    pub table: String,
+    pub fields: Vec<FormField>,
+    pub selected: usize,
+}
+
+impl InsertForm {
+    pub fn load(db: &Connection, catalog: &str, schema: &str, table: &str) -> duckdb::Result<Self> {
+        let mut stmt = db.prepare(&format!(
+            "SELECT column_name, data_type, column_default, is_nullable
+             FROM duckdb_columns()
+             WHERE database_name = {} AND schema_name = {} AND table_name = {}
+             ORDER BY column_index",
+            quote_literal(catalog),
+            quote_literal(schema),
+            quote_literal(table)
+        ))?;
+        let fields = stmt
+            .query_map([], |row| {
+                Ok(FormField {
+                    name: row.get(0)?,
+                    data_type: row.get(1)?,
+                    default: row.get(2)?,
+                    nullable: row.get(3)?,
+                    value: String::new(),
+                })
+            })?
+            .collect::<duckdb::Result<_>>()?;
+
+        Ok(Self {
+            table: [catalog, schema, table].map(quote_identifier).join("."),
+            fields,
+            selected: 0,
+        })
+    }
+
+    pub fn next_field(&mut self) {
+        self.selected = (self.selected + 1) % self.fields.len().max(1);
+    }
+
+    pub fn previous_field(&mut self) {
+        let len = self.fields.len().max(1);
+        self.selected = (self.selected + len - 1) % len;
+    }
+
+    pub fn enter_char(&mut self, c: char) {
+        if let Some(field) = self.fields.get_mut(self.selected) {
+            field.value.push(c);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        if let Some(field) = self.fields.get_mut(self.selected) {
+            field.value.pop();
+        }
+    }
+
+    /// The `INSERT` for the typed values. Columns left empty are omitted so
+    /// their defaults apply, and `NULL` inserts a NULL.
+    pub fn statement(&self) -> String {
+        let filled: Vec<&FormField> = self
+            .fields
+            .iter()
+            .filter(|field| !field.value.is_empty())
+            .collect();
+        if filled.is_empty() {
+            return format!("INSERT INTO {} DEFAULT VALUES", self.table);
+        }
+
+        let columns: Vec<String> = filled
+            .iter()
+            .map(|field| quote_identifier(&field.name))
+            .collect();
+        let values: Vec<String> = filled
+            .iter()
+            .map(|field| match field.value.as_str() {
+                value if value.eq_ignore_ascii_case("null") => "NULL".to_string(),
+                value => quote_literal(value),
+            })
+            .collect();
+        format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            self.table,
+            columns.join(", "),
+            values.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(values: &[(&str, &str)]) -> InsertForm {
+        InsertForm {
+            table: "\"t\"".to_string(),
+            fields: values
+                .iter()
+                .map(|(name, value)| FormField {
+                    name: name.to_string(),
+                    data_type: "VARCHAR".to_string(),
+                    default: None,
+                    nullable: true,
+                    value: value.to_string(),
+                })
+                .collect(),
+            selected: 0,
+        }
+    }
+
+    #[test]
+    fn statement_omits_empty_fields() {
+        assert_eq!(
+            form(&[("id", "1"), ("note", ""), ("name", "O'Brien")]).statement(),
+            "INSERT INTO \"t\" (\"id\", \"name\") VALUES ('1', 'O''Brien')"
+        );
+    }
+
+    #[test]
+    fn statement_inserts_null() {
+        assert_eq!(
+            form(&[("a\"b", "null")]).statement(),
+            "INSERT INTO \"t\" (\"a\"\"b\") VALUES (NULL)"
+        );
+    }
+
+    #[test]
+    fn statement_uses_default_values_when_nothing_is_typed() {
+        assert_eq!(
+            form(&[("id", ""), ("note", "")]).statement(),
+            "INSERT INTO \"t\" DEFAULT VALUES"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// How many finished queries the job list keeps.
+const KEEP: usize = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Done,
+    Failed,
+    /// Cancelled, but still holding its pane's session until the query
+    /// thread lets go of it.
+    Cancelling,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn label(self) -> &'static str {
+        match self {
+            JobState::Running => "running",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+            JobState::Cancelling => "cancelling",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A query or background statement in the job list.
+#[derive(Clone)]
+pub struct JobEntry {
+    pub pane: usize,
+    pub sql: String,
+    pub elapsed: Duration,
+    pub state: JobState,
+}
+
+impl JobEntry {
+    /// The statement on one line, cut off after `width` characters.
+    pub fn snippet(&self, width: usize) -> String {
+        let sql = self.sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        if sql.chars().count() <= width {
+            return sql;
+        }
+        let mut snippet: String = sql.chars().take(width.saturating_sub(1)).collect();
+        snippet.push('…');
+        snippet
+    }
+}
+
+/// The queries that finished lately, newest first.
+#[derive(Default)]
+pub struct JobHistory {
+    finished: VecDeque<JobEntry>,
+}
+
+impl JobHistory {
+    pub fn push(&mut self, job: JobEntry) {
+        self.finished.push_front(job);
+        self.finished.truncate(KEEP);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &JobEntry> {
+        self.finished.iter()
+    }
+}
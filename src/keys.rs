@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// Keystrokes recorded into registers and played back, much like vim's
+/// macros: `Q` and a letter records into that register until `Q` is pressed
+/// again, `@` and a letter plays it back and `@@` plays the last one again.
+#[derive(Default)]
+pub struct KeyMacros {
+    registers: HashMap<char, Vec<KeyEvent>>,
+    recording: Option<(char, Vec<KeyEvent>)>,
+    /// `Q` or `@`, waiting for the register that follows it.
+    prefix: Option<char>,
+    last_played: Option<char>,
+}
+
+pub enum Outcome {
+    /// The key drove the macros, with a message for the status bar.
+    Handled(Option<String>),
+    /// Keys to play back.
+    Play(Vec<KeyEvent>),
+}
+
+impl KeyMacros {
+    /// The register being recorded into.
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    /// Looks at a key press before it is handled. `Q` and `@` only drive the
+    /// macros in the result view, where they have no other use. Any other
+    /// key is left to be handled, and recorded if a macro is being recorded.
+    pub fn intercept(&mut self, key: KeyEvent, in_result_view: bool) -> Option<Outcome> {
+        if let Some(prefix) = self.prefix.take() {
+            let KeyCode::Char(register) = key.code else {
+                return Some(Outcome::Handled(None));
+            };
+            return Some(match prefix {
+                '@' => self.play(register),
+                _ if register.is_ascii_alphanumeric() => {
+                    self.recording = Some((register, vec![]));
+                    Outcome::Handled(Some(format!("Recording @{register}, Q to stop")))
+                }
+                _ => Outcome::Handled(Some(format!("Invalid register: {register}"))),
+            });
+        }
+
+        match key.code {
+            KeyCode::Char('Q') if in_result_view => {
+                let status = self.recording.take().map(|(register, keys)| {
+                    let status = format!("Recorded {} keys into @{register}", keys.len());
+                    self.registers.insert(register, keys);
+                    status
+                });
+                if status.is_none() {
+                    self.prefix = Some('Q');
+                }
+                Some(Outcome::Handled(status))
+            }
+            KeyCode::Char('@') if in_result_view => {
+                self.prefix = Some('@');
+                Some(Outcome::Handled(None))
+            }
+            _ => {
+                if let Some((_, keys)) = &mut self.recording {
+                    keys.push(key);
+                }
+                None
+            }
+        }
+    }
+
+    fn play(&mut self, register: char) -> Outcome {
+        let register = match (register, self.last_played) {
+            ('@', Some(last)) => last,
+            ('@', None) => return Outcome::Handled(Some("No macro played yet".to_string())),
+            _ => register,
+        };
+        let Some(keys) = self.registers.get(&register) else {
+            return Outcome::Handled(Some(format!("Nothing recorded into @{register}")));
+        };
+        self.last_played = Some(register);
+        // A macro played while recording becomes part of the new one.
+        if let Some((_, recording)) = &mut self.recording {
+            recording.extend(keys.iter().copied());
+        }
+        Outcome::Play(keys.clone())
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use duckdb::Connection;
+
+use crate::sql::{quote_identifier, quote_literal};
+
+/// Table formats that keep a table as data files plus metadata in a
+/// folder, read through a DuckDB extension.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Iceberg,
+    Delta,
+}
+
+impl TableFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "iceberg" => Some(Self::Iceberg),
+            "delta" => Some(Self::Delta),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Iceberg => "iceberg",
+            Self::Delta => "delta",
+        }
+    }
+
+    fn scan(self) -> &'static str {
+        match self {
+            Self::Iceberg => "iceberg_scan",
+            Self::Delta => "delta_scan",
+        }
+    }
+}
+
+/// Loads the format's extension, installing it on first use, and creates a
+/// view over the table at `path`, named after its folder unless `name` is
+/// given. Returns the view's name.
+pub fn register(
+    db: &Connection,
+    format: TableFormat,
+    path: &str,
+    name: Option<&str>,
+) -> duckdb::Result<String> {
+    let name = name.map_or_else(|| default_name(path), str::to_string);
+    let extension = format.extension();
+    db.execute_batch(&format!("INSTALL {extension}; LOAD {extension};"))?;
+    db.execute_batch(&format!(
+        "CREATE OR REPLACE VIEW {} AS SELECT * FROM {}({})",
+        quote_identifier(&name),
+        format.scan(),
+        quote_literal(path)
+    ))?;
+    Ok(name)
+}
+
+fn default_name(path: &str) -> String {
+    let folder = Path::new(path.trim_end_matches('/'))
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("lakehouse");
+
+    folder
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
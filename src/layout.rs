@@ -0,0 +1,59 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+const MIN_EDITOR_PERCENT: u16 = 10;
+const MAX_EDITOR_PERCENT: u16 = 90;
+const STEP: u16 = 5;
+
+/// Pane sizes adjusted by the user, kept between sessions.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneSizes {
+    /// Share of a pane taken by the SQL editor, or `None` for the default
+    /// three rows.
+    pub editor_percent: Option<u16>,
+}
+
+impl PaneSizes {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("civciv").join("layout.json"))
+    }
+
+    /// Loads the saved layout, treating a missing or unreadable file as the
+    /// default.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn grow_editor(&mut self) {
+        self.editor_percent = Some(match self.editor_percent {
+            None => 2 * MIN_EDITOR_PERCENT,
+            Some(percent) => (percent + STEP).min(MAX_EDITOR_PERCENT),
+        });
+    }
+
+    /// Shrinks the editor, going back to the default height once it is at
+    /// the minimum share.
+    pub fn shrink_editor(&mut self) {
+        self.editor_percent = match self.editor_percent {
+            Some(percent) if percent > MIN_EDITOR_PERCENT => {
+                Some((percent - STEP).max(MIN_EDITOR_PERCENT))
+            }
+            _ => None,
+        };
+    }
+}
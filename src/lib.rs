@@ -0,0 +1,49 @@
+//! The parts of civciv that don't touch the terminal: the app state and its
+//! input modes, running queries on DuckDB, result formatting and the
+//! widgets that draw it all. The `civciv` binary is a thin frontend that
+//! owns the terminal and turns key presses into calls on [`app::App`];
+//! other ratatui apps can embed the same components.
+
+pub mod app;
+pub mod audit;
+pub mod bench;
+pub mod cache;
+pub mod chart;
+pub mod command;
+pub mod complete;
+pub mod config;
+pub mod db;
+pub mod diff;
+pub mod dml;
+pub mod draft;
+pub mod erd;
+pub mod files;
+pub mod finder;
+pub mod grid;
+pub mod import;
+pub mod insert;
+pub mod jobs;
+pub mod lakehouse;
+pub mod layout;
+pub mod lint;
+pub mod pane;
+pub mod params;
+pub mod perf;
+pub mod pivot;
+pub mod plan;
+pub mod plugin;
+pub mod profile;
+pub mod recent;
+pub mod remote;
+pub mod samples;
+pub mod schema;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod secrets;
+pub mod settings;
+pub mod sql;
+pub mod stats;
+pub mod stream;
+pub mod transcript;
+pub mod tutorial;
+pub mod ui;
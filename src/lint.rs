@@ -0,0 +1,177 @@
+use std::ops::Range;
+
+use duckdb::Connection;
+
+use crate::sql;
+
+/// Something wrong with a statement, found before it runs or when it
+/// failed.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Lint {
+    /// DuckDB can't parse, bind or run it, with the bytes of the token it
+    /// complained about when it said where.
+    Error {
+        message: String,
+        span: Option<Range<usize>>,
+    },
+    /// It runs, but likely not as meant.
+    Warning(String),
+}
+
+/// Prepares the first statement without running it, which catches syntax
+/// errors and missing tables or columns, then looks for risky patterns in
+/// all of them. Later statements may need what the ones before them
+/// create, so they aren't prepared.
+pub fn check(db: &Connection, sql: &str) -> Vec<Lint> {
+    let statements = sql::split_statements(sql);
+    let Some(first) = statements.first() else {
+        return vec![];
+    };
+    let mut lints = vec![];
+    // Binding a query over a URL would fetch from it, so only the risky
+    // patterns are looked for.
+    if !first.contains("://") {
+        // Named placeholders are bound by number when it runs.
+        let (numbered, _) = sql::placeholders(first);
+        if let Err(err) = db.prepare(&numbered) {
+            lints.push(error(&err.to_string(), sql));
+        }
+    }
+    lints.extend(warnings(sql).into_iter().map(Lint::Warning));
+    lints
+}
+
+/// Risky patterns in the statements, going by their words alone.
+pub fn warnings(sql: &str) -> Vec<String> {
+    let mut warnings = vec![];
+    for statement in sql::split_statements(sql) {
+        let statement = tokens(statement);
+        let has = |word: &str| statement.iter().any(|token| token == word);
+        match statement.first().map(String::as_str) {
+            Some("DELETE") if !has("WHERE") => {
+                warnings.push("DELETE without WHERE removes every row".to_string())
+            }
+            Some("UPDATE") if !has("WHERE") => {
+                warnings.push("UPDATE without WHERE changes every row".to_string())
+            }
+            _ => {}
+        }
+        // Columns left out with EXCLUDE or swapped with REPLACE were
+        // thought about.
+        let star = (1..statement.len()).any(|i| {
+            matches!(statement[i - 1].as_str(), "SELECT" | "DISTINCT")
+                && statement[i] == "*"
+                && !matches!(
+                    statement.get(i + 1).map(String::as_str),
+                    Some("EXCLUDE" | "REPLACE")
+                )
+        });
+        if star {
+            warnings.push("SELECT * reads every column, list the ones needed".to_string());
+        }
+    }
+    warnings
+}
+
+/// An error DuckDB gave for `sql`, pointing at the token it quotes.
+pub fn error(message: &str, sql: &str) -> Lint {
+    Lint::Error {
+        message: summary(message),
+        span: error_span(message, sql),
+    }
+}
+
+/// Where in `sql` the caret under DuckDB's `LINE n:` quote points, as the
+/// bytes of the token there. The quote is found by its text rather than
+/// its line number, since the query may have been wrapped for paging, and
+/// DuckDB cuts long lines down to `...` around the spot.
+fn error_span(message: &str, sql: &str) -> Option<Range<usize>> {
+    let mut lines = message
+        .lines()
+        .skip_while(|line| !line.starts_with("LINE "));
+    let quote = lines.next()?;
+    let caret = lines.next()?.find('^')?;
+    let prefix = quote.find(": ")? + 2;
+    let mut offset = caret.checked_sub(prefix)?;
+    let mut snippet = &quote[prefix..];
+    if let Some(rest) = snippet.strip_prefix("...") {
+        snippet = rest;
+        offset = offset.saturating_sub(3);
+    }
+    let snippet = snippet.strip_suffix("...").unwrap_or(snippet).trim_end();
+    let found = sql.find(snippet)?;
+    let at = snippet
+        .char_indices()
+        .nth(offset)
+        .map_or(snippet.len(), |(i, _)| i);
+    let start = found + at;
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    match sql[start..].chars().next() {
+        Some(c) if is_word(c) => {
+            let end = sql[start..]
+                .find(|c| !is_word(c))
+                .map_or(sql.len(), |i| start + i);
+            Some(start..end)
+        }
+        Some(c) => Some(start..start + c.len_utf8()),
+        // At the end of the input: the last character, so there is
+        // something to see.
+        None => {
+            let last = sql.trim_end().char_indices().next_back()?;
+            Some(last.0..last.0 + last.1.len_utf8())
+        }
+    }
+}
+
+/// DuckDB's message up to where it quotes the statement, on one line.
+fn summary(message: &str) -> String {
+    message
+        .lines()
+        .take_while(|line| !line.starts_with("LINE "))
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Words in upper case and other characters one by one, leaving out
+/// comments, strings and quoted names.
+fn tokens(sql: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '-' if chars.peek() == Some(&'-') => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                chars.by_ref().find(|&c| {
+                    let end = previous == '*' && c == '/';
+                    previous = c;
+                    end
+                });
+            }
+            // A doubled quote inside is read as the end and start of two
+            // strings, which leaves nothing behind either way.
+            '\'' | '"' => {
+                chars.by_ref().find(|&next| next == c);
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_uppercase().to_string();
+                while let Some(&next) = chars.peek() {
+                    if !next.is_alphanumeric() && next != '_' {
+                        break;
+                    }
+                    word.extend(next.to_uppercase());
+                    chars.next();
+                }
+                tokens.push(word);
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push(c.to_string()),
+        }
+    }
+    tokens
+}
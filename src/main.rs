@@ -1,41 +1,172 @@
 use std::{
+    collections::VecDeque,
     error::Error,
+    fs::OpenOptions,
     io::{self},
+    panic,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
-use app::{App, InputMode};
+use civciv_core::{
+    app::App,
+    app::InputMode,
+    audit::AuditLog,
+    chart::ChartKind,
+    config::Config,
+    db,
+    pane::AfterRun,
+    sql,
+    transcript::Transcript,
+    tutorial::{Step, Tutorial},
+    ui,
+};
+use clap::Parser;
+use cli::Cli;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    cursor,
+    event::{
+        self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
 };
-use duckdb::Connection;
-use ratatui::{prelude::*, widgets::*};
+use keys::{KeyMacros, Outcome};
+use ratatui::{prelude::*, widgets::Paragraph, TerminalOptions, Viewport};
+use tracing::Level;
+
+mod cli;
+mod clipboard;
+mod dotenv;
+mod keys;
+mod notify;
+mod pipe;
+
+/// How often the event loop picks up rows while a query runs.
+const QUERY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the screen is drawn again while nothing happens, keeping
+/// counters and spinners moving.
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Lines the UI takes up below the prompt with `--inline`.
+const INLINE_HEIGHT: u16 = 24;
 
-mod app;
+/// Whether the UI is drawn inline rather than on the alternate screen.
+static INLINE: AtomicBool = AtomicBool::new(false);
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let conn = Connection::open_in_memory()?;
+    let cli = Cli::parse();
+    // Before anything reads the environment, or starts a thread.
+    if let Some(path) = &cli.env_file {
+        dotenv::load(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    }
+    if let Some(path) = &cli.log_file {
+        start_log(path, cli.verbose)?;
+    }
+    let config = Config::load()?;
+    let profile = match cli.profile.as_deref() {
+        Some(name) => Some(
+            config
+                .connections
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("No connection named {name} in the config"))?,
+        ),
+        None => None,
+    };
+    let database = match &profile {
+        Some(profile) => profile.path.clone(),
+        None => cli.database.clone(),
+    };
+    let conn = match &profile {
+        Some(profile) => {
+            db::open_profile(cli.profile.as_deref().unwrap_or_default(), profile, &config)?
+        }
+        None => db::open(database.as_deref(), &config)?,
+    };
+    let settings = [
+        ("threads", cli.threads.map(|threads| threads.to_string())),
+        ("memory_limit", cli.memory_limit.clone()),
+        (
+            "temp_directory",
+            cli.temp_dir.as_ref().map(|dir| dir.display().to_string()),
+        ),
+    ];
+    for (name, value) in settings {
+        if let Some(value) = value {
+            conn.execute_batch(&format!("SET {name} = {}", sql::quote_literal(&value)))?;
+        }
+    }
+    let transcript = cli
+        .transcript
+        .as_deref()
+        .map(Transcript::open)
+        .transpose()?;
+    let audit = config
+        .audit_log(database.as_deref())
+        .map(|path| AuditLog::open(&path, database.as_deref().unwrap_or(":memory:").to_string()))
+        .transpose()?;
 
     // setup terminal
+    INLINE.store(cli.inline, Ordering::Relaxed);
+    restore_terminal_on_exit();
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    take_terminal()?;
+    let backend = CrosstermBackend::new(io::stdout());
+    let viewport = if cli.inline {
+        // Printed tables go above the UI, so some room has to be left.
+        let rows = terminal::size()?.1;
+        Viewport::Inline(INLINE_HEIGHT.min(rows.saturating_sub(1)).max(1))
+    } else {
+        Viewport::Fullscreen
+    };
+    let mut terminal = Terminal::with_options(backend, TerminalOptions { viewport })?;
 
     // create app and run it
-    let app = App::new(&conn);
+    let sessions = [db::clone_session(&conn)?, conn].map(|conn| Arc::new(Mutex::new(conn)));
+    let mut app = App::new(sessions, config);
+    app.inline = cli.inline;
+    app.transcript = transcript;
+    app.audit = audit;
+    // A server's connection string may hold its password.
+    let server = profile
+        .as_ref()
+        .is_some_and(|profile| profile.kind.is_some());
+    match database.as_deref() {
+        Some(_) if server => {}
+        Some(database) => app.remember_database(database),
+        // Without a database to work on, start from the recent list.
+        None if profile.is_none() && !app.recent.is_empty() && cli.execute.is_none() => {
+            app.open_recent()
+        }
+        // Nor anything opened before, so this is likely the first run.
+        None if profile.is_none() && cli.execute.is_none() && !Tutorial::seen() => {
+            app.start_tutorial()
+        }
+        None => {}
+    }
+    if let Some(sql) = &cli.execute {
+        app.execute(sql, AfterRun::Nothing);
+        app.add_to_history(sql.clone());
+    }
+    if let Some(interval) = cli.watch {
+        app.start_watch(interval);
+    }
     let res = run_app(&mut terminal, app);
 
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    if cli.inline {
+        // Leaves the prompt right below the last printed table.
+        terminal.clear()?;
+    }
+    restore_terminal()?;
 
     if let Err(err) = res {
         println!("{err:?}");
@@ -44,109 +175,705 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Switches to the alternate screen, unless drawing inline, and captures
+/// the mouse and focus changes.
+fn take_terminal() -> io::Result<()> {
+    if !INLINE.load(Ordering::Relaxed) {
+        execute!(io::stdout(), EnterAlternateScreen)?;
+    }
+    execute!(io::stdout(), EnableMouseCapture, EnableFocusChange)
+}
+
+/// Hands the terminal back to the shell: out of raw mode and the alternate
+/// screen, with mouse capture and focus changes off and the cursor shown.
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    if !INLINE.load(Ordering::Relaxed) {
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+    }
+    execute!(
+        io::stdout(),
+        DisableMouseCapture,
+        DisableFocusChange,
+        cursor::Show
+    )
+}
+
+/// Hands the terminal to `run` for as long as it takes, then takes it back
+/// and draws everything again.
+fn suspend<B: Backend, T>(terminal: &mut Terminal<B>, run: impl FnOnce() -> T) -> io::Result<T> {
+    restore_terminal()?;
+    let result = run();
+    enable_raw_mode()?;
+    take_terminal()?;
+    terminal.clear()?;
+    Ok(result)
+}
+
+/// Restores the terminal before a panic is printed, so the message shows up
+/// in a usable shell, and when civciv is told to stop by a signal.
+fn restore_terminal_on_exit() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        previous(info);
+    }));
+
+    #[cfg(unix)]
+    {
+        use signal_hook::consts::{SIGHUP, SIGQUIT, SIGTERM};
+        if let Ok(mut signals) = signal_hook::iterator::Signals::new([SIGTERM, SIGHUP, SIGQUIT]) {
+            std::thread::spawn(move || {
+                if let Some(signal) = signals.forever().next() {
+                    tracing::info!(signal, "stopped by signal");
+                    let _ = restore_terminal();
+                    std::process::exit(128 + signal);
+                }
+            });
+        }
+    }
+}
+
+/// Appends the log to `path`. Panics are logged too, since the restored
+/// terminal tends to swallow them.
+fn start_log(path: &Path, verbose: bool) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let level = if verbose { Level::DEBUG } else { Level::INFO };
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_max_level(level)
+        .init();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        tracing::error!("civciv panicked: {info}");
+        previous(info);
+    }));
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "civciv started");
+    Ok(())
+}
+
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+    let mut macros = KeyMacros::default();
+    // Keys of a macro still to be played back.
+    let mut replay = VecDeque::new();
+    // When the event the next frame answers was read, for the overlay.
+    let mut event_read: Option<Instant> = None;
     loop {
-        terminal.draw(|f| ui(f, &mut app))?;
+        app.poll_queries();
+        // The app only hands over copied text; the terminal carries it to
+        // the clipboard.
+        if let Some(text) = app.clipboard.take() {
+            if let Err(err) = clipboard::copy(&text) {
+                app.status = Some(format!("Copy failed: {err}"));
+            }
+        }
+        for text in std::mem::take(&mut app.printed) {
+            let lines = u16::try_from(text.lines().count()).unwrap_or(u16::MAX);
+            terminal.insert_before(lines, |buf| Paragraph::new(text).render(buf.area, buf))?;
+        }
+        for message in std::mem::take(&mut app.notifications) {
+            if let Err(err) = notify::send(app.config.notify, &message) {
+                app.status = Some(format!("Notification failed: {err}"));
+            }
+        }
+        if let Some(pipe) = app.pipe.take() {
+            suspend(terminal, || pipe::run(&pipe.command, &pipe.input))?
+                .unwrap_or_else(|err| app.status = Some(err));
+        }
+        // Checked on every pass, so steady typing can't hold the watch off.
+        app.watch_tick();
+        app.autosave_draft();
+        app.lint_tick();
+        app.recording = macros.recording();
+        let drawing = Instant::now();
+        terminal.draw(|f| ui::draw(f, &mut app))?;
+        if let Some(perf) = app.perf.as_mut() {
+            perf.record_frame(drawing.elapsed());
+            if let Some(read) = event_read.take() {
+                perf.record_latency(read.elapsed());
+            }
+        }
 
-        if let Event::Key(key) = event::read()? {
-            match app.input_mode {
-                InputMode::Normal => match key.code {
-                    KeyCode::Char('e') => {
-                        app.input_mode = InputMode::Editing;
-                    }
-                    KeyCode::Char('q') => {
-                        return Ok(());
+        // Each played back key waits for the query the one before it ran,
+        // and for keys typed meanwhile, which stop the macro.
+        if !app.is_running() && !replay.is_empty() && !event::poll(Duration::ZERO)? {
+            if let Some(key) = replay.pop_front() {
+                if handle_key(&mut app, key) {
+                    app.save_draft();
+                    return Ok(());
+                }
+            }
+            continue;
+        }
+
+        // Wake up on every tick, more often to pick up rows while a query
+        // runs, and when the watched query is due again. A pass without an
+        // event just draws again.
+        let timeout = [
+            Some(TICK_INTERVAL),
+            app.is_running().then_some(QUERY_POLL_INTERVAL),
+            app.watch_timeout(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(TICK_INTERVAL);
+        if !event::poll(timeout)? {
+            continue;
+        }
+
+        let event = event::read()?;
+        event_read = Some(Instant::now());
+        if let Event::Mouse(mouse) = event {
+            match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => app.click(mouse.column, mouse.row),
+                MouseEventKind::Drag(MouseButton::Left) => app.drag_column(mouse.column),
+                MouseEventKind::Up(MouseButton::Left) => app.column_drag = None,
+                MouseEventKind::ScrollDown => app.scroll_wheel(mouse.column, mouse.row, true),
+                MouseEventKind::ScrollUp => app.scroll_wheel(mouse.column, mouse.row, false),
+                _ => {}
+            }
+        }
+        match event {
+            Event::FocusGained => app.focused = true,
+            Event::FocusLost => app.focused = false,
+            _ => {}
+        }
+        if let Event::Resize(..) = event {
+            // The header edges a drag follows move with the layout. The next
+            // pass draws at the new size right away.
+            app.column_drag = None;
+            continue;
+        }
+        if let Event::Key(key) = event {
+            // Any key stops a macro that is playing back.
+            if !replay.is_empty() {
+                replay.clear();
+                app.status = Some("Stopped playing the macro".to_string());
+                continue;
+            }
+            if key.kind == KeyEventKind::Press {
+                let in_result_view = matches!(app.input_mode, InputMode::Normal);
+                match macros.intercept(key, in_result_view) {
+                    Some(Outcome::Handled(status)) => {
+                        app.status = status;
+                        continue;
                     }
-                    KeyCode::Down => {
-                        app.vertical_scroll = app.vertical_scroll.saturating_add(1);
-                        app.vertical_scroll_state =
-                            app.vertical_scroll_state.position(app.vertical_scroll);
+                    Some(Outcome::Play(keys)) => {
+                        replay.extend(keys);
+                        continue;
                     }
-                    KeyCode::Up => {
-                        if app.vertical_scroll >= 1 {
-                            app.vertical_scroll = app.vertical_scroll.saturating_sub(1);
-                            app.vertical_scroll_state =
-                                app.vertical_scroll_state.position(app.vertical_scroll);
-                        }
+                    None => {}
+                }
+            }
+            if handle_key(&mut app, key) {
+                app.save_draft();
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Acts on a key press. Returns whether civciv should quit.
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    tracing::debug!(code = ?key.code, modifiers = ?key.modifiers, "key");
+    if key.code == KeyCode::F(12) && key.kind == KeyEventKind::Press {
+        app.toggle_perf();
+        return false;
+    }
+    // Status messages only describe the previous action.
+    app.status = None;
+    // Shift with a movement key selects a block of cells.
+    let extend = key.modifiers.contains(KeyModifiers::SHIFT);
+
+    match app.input_mode {
+        InputMode::Normal => match key.code {
+            KeyCode::Char('e') => {
+                app.input_mode = InputMode::Editing;
+                app.tutorial_reached(Step::Edit);
+            }
+            KeyCode::Char('q') => {
+                return true;
+            }
+            KeyCode::Char('s') => {
+                app.show_schema = true;
+                app.input_mode = InputMode::Browsing;
+            }
+            KeyCode::Char('o') => app.open_import(),
+            KeyCode::Char('R') => app.open_recent(),
+            KeyCode::Char('C') => app.open_connections(),
+            KeyCode::Char('f') => app.open_files(),
+            KeyCode::Char(':') => app.open_command(),
+            KeyCode::Char('r') => app.rerun_last_query(),
+            KeyCode::Char('x') => app.explain_query(),
+            KeyCode::Char('X') => app.profile_query(),
+            KeyCode::Char('c') => app.open_chart(ChartKind::Line),
+            KeyCode::Char('p') => app.open_scatter(),
+            KeyCode::Char('H') => app.open_histogram(),
+            KeyCode::Char('S') => app.open_stats(app.pane().selected_column),
+            KeyCode::Char('m') => app.fetch_more(),
+            KeyCode::Char('P') => app.toggle_pin(),
+            KeyCode::Char('d') => app.toggle_diff(),
+            KeyCode::Char('w') => app.cycle_split(),
+            KeyCode::Char('v') => app.open_column_picker(),
+            KeyCode::Char('>') => app.resize_column(true),
+            KeyCode::Char('<') => app.resize_column(false),
+            KeyCode::Char('z') => app.pane_mut().toggle_wrap(),
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.open_finder(false)
+            }
+            KeyCode::Char('t') => app.show_types = !app.show_types,
+            KeyCode::Char('n') => app.show_row_numbers = !app.show_row_numbers,
+            KeyCode::Char('g') => app.prompt_goto(),
+            KeyCode::Char('/') => app.prompt_filter(),
+            KeyCode::Char('*') => app.toggle_highlight(),
+            KeyCode::Char('G') => app.count_values(),
+            KeyCode::Char('T') => app.toggle_transpose(),
+            KeyCode::Char('F') => app.clear_filters(),
+            KeyCode::PageDown => app.page_result(true),
+            KeyCode::PageUp => app.page_result(false),
+            KeyCode::Home => app.first_page(),
+            KeyCode::End => app.last_page(),
+            KeyCode::Char('+') => app.resize_editor(true),
+            KeyCode::Char('-') => app.resize_editor(false),
+            KeyCode::Tab => app.focus_other_pane(),
+            KeyCode::Down | KeyCode::Char('j') => app.move_row(true, extend),
+            KeyCode::Up | KeyCode::Char('k') => app.move_row(false, extend),
+            KeyCode::Right | KeyCode::Char('l') => app.pane_mut().select_column(true, extend),
+            KeyCode::Left | KeyCode::Char('h') => app.pane_mut().select_column(false, extend),
+            KeyCode::Char('u') => app.edit_cell(),
+            KeyCode::Char('D') => app.delete_rows(),
+            KeyCode::Char('K') => app.describe_cell(),
+            KeyCode::Char('y') => app.copy_selection(false),
+            KeyCode::Char('Y') => app.copy_selection(true),
+            KeyCode::Esc => app.pane_mut().selection_anchor = None,
+            _ => {
+                if let Some(name) = key_name(key) {
+                    app.run_bound_key(&name);
+                }
+            }
+        },
+        InputMode::Editing if key.kind == KeyEventKind::Press => {
+            // Candidates only last until the next key, which still does
+            // its usual job, so typing on narrows the word. Esc just
+            // dismisses them.
+            let dismissed = key.code != KeyCode::Tab && !app.completions.is_empty();
+            if dismissed {
+                app.completions.clear();
+            }
+            match key.code {
+                KeyCode::Tab => app.complete(),
+                KeyCode::Esc if dismissed => {}
+                KeyCode::Enter => app.submit_sql(),
+                KeyCode::Up => app.history_previous(),
+                KeyCode::Down => app.history_next(),
+                KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.describe_at_cursor()
+                }
+                KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.open_finder(true)
+                }
+                KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.format_input()
+                }
+                KeyCode::Char(to_insert) => app.type_char(to_insert),
+                KeyCode::Backspace => {
+                    app.pane_mut().delete_char();
+                }
+                KeyCode::Left => {
+                    app.pane_mut().move_cursor_left();
+                }
+                KeyCode::Right => {
+                    app.pane_mut().move_cursor_right();
+                }
+
+                KeyCode::Esc => {
+                    app.input_mode = InputMode::Normal;
+                }
+                _ => {}
+            }
+        }
+        InputMode::Editing => {}
+        InputMode::Browsing => match key.code {
+            KeyCode::Down => app.select_next_node(),
+            KeyCode::Up => app.select_previous_node(),
+            KeyCode::Right => app.schema.set_expanded(true),
+            KeyCode::Left => app.schema.set_expanded(false),
+            KeyCode::Enter => app.schema.toggle_expanded(),
+            KeyCode::Char('r') => app.refresh_schema(),
+            KeyCode::Char('i') => app.open_insert_form(),
+            KeyCode::Char('d') => app.open_ddl(),
+            KeyCode::Char('E') => app.open_diagram(),
+            KeyCode::Char('s') => {
+                app.show_schema = false;
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        },
+        InputMode::Importing if key.kind == KeyEventKind::Press => match key.code {
+            KeyCode::Tab => app.import_next_field(),
+            KeyCode::BackTab => app.import_previous_field(),
+            KeyCode::Enter => app.finish_import(),
+            KeyCode::Esc => app.close_import(),
+            KeyCode::Char(c) => {
+                if let Some(wizard) = app.import.as_mut() {
+                    wizard.enter_char(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(wizard) = app.import.as_mut() {
+                    wizard.delete_char();
+                }
+            }
+            _ => {}
+        },
+        InputMode::Importing => {}
+        InputMode::Inserting if key.kind == KeyEventKind::Press => {
+            let Some(form) = app.insert.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Tab | KeyCode::Down => form.next_field(),
+                KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+                KeyCode::Char(c) => form.enter_char(c),
+                KeyCode::Backspace => form.delete_char(),
+                KeyCode::Enter => app.preview_insert(),
+                KeyCode::Esc => app.close_insert_form(),
+                _ => {}
+            }
+        }
+        InputMode::Inserting => {}
+        InputMode::Remote if key.kind == KeyEventKind::Press => {
+            let Some(form) = app.remote.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Tab | KeyCode::Down => form.next_field(),
+                KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+                KeyCode::Char(c) => form.enter_char(c),
+                KeyCode::Backspace => form.delete_char(),
+                KeyCode::Enter => app.attach_remote(),
+                KeyCode::Esc => app.close_remote(),
+                _ => {}
+            }
+        }
+        InputMode::Remote => {}
+        InputMode::Params if key.kind == KeyEventKind::Press => {
+            let Some(form) = app.params.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Tab | KeyCode::Down => form.next_field(),
+                KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+                KeyCode::Char(c) => form.enter_char(c),
+                KeyCode::Backspace => form.delete_char(),
+                KeyCode::Enter => app.run_params(),
+                KeyCode::Esc => app.close_params(),
+                _ => {}
+            }
+        }
+        InputMode::Params => {}
+        InputMode::Pivot if key.kind == KeyEventKind::Press => {
+            let Some(form) = app.pivot.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Tab | KeyCode::Down => form.next_field(),
+                KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+                KeyCode::Right | KeyCode::Char(' ') => form.cycle(true),
+                KeyCode::Left => form.cycle(false),
+                KeyCode::Enter => app.run_pivot(),
+                KeyCode::Esc => app.close_pivot(),
+                _ => {}
+            }
+        }
+        InputMode::Pivot => {}
+        InputMode::Secrets if key.kind == KeyEventKind::Press => {
+            let Some(panel) = app.secrets.as_mut() else {
+                return false;
+            };
+            match (panel.form.as_mut(), key.code) {
+                (Some(form), KeyCode::Tab | KeyCode::Down) => form.next_field(),
+                (Some(form), KeyCode::BackTab | KeyCode::Up) => form.previous_field(),
+                (Some(form), KeyCode::Char(c)) => form.enter_char(c),
+                (Some(form), KeyCode::Backspace) => form.delete_char(),
+                (Some(_), KeyCode::Enter) => app.create_secret(),
+                (Some(_), KeyCode::Esc) => panel.form = None,
+                (Some(_), _) => {}
+                (None, KeyCode::Down) => panel.select_next(),
+                (None, KeyCode::Up) => panel.select_previous(),
+                (None, KeyCode::Char('n')) => panel.open_form(),
+                (None, KeyCode::Char('d')) => app.drop_secret(),
+                (None, KeyCode::Esc) => app.close_secrets(),
+                (None, _) => {}
+            }
+        }
+        InputMode::Secrets => {}
+        InputMode::Settings if key.kind == KeyEventKind::Press => {
+            let Some(panel) = app.settings.as_mut() else {
+                return false;
+            };
+            if let Some(value) = panel.edit.as_mut() {
+                match key.code {
+                    KeyCode::Char(c) => value.push(c),
+                    KeyCode::Backspace => {
+                        value.pop();
                     }
+                    KeyCode::Enter => app.apply_setting(),
+                    KeyCode::Esc => panel.edit = None,
                     _ => {}
-                },
-                InputMode::Editing if key.kind == KeyEventKind::Press => match key.code {
-                    KeyCode::Enter => app.submit_sql(),
-                    KeyCode::Char(to_insert) => {
-                        app.enter_char(to_insert);
-                    }
+                }
+                return false;
+            }
+            if panel.editing_filter {
+                let mut filter = panel.filter.clone();
+                match key.code {
+                    KeyCode::Char(c) => filter.push(c),
                     KeyCode::Backspace => {
-                        app.delete_char();
+                        filter.pop();
                     }
-                    KeyCode::Left => {
-                        app.move_cursor_left();
-                    }
-                    KeyCode::Right => {
-                        app.move_cursor_right();
+                    KeyCode::Enter | KeyCode::Esc => panel.editing_filter = false,
+                    _ => {}
+                }
+                panel.set_filter(filter);
+                return false;
+            }
+            match key.code {
+                KeyCode::Down => panel.select_next(),
+                KeyCode::Up => panel.select_previous(),
+                KeyCode::Char('/') => panel.editing_filter = true,
+                KeyCode::Enter => panel.start_edit(),
+                KeyCode::Esc => app.close_settings(),
+                _ => {}
+            }
+        }
+        InputMode::Settings => {}
+        InputMode::Files if key.kind == KeyEventKind::Press => {
+            let Some(browser) = app.files.as_mut() else {
+                return false;
+            };
+            if browser.editing_filter {
+                match key.code {
+                    KeyCode::Char(c) => browser.filter.push(c),
+                    KeyCode::Backspace => {
+                        browser.filter.pop();
                     }
-
-                    KeyCode::Esc => {
-                        app.input_mode = InputMode::Normal;
+                    KeyCode::Enter | KeyCode::Esc => {
+                        browser.editing_filter = false;
+                        if let Err(err) = browser.refresh() {
+                            app.status = Some(err.to_string());
+                        }
                     }
                     _ => {}
-                },
-                InputMode::Editing => {}
+                }
+                return false;
+            }
+            match key.code {
+                KeyCode::Down => browser.select_next(),
+                KeyCode::Up => browser.select_previous(),
+                KeyCode::Char('/') => browser.editing_filter = true,
+                KeyCode::Enter | KeyCode::Right => app.files_open_selected(),
+                KeyCode::Backspace | KeyCode::Left => app.files_parent(),
+                KeyCode::Char('f') => app.close_files(),
+                KeyCode::Esc => app.input_mode = InputMode::Normal,
+                _ => {}
             }
         }
+        InputMode::Files => {}
+        InputMode::Command if key.kind == KeyEventKind::Press => match key.code {
+            KeyCode::Enter => app.run_command(),
+            KeyCode::Char(c) => app.command_input.push(c),
+            KeyCode::Backspace => {
+                app.command_input.pop();
+            }
+            KeyCode::Esc => app.input_mode = InputMode::Normal,
+            _ => {}
+        },
+        InputMode::Command => {}
+        InputMode::CellEdit if key.kind == KeyEventKind::Press => {
+            let Some(edit) = app.cell_edit.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Enter => app.preview_cell_edit(),
+                KeyCode::Char(c) => edit.value.push(c),
+                KeyCode::Backspace => {
+                    edit.value.pop();
+                }
+                KeyCode::Esc => app.cancel_cell_edit(),
+                _ => {}
+            }
+        }
+        InputMode::CellEdit => {}
+        InputMode::Confirm => match key.code {
+            KeyCode::Enter | KeyCode::Char('y') => app.run_pending(),
+            KeyCode::Esc | KeyCode::Char('n') => app.cancel_pending(),
+            _ => {}
+        },
+        InputMode::Plan => {
+            let Some(plan) = app.plan.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Down => plan.select_next(),
+                KeyCode::Up => plan.select_previous(),
+                KeyCode::Right => plan.set_expanded(true),
+                KeyCode::Left => plan.set_expanded(false),
+                KeyCode::Enter => plan.toggle_expanded(),
+                KeyCode::Esc | KeyCode::Char('x') => app.close_plan(),
+                _ => {}
+            }
+        }
+        InputMode::Profile => {
+            let Some(profile) = app.profile.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Down => profile.select_next(),
+                KeyCode::Up => profile.select_previous(),
+                KeyCode::Char('o') => profile.cycle_sort(),
+                KeyCode::Esc | KeyCode::Char('X') => app.close_profile(),
+                _ => {}
+            }
+        }
+        InputMode::Chart => match key.code {
+            KeyCode::Right => app.cycle_chart_column(true, true),
+            KeyCode::Left => app.cycle_chart_column(true, false),
+            KeyCode::Up => app.cycle_chart_column(false, true),
+            KeyCode::Down => app.cycle_chart_column(false, false),
+            KeyCode::Char('+') => app.resize_histogram(true),
+            KeyCode::Char('-') => app.resize_histogram(false),
+            KeyCode::Esc | KeyCode::Char('c' | 'p' | 'H') => app.close_chart(),
+            _ => {}
+        },
+        InputMode::Finder if key.kind == KeyEventKind::Press => {
+            let Some(finder) = app.finder.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Down => finder.select_next(),
+                KeyCode::Up => finder.select_previous(),
+                KeyCode::Enter => app.insert_found(),
+                KeyCode::Tab => app.preview_found(),
+                KeyCode::Esc => app.close_finder(),
+                KeyCode::Char(c) => finder.enter_char(c),
+                KeyCode::Backspace => finder.delete_char(),
+                _ => {}
+            }
+        }
+        InputMode::Finder => {}
+        InputMode::PluginPane => {
+            let Some(pane) = app.plugin_pane.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Down => pane.scroll = pane.scroll.saturating_add(1),
+                KeyCode::Up => pane.scroll = pane.scroll.saturating_sub(1),
+                KeyCode::Esc => app.close_plugin_pane(),
+                _ => {}
+            }
+        }
+        InputMode::Diagram => {
+            let Some(diagram) = app.diagram.as_mut() else {
+                return false;
+            };
+            let (rows, columns) = &mut diagram.scroll;
+            match key.code {
+                KeyCode::Down => *rows = rows.saturating_add(1),
+                KeyCode::Up => *rows = rows.saturating_sub(1),
+                KeyCode::Right => *columns = columns.saturating_add(4),
+                KeyCode::Left => *columns = columns.saturating_sub(4),
+                KeyCode::Esc | KeyCode::Char('E') => app.close_diagram(),
+                _ => {}
+            }
+        }
+        InputMode::Describe => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+                app.close_describe();
+            }
+        }
+        InputMode::Ddl => {
+            let Some(ddl) = app.ddl.as_mut() else {
+                return false;
+            };
+            match key.code {
+                KeyCode::Down => ddl.scroll = ddl.scroll.saturating_add(1),
+                KeyCode::Up => ddl.scroll = ddl.scroll.saturating_sub(1),
+                KeyCode::Char('y') => app.copy_ddl(),
+                KeyCode::Esc | KeyCode::Char('d') => app.close_ddl(),
+                _ => {}
+            }
+        }
+        InputMode::Stats => match key.code {
+            KeyCode::Right => app.cycle_stats_column(true),
+            KeyCode::Left => app.cycle_stats_column(false),
+            KeyCode::Esc | KeyCode::Char('S') => app.close_stats(),
+            _ => {}
+        },
+        InputMode::Columns => match key.code {
+            KeyCode::Down => app.select_next_column(),
+            KeyCode::Up => app.select_previous_column(),
+            KeyCode::Char(' ') | KeyCode::Enter => app.toggle_column(),
+            KeyCode::Esc | KeyCode::Char('v') => app.input_mode = InputMode::Normal,
+            _ => {}
+        },
+        InputMode::Connections => match key.code {
+            KeyCode::Down => app.select_next_connection(),
+            KeyCode::Up => app.select_previous_connection(),
+            KeyCode::Enter => app.choose_connection(),
+            KeyCode::Esc => app.input_mode = InputMode::Normal,
+            _ => {}
+        },
+        InputMode::Jobs => match key.code {
+            KeyCode::Down => app.select_next_job(),
+            KeyCode::Up => app.select_previous_job(),
+            KeyCode::Enter => app.choose_job(),
+            KeyCode::Char('x') | KeyCode::Delete => app.cancel_job(),
+            KeyCode::Esc => app.input_mode = InputMode::Normal,
+            _ => {}
+        },
+        InputMode::Templates => match key.code {
+            KeyCode::Down => app.select_next_template(),
+            KeyCode::Up => app.select_previous_template(),
+            KeyCode::Enter => app.choose_template(),
+            KeyCode::Esc => app.input_mode = InputMode::Normal,
+            _ => {}
+        },
+        InputMode::Recent => match key.code {
+            KeyCode::Down => app.select_next_recent(),
+            KeyCode::Up => app.select_previous_recent(),
+            KeyCode::Enter => app.choose_recent(),
+            KeyCode::Esc => app.input_mode = InputMode::Normal,
+            _ => {}
+        },
     }
+    false
 }
 
-fn ui(frame: &mut Frame, app: &mut App) {
-    let vertical = Layout::vertical([Constraint::Length(3), Constraint::Min(1)]);
-
-    let [sql_area, chart_area] = vertical.areas(frame.size());
-
-    let input = Paragraph::new(app.input.as_str())
-        .style(match app.input_mode {
-            InputMode::Normal => Style::default(),
-            InputMode::Editing => Style::default().fg(Color::Yellow),
-        })
-        .block(Block::default().borders(Borders::ALL).title("SQL"));
-
-    frame.render_widget(input, sql_area);
-
-    let table = app.data_to_table().unwrap().to_string();
-
-    // let table = app.data_to_table().unwrap();
-    // let mut rows = vec![];
-    // let mut colcount = 0;
-    // table.row_iter().for_each(|row| {
-    //     let mut cells: Vec<String> = vec![];
-    //     row.cell_iter().for_each(|cell| {
-    //         cells.push(cell.content());
-    //     });
-
-    //     colcount = cells.len();
-    //     rows.push(Row::new(cells));
-    // });
-
-    // let widths: Vec<Constraint> = (0..colcount).map(|_| Constraint::Length(4)).collect();
-
-    // let table = Table::new(rows, widths)
-    //     .block(Block::default().title("Table"))
-    //     .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
-    //     .highlight_symbol(">>");
-
-    // frame.render_widget(table, chart_area);
-
-    app.vertical_scroll_state = app.vertical_scroll_state.content_length(table.len());
-
-    let pretty_table = Paragraph::new(table)
-        .scroll((app.vertical_scroll as u16, 0))
-        .block(Block::default().borders(Borders::ALL).title("Result"));
-
-    frame.render_widget(pretty_table, chart_area);
-    frame.render_stateful_widget(
-        Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(Some("↑"))
-            .end_symbol(Some("↓")),
-        chart_area,
-        &mut app.vertical_scroll_state,
-    )
+/// Names a key the way plugins bind it, such as `F5`, `ctrl+r` or `#`.
+fn key_name(key: KeyEvent) -> Option<String> {
+    let name = match key.code {
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        _ => return None,
+    };
+    let mut prefix = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        prefix.push_str("ctrl+");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        prefix.push_str("alt+");
+    }
+    Some(prefix + &name)
 }
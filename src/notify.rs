@@ -0,0 +1,19 @@
+use std::io::{self, Write};
+
+use civciv_core::config::Notify;
+
+/// Passes a message on through the terminal, by ringing the bell or asking
+/// for a desktop notification with the OSC 9 escape sequence, which iTerm2,
+/// kitty, WezTerm and Windows Terminal show. Others ignore it.
+pub fn send(how: Notify, message: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    if matches!(how, Notify::Bell | Notify::Both) {
+        write!(stdout, "\x07")?;
+    }
+    if matches!(how, Notify::Desktop | Notify::Both) {
+        // A control character would end the sequence early.
+        let message: String = message.chars().filter(|c| !c.is_control()).collect();
+        write!(stdout, "\x1b]9;{message}\x07")?;
+    }
+    stdout.flush()
+}
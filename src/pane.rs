@@ -0,0 +1,399 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::RangeInclusive,
+    path::PathBuf,
+    time::Instant,
+};
+
+use arrow::array::RecordBatch;
+use ratatui::{layout::Rect, widgets::ScrollbarState};
+
+use crate::{grid, lint::Lint, sql, stream::QueryStream};
+
+/// Paging state of a query that was run with the automatic row limit.
+#[derive(Clone)]
+pub struct Page {
+    pub sql: String,
+    /// Rows of the result before the ones held, dropped to keep the window
+    /// under `max_window_rows`.
+    pub start: usize,
+    pub fetched: usize,
+    /// Whether the last page was full, so more rows may follow.
+    pub more: bool,
+}
+
+/// The separator after a result column's header as last drawn, where the
+/// mouse can drag to resize the column.
+#[derive(Clone, Copy)]
+pub struct HeaderEdge {
+    pub column: usize,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+}
+
+/// The column a pane's query is sorted by, added as an `ORDER BY` around it
+/// each time it is read.
+#[derive(Clone)]
+pub struct Sort {
+    pub column: String,
+    pub descending: bool,
+}
+
+/// What to do once a statement has delivered its whole result.
+pub enum AfterRun {
+    Nothing,
+    /// Clear the editor, unless it was changed from this text while the
+    /// query ran.
+    ClearInput(String),
+    /// Mark the rows that differ from the previous run of a watched query.
+    MarkChanges(Vec<String>),
+    /// Load the schema browser again, for statements that create tables.
+    RefreshSchema,
+}
+
+/// What to do once a job is done.
+pub enum JobDone {
+    /// Show a message, like `Exported to out.csv`.
+    Status(String),
+    /// Show the profile the query wrote to this file.
+    Profile(PathBuf),
+    /// Show what it reported last, like a benchmark's timings.
+    Report,
+}
+
+/// A statement run on a pane's session in the background that leaves its
+/// result alone, such as an export.
+pub struct Job {
+    pub stream: QueryStream,
+    pub sql: String,
+    /// What it is doing, like `Exporting to out.csv`.
+    pub label: String,
+    /// Rows it returned so far, which aren't kept.
+    pub rows: usize,
+    /// What it reported last, like how many runs of a benchmark are done.
+    pub report: Option<String>,
+    pub then: JobDone,
+}
+
+/// A query or job that was cancelled, kept until its thread has let go of
+/// the pane's session.
+pub struct Cancelling {
+    pub stream: QueryStream,
+    pub sql: String,
+}
+
+/// A result as it was before it was transposed, to be put back.
+pub struct Untransposed {
+    pub data: Vec<RecordBatch>,
+    pub source: Option<String>,
+    pub page: Option<Page>,
+}
+
+/// Where a page goes in the rows already held.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Place {
+    Replace,
+    Append,
+    /// Put in front, paging back to rows dropped from the window.
+    Prepend,
+}
+
+/// A page of a query being read on the background thread.
+pub struct Running {
+    pub stream: QueryStream,
+    /// The query as entered, before the row limit was applied.
+    pub sql: String,
+    pub offset: usize,
+    /// Rows asked for, or 0 for all of them.
+    pub limit: usize,
+    pub place: Place,
+    /// Batches of a page to prepend, held until it is complete.
+    pub incoming: Vec<RecordBatch>,
+    /// Rows received so far, including the extra one that tells whether
+    /// another page follows.
+    pub rows: usize,
+    /// Memory taken by the batches received so far, kept or not.
+    pub bytes: usize,
+    pub then: AfterRun,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SplitLayout {
+    SideBySide,
+    Stacked,
+}
+
+/// A SQL editor and the result of the statements run from it. The split
+/// layout shows two of them at once, each running its queries on its own.
+#[derive(Default)]
+pub struct Pane {
+    pub input: String,
+    pub cursor_position: usize,
+    pub data: Vec<RecordBatch>,
+    /// The statement `data` came from, or `None` when it wasn't read by one,
+    /// such as an import preview.
+    pub source: Option<String>,
+    pub vertical_scroll_state: ScrollbarState,
+    pub vertical_scroll: usize,
+    /// Columns scrolled past, not counting the first one, which stays put.
+    pub horizontal_scroll: usize,
+    pub horizontal_scroll_state: ScrollbarState,
+    /// Names of the result columns left out of the grid.
+    pub hidden_columns: HashSet<String>,
+    /// Widths set by hand, by column name.
+    pub column_widths: HashMap<String, u16>,
+    pub header_edges: Vec<HeaderEdge>,
+    /// Where the editor and the result were last drawn, for mouse clicks.
+    pub editor_area: Rect,
+    pub result_area: Rect,
+    /// Rows the result had room for when last drawn, which make a page.
+    pub page_rows: usize,
+    pub sort: Option<Sort>,
+    /// A value whose cells are highlighted wherever they are, as shown.
+    pub highlight: Option<String>,
+    /// Regexes result columns must match, as text, added as a `WHERE`
+    /// around the query each time it is read.
+    pub filters: BTreeMap<String, String>,
+    /// Names of the columns whose long values wrap instead of being cut off.
+    pub wrapped_columns: HashSet<String>,
+    /// Row of the cell cursor.
+    pub selected_row: usize,
+    /// Column of the cell cursor, which width changes apply to.
+    pub selected_column: usize,
+    /// Row and column where a range selection started; the cell cursor is
+    /// its other corner.
+    pub selection_anchor: Option<(usize, usize)>,
+    /// Rows that differ from the previous run of a watched query.
+    pub changed_rows: HashSet<usize>,
+    pub page: Option<Page>,
+    pub running: Option<Running>,
+    pub job: Option<Job>,
+    pub cancelling: Option<Cancelling>,
+    /// The result shown before it was transposed, while it is.
+    pub untransposed: Option<Untransposed>,
+    /// Position in the history while stepping through it in the editor.
+    pub history_index: Option<usize>,
+    /// The statement last run from this pane.
+    pub last_query: Option<String>,
+    /// Values bound to the placeholders of `last_query`.
+    pub params: Vec<Option<String>>,
+    /// What the lint found in `linted`, the input as it was last checked.
+    pub lints: Vec<Lint>,
+    pub linted: String,
+}
+
+impl Pane {
+    pub fn insert_text(&mut self, text: &str) {
+        let cursor = self.clamp_cursor(self.cursor_position);
+        self.input.insert_str(cursor, text);
+        self.cursor_position = cursor + text.len();
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        let cursor_moved_left = self.cursor_position.saturating_sub(10);
+        self.cursor_position = self.clamp_cursor(cursor_moved_left);
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        let cursor_moved_right = self.cursor_position.saturating_add(10);
+        self.cursor_position = self.clamp_cursor(cursor_moved_right);
+    }
+
+    pub fn enter_char(&mut self, new_char: char) {
+        self.input.insert(self.cursor_position, new_char);
+
+        self.move_cursor_right();
+    }
+
+    /// Types `c`, adding the closer right after an opening bracket or
+    /// quote, and stepping over a closer that is already next instead of
+    /// typing another.
+    pub fn enter_char_paired(&mut self, c: char) {
+        let cursor = self.clamp_cursor(self.cursor_position);
+        if !self.input.is_char_boundary(cursor) {
+            self.enter_char(c);
+            return;
+        }
+        let before = self.input[..cursor].chars().next_back();
+        let after = self.input[cursor..].chars().next();
+        if matches!(c, ')' | ']' | '\'' | '"') && after == Some(c) {
+            self.cursor_position = cursor + c.len_utf8();
+            return;
+        }
+        let closer = match c {
+            '(' => Some(')'),
+            '[' => Some(']'),
+            // A quote right after a word is an apostrophe or ends a string.
+            '\'' | '"' if !before.is_some_and(|b| b.is_alphanumeric() || b == c) => Some(c),
+            _ => None,
+        };
+        // Only before a space or the end, so typing in front of a word
+        // doesn't leave a stray closer.
+        let room = after.is_none_or(|a| a.is_whitespace() || matches!(a, ')' | ']' | ',' | ';'));
+        match closer {
+            Some(closer) if room => {
+                self.input.insert(cursor, closer);
+                self.input.insert(cursor, c);
+                self.cursor_position = cursor + c.len_utf8();
+            }
+            _ => self.enter_char(c),
+        }
+    }
+
+    /// Line and column of the cursor in the editor, counting from 1.
+    pub fn cursor_line_column(&self) -> (usize, usize) {
+        let mut cursor = self.clamp_cursor(self.cursor_position);
+        while !self.input.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        let before = &self.input[..cursor];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+
+    /// Uppercases the keyword right before the cursor, once it is typed
+    /// out.
+    pub fn uppercase_keyword(&mut self) {
+        let cursor = self.clamp_cursor(self.cursor_position);
+        if !self.input.is_char_boundary(cursor) {
+            return;
+        }
+        if let Some(range) = sql::keyword_before(&self.input, cursor) {
+            let upper = self.input[range.clone()].to_ascii_uppercase();
+            self.input.replace_range(range, &upper);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        let is_not_cursor_leftmost = self.cursor_position != 0;
+        if is_not_cursor_leftmost {
+            // Method "remove" is not used on the saved text for deleting the selected char.
+            // Reason: Using remove on String works on bytes instead of the chars.
+            // Using remove would require special care because of char boundaries.
+
+            let current_index = self.cursor_position;
+            let from_left_to_current_index = current_index - 1;
+
+            // Getting all characters before the selected character.
+            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
+            // Getting all characters after selected character.
+            let after_char_to_delete = self.input.chars().skip(current_index);
+
+            // Put all characters together except the selected one.
+            // By leaving the selected one out, it is forgotten and therefore deleted.
+            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
+            self.move_cursor_left();
+        }
+    }
+
+    pub fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.input.len())
+    }
+
+    pub fn reset_cursor(&mut self) {
+        self.cursor_position = 0;
+    }
+
+    /// Moves the cell cursor a row down or up, extending the range selection
+    /// when `extend` is set and dropping it otherwise. The grid scrolls to
+    /// follow it.
+    pub fn select_row(&mut self, forward: bool, extend: bool) {
+        self.anchor_selection(extend);
+        self.selected_row = if forward {
+            self.selected_row.saturating_add(1)
+        } else {
+            self.selected_row.saturating_sub(1)
+        };
+    }
+
+    /// Moves the cell cursor to the next or previous shown column, extending
+    /// the range selection when `extend` is set and dropping it otherwise.
+    /// The grid scrolls sideways to follow it.
+    pub fn select_column(&mut self, forward: bool, extend: bool) {
+        self.anchor_selection(extend);
+        let header = grid::header(&self.data);
+        let shown: Vec<usize> = (0..header.len())
+            .filter(|&c| !self.hidden_columns.contains(&header[c]))
+            .collect();
+        let position = shown.iter().position(|&c| c == self.selected_column);
+        let next = match position {
+            Some(p) if forward => shown.get(p + 1),
+            Some(p) => p.checked_sub(1).and_then(|p| shown.get(p)),
+            None => shown.first(),
+        };
+        if let Some(&next) = next {
+            self.selected_column = next;
+        }
+    }
+
+    fn anchor_selection(&mut self, extend: bool) {
+        if !extend {
+            self.selection_anchor = None;
+        } else if self.selection_anchor.is_none() {
+            self.selection_anchor = Some((self.selected_row, self.selected_column));
+        }
+    }
+
+    /// Rows and columns of the selected block, or of the cell under the
+    /// cursor when nothing is selected.
+    pub fn selection(&self) -> (RangeInclusive<usize>, RangeInclusive<usize>) {
+        let (row, column) = self
+            .selection_anchor
+            .unwrap_or((self.selected_row, self.selected_column));
+        (
+            row.min(self.selected_row)..=row.max(self.selected_row),
+            column.min(self.selected_column)..=column.max(self.selected_column),
+        )
+    }
+
+    /// Switches the selected column between wrapping and cutting off long
+    /// values.
+    pub fn toggle_wrap(&mut self) {
+        let header = grid::header(&self.data);
+        if let Some(name) = header.get(self.selected_column) {
+            if !self.wrapped_columns.remove(name) {
+                self.wrapped_columns.insert(name.clone());
+            }
+        }
+    }
+
+    /// When the query or job holding the pane's session started.
+    pub fn busy_since(&self) -> Option<Instant> {
+        let running = self.running.as_ref().map(|running| &running.stream);
+        let job = self.job.as_ref().map(|job| &job.stream);
+        let cancelling = self
+            .cancelling
+            .as_ref()
+            .map(|cancelling| &cancelling.stream);
+        running.or(job).or(cancelling).map(|stream| stream.started)
+    }
+
+    /// Rows of the running query received so far.
+    pub fn rows_so_far(&self) -> Option<usize> {
+        self.running.as_ref().map(|running| {
+            let rows = match running.limit {
+                0 => running.rows,
+                limit => running.rows.min(limit),
+            };
+            running.offset + rows
+        })
+    }
+
+    /// The page the cursor is on and how many pages the rows fetched make,
+    /// counting from 1.
+    pub fn page_position(&self) -> (usize, usize) {
+        let size = self.page_rows.max(1);
+        let fetched = self.first_row() + self.data.iter().map(|b| b.num_rows()).sum::<usize>();
+        let current = (self.first_row() + self.selected_row) / size + 1;
+        (current, fetched.div_ceil(size).max(1))
+    }
+
+    /// Number in the whole result of the first row held.
+    pub fn first_row(&self) -> usize {
+        self.page.as_ref().map_or(0, |page| page.start)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{pane::AfterRun, sql};
+
+pub struct Param {
+    /// `id` for `$id`, `1` for `$1`, or `?1` for the first `?`.
+    pub name: String,
+    pub value: String,
+}
+
+/// What the placeholder prompt does with the values typed.
+pub enum Submit {
+    /// Binds them to the query and runs it.
+    Run(AfterRun),
+    /// Writes them into a template, which is left in the editor.
+    Fill,
+}
+
+/// State of the prompt for the values of a query's placeholders, which are
+/// bound to it rather than written into it unless it is a template.
+pub struct ParamsForm {
+    pub pane: usize,
+    /// The query with its placeholders numbered as `$1`, `$2` and so on.
+    pub sql: String,
+    pub params: Vec<Param>,
+    pub selected: usize,
+    pub submit: Submit,
+}
+
+impl ParamsForm {
+    pub fn next_field(&mut self) {
+        self.selected = (self.selected + 1) % self.params.len().max(1);
+    }
+
+    pub fn previous_field(&mut self) {
+        let len = self.params.len().max(1);
+        self.selected = (self.selected + len - 1) % len;
+    }
+
+    pub fn enter_char(&mut self, c: char) {
+        if let Some(param) = self.params.get_mut(self.selected) {
+            param.value.push(c);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        if let Some(param) = self.params.get_mut(self.selected) {
+            param.value.pop();
+        }
+    }
+
+    /// The values to bind, with `NULL` binding a NULL.
+    pub fn values(&self) -> Vec<Option<String>> {
+        self.params
+            .iter()
+            .map(|param| {
+                Some(param.value.clone()).filter(|value| !value.eq_ignore_ascii_case("null"))
+            })
+            .collect()
+    }
+
+    /// The query with the values written in as string literals, which
+    /// DuckDB casts to what they are compared with, and `NULL` as is.
+    pub fn fill(&self) -> String {
+        let values = self.values();
+        sql::replace_placeholders(&self.sql, |number| {
+            let value = number
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| values.get(number.checked_sub(1)?));
+            match value {
+                Some(Some(value)) => sql::quote_literal(value),
+                _ => "NULL".to_string(),
+            }
+        })
+    }
+}
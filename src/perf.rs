@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+/// Timings shown by the performance overlay, recorded by the frontend as it
+/// draws frames and handles events.
+#[derive(Default)]
+pub struct PerfStats {
+    pub frames: u64,
+    pub frame: Duration,
+    pub worst_frame: Duration,
+    /// From reading an event to the frame that shows what it did.
+    pub latency: Duration,
+    pub worst_latency: Duration,
+}
+
+impl PerfStats {
+    pub fn record_frame(&mut self, time: Duration) {
+        self.frames += 1;
+        self.frame = time;
+        self.worst_frame = self.worst_frame.max(time);
+    }
+
+    pub fn record_latency(&mut self, time: Duration) {
+        self.latency = time;
+        self.worst_latency = self.worst_latency.max(time);
+    }
+}
+
+/// A byte count in the largest unit that keeps it above 1, like `12.3 MiB`.
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,38 @@
+use std::{
+    io::{self, BufRead, Write},
+    process::{Command, Stdio},
+};
+
+/// Runs `command` in the shell with `input` on its stdin, on the terminal
+/// civciv gave up for it. Once it exits, waits for Enter so output that
+/// didn't go through a pager can still be read.
+pub fn run(command: &str, input: &[u8]) -> Result<(), String> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("{command}: {err}"))?;
+    // A pager quit before reading everything closes the pipe early.
+    let written = match child.stdin.take().map(|mut stdin| stdin.write_all(input)) {
+        Some(Err(err)) if err.kind() != io::ErrorKind::BrokenPipe => Err(err),
+        _ => Ok(()),
+    };
+    let status = child.wait();
+
+    print!("\nPress Enter to return to civciv");
+    let _ = io::stdout().flush();
+    let _ = io::stdin().lock().read_line(&mut String::new());
+    let status = written
+        .and(status)
+        .map_err(|err| format!("{command}: {err}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{command} exited with {status}"))
+    }
+}
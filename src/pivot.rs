@@ -0,0 +1,93 @@
+use crate::sql::{quote_identifier, quote_literal, subquery};
+
+/// Most distinct values the pivot column may have, each becoming a column.
+pub const MAX_COLUMNS: usize = 100;
+
+const AGGREGATES: [&str; 5] = ["sum", "count", "avg", "min", "max"];
+
+const LABELS: [&str; 4] = ["Rows", "Columns", "Values", "Aggregate"];
+
+/// State of the form that picks the columns of a result to pivot on. Each
+/// field cycles through the result's columns, or the aggregates.
+pub struct PivotForm {
+    pub columns: Vec<String>,
+    /// The column whose values label the rows, the one whose values become
+    /// columns and the one aggregated into the cells.
+    pub choices: [usize; 3],
+    pub aggregate: usize,
+    pub selected: usize,
+}
+
+impl PivotForm {
+    /// Starts with the first column as the rows, `on` as the columns and
+    /// the last column as the values.
+    pub fn new(columns: Vec<String>, on: usize) -> Self {
+        let last = columns.len().saturating_sub(1);
+        Self {
+            columns,
+            choices: [0, on.min(last), last],
+            aggregate: 0,
+            selected: 0,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.selected = (self.selected + 1) % LABELS.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        self.selected = (self.selected + LABELS.len() - 1) % LABELS.len();
+    }
+
+    /// Moves the selected field on to the next column or aggregate.
+    pub fn cycle(&mut self, forward: bool) {
+        let (choice, len) = match self.selected {
+            3 => (&mut self.aggregate, AGGREGATES.len()),
+            i => (&mut self.choices[i], self.columns.len()),
+        };
+        let len = len.max(1);
+        *choice = if forward {
+            (*choice + 1) % len
+        } else {
+            (*choice + len - 1) % len
+        };
+    }
+
+    /// Each field's label and what it is set to.
+    pub fn fields(&self) -> Vec<(&'static str, &str)> {
+        let column = |i: usize| self.columns.get(i).map_or("", String::as_str);
+        vec![
+            (LABELS[0], column(self.choices[0])),
+            (LABELS[1], column(self.choices[1])),
+            (LABELS[2], column(self.choices[2])),
+            (LABELS[3], AGGREGATES[self.aggregate]),
+        ]
+    }
+
+    /// The query for the pivot column's values, as text, one more than
+    /// [`MAX_COLUMNS`] at most so too many can be told.
+    pub fn values_sql(&self, source: &str) -> String {
+        let on = quote_identifier(self.fields()[1].1);
+        format!(
+            "SELECT DISTINCT CAST({on} AS VARCHAR) FROM {} WHERE {on} IS NOT NULL ORDER BY 1 LIMIT {}",
+            subquery(source),
+            MAX_COLUMNS + 1
+        )
+    }
+
+    /// The `PIVOT` over `source`, with the pivot column's `values` listed
+    /// so it is a single statement DuckDB can prepare.
+    pub fn sql(&self, source: &str, values: &[String]) -> String {
+        let fields = self.fields();
+        let values: Vec<String> = values.iter().map(|value| quote_literal(value)).collect();
+        format!(
+            "PIVOT {}\nON {} IN ({})\nUSING {}({})\nGROUP BY {rows}\nORDER BY {rows}",
+            subquery(source),
+            quote_identifier(fields[1].1),
+            values.join(", "),
+            fields[3].1,
+            quote_identifier(fields[2].1),
+            rows = quote_identifier(fields[0].1)
+        )
+    }
+}
@@ -0,0 +1,175 @@
+use std::error::Error;
+
+use duckdb::Connection;
+use ratatui::widgets::ListState;
+use serde_json::Value;
+
+pub struct PlanNode {
+    pub name: String,
+    pub details: Vec<(String, String)>,
+    pub estimated_cardinality: Option<String>,
+    pub children: Vec<PlanNode>,
+    pub expanded: bool,
+}
+
+impl PlanNode {
+    fn from_json(value: &Value) -> Self {
+        let name = ["name", "operator_name", "operator_type"]
+            .iter()
+            .find_map(|key| value.get(key).and_then(Value::as_str))
+            .unwrap_or("?")
+            .trim()
+            .to_string();
+
+        let mut details = Vec::new();
+        let mut estimated_cardinality = None;
+        match value.get("extra_info") {
+            Some(Value::Object(info)) => {
+                for (key, value) in info {
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        Value::Array(items) => items
+                            .iter()
+                            .map(|item| {
+                                item.as_str()
+                                    .map_or_else(|| item.to_string(), str::to_string)
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        other => other.to_string(),
+                    };
+                    if key == "Estimated Cardinality" {
+                        estimated_cardinality = Some(value);
+                    } else {
+                        details.push((key.clone(), value));
+                    }
+                }
+            }
+            // Older versions render the extra info as one pre-formatted string.
+            Some(Value::String(info)) => {
+                for line in info.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                    match line.strip_prefix("EC:") {
+                        Some(ec) => estimated_cardinality = Some(ec.trim().to_string()),
+                        None if line.chars().all(|c| c == '-') => {}
+                        None => details.push((String::new(), line.to_string())),
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let children = value
+            .get("children")
+            .and_then(Value::as_array)
+            .map(|children| children.iter().map(PlanNode::from_json).collect())
+            .unwrap_or_default();
+
+        Self {
+            name,
+            details,
+            estimated_cardinality,
+            children,
+            expanded: true,
+        }
+    }
+}
+
+/// The operator tree of `EXPLAIN (FORMAT JSON)`, shown as a collapsible list.
+pub struct PlanView {
+    pub roots: Vec<PlanNode>,
+    pub state: ListState,
+}
+
+/// A displayed row of the tree: the path of child indices from a root, and
+/// its depth for indentation.
+pub struct PlanRow {
+    pub path: Vec<usize>,
+    pub depth: usize,
+}
+
+impl PlanView {
+    pub fn explain(db: &Connection, sql: &str) -> Result<Self, Box<dyn Error>> {
+        let json: String = db.query_row(&format!("EXPLAIN (FORMAT JSON) {sql}"), [], |row| {
+            row.get(1)
+        })?;
+        let value: Value = serde_json::from_str(&json)?;
+
+        let roots = match &value {
+            Value::Array(nodes) => nodes.iter().map(PlanNode::from_json).collect(),
+            node => vec![PlanNode::from_json(node)],
+        };
+
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Ok(Self { roots, state })
+    }
+
+    pub fn rows(&self) -> Vec<PlanRow> {
+        fn visit(node: &PlanNode, path: &mut Vec<usize>, rows: &mut Vec<PlanRow>) {
+            rows.push(PlanRow {
+                path: path.clone(),
+                depth: path.len() - 1,
+            });
+            if node.expanded {
+                for (i, child) in node.children.iter().enumerate() {
+                    path.push(i);
+                    visit(child, path, rows);
+                    path.pop();
+                }
+            }
+        }
+
+        let mut rows = Vec::new();
+        for (i, root) in self.roots.iter().enumerate() {
+            visit(root, &mut vec![i], &mut rows);
+        }
+        rows
+    }
+
+    pub fn node(&self, path: &[usize]) -> Option<&PlanNode> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.roots.get(*first)?, |node, i| node.children.get(*i))
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut PlanNode> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.roots.get_mut(*first)?, |node, i| {
+                node.children.get_mut(*i)
+            })
+    }
+
+    pub fn selected(&self) -> Option<&PlanNode> {
+        let rows = self.rows();
+        let row = rows.get(self.state.selected()?)?;
+        self.node(&row.path)
+    }
+
+    pub fn select_next(&mut self) {
+        let last = self.rows().len().saturating_sub(1);
+        let next = self.state.selected().map_or(0, |i| i + 1);
+        self.state.select(Some(next.min(last)));
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(previous));
+    }
+
+    pub fn set_expanded(&mut self, expanded: bool) {
+        let rows = self.rows();
+        let Some(row) = self.state.selected().and_then(|i| rows.get(i)) else {
+            return;
+        };
+        if let Some(node) = self.node_mut(&row.path) {
+            node.expanded = expanded;
+        }
+    }
+
+    pub fn toggle_expanded(&mut self) {
+        if let Some(expanded) = self.selected().map(|node| node.expanded) {
+            self.set_expanded(!expanded);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+
+use arrow::{array::RecordBatch, csv::Writer};
+use serde::Deserialize;
+
+/// Hooks a plugin can implement to extend civciv. Apps embedding the
+/// library register plugins with `App::add_plugin`; programs listed under
+/// `[[plugins]]` in the config are wrapped in an [`ExternalPlugin`].
+pub trait Plugin {
+    fn name(&self) -> &str;
+
+    /// Called when a statement is submitted from the editor, before it
+    /// runs. Returning a statement runs that one instead.
+    fn on_query_submit(&mut self, _sql: &str) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+
+    /// Called once a statement has delivered its whole result.
+    fn on_result(&mut self, _sql: &str, _data: &[RecordBatch]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called before a result is shown. Returning batches shows those
+    /// instead; they stand on their own, so they can't be paged or edited.
+    fn transform_result(
+        &mut self,
+        _sql: &str,
+        _data: &[RecordBatch],
+    ) -> Result<Option<Vec<RecordBatch>>, String> {
+        Ok(None)
+    }
+
+    /// Names of the `:` commands the plugin adds. Built-in commands win
+    /// over these.
+    fn commands(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Keys that run one of the plugin's commands, as `(key, command)`
+    /// pairs with keys written like `F5` or `ctrl+r`. Keys civciv already
+    /// uses in the result view win over these.
+    fn key_bindings(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+
+    /// Runs one of the plugin's commands on the current result.
+    fn run_command(
+        &mut self,
+        command: &str,
+        _args: &[&str],
+        _data: &[RecordBatch],
+    ) -> Result<Output, String> {
+        Err(format!("{} has no command {command}", self.name()))
+    }
+}
+
+/// What a plugin command produced.
+pub enum Output {
+    /// A message for the status bar.
+    Status(String),
+    /// Text shown in a pane of its own.
+    Pane(PluginPane),
+}
+
+pub struct PluginPane {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub scroll: u16,
+}
+
+/// A program registered as a plugin under `[[plugins]]`.
+#[derive(Debug, Deserialize)]
+pub struct PluginConfig {
+    pub name: String,
+    /// Program run for each hook, with the hook as its first argument.
+    pub command: String,
+    /// `:` commands run as `<command> <name> <args...>`, with the current
+    /// result as CSV on stdin. What the program prints is shown in a pane.
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// Run `<command> submit` with each submitted statement on stdin; a
+    /// statement printed back replaces it.
+    #[serde(default)]
+    pub rewrite_queries: bool,
+    /// Run `<command> result` with each result as CSV on stdin and the
+    /// statement in `CIVCIV_SQL`.
+    #[serde(default)]
+    pub on_result: bool,
+}
+
+/// Runs a program for the hooks its config enables.
+pub struct ExternalPlugin {
+    config: PluginConfig,
+}
+
+impl ExternalPlugin {
+    pub fn new(config: PluginConfig) -> Self {
+        Self { config }
+    }
+
+    fn run(&self, args: &[&str], input: &[u8], sql: Option<&str>) -> Result<String, String> {
+        let mut command = Command::new(&self.config.command);
+        command
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(sql) = sql {
+            command.env("CIVCIV_SQL", sql);
+        }
+        let mut child = command.spawn().map_err(|err| err.to_string())?;
+        // Written from a thread of its own, since a program that prints as
+        // it reads would otherwise block on a full stdout while civciv is
+        // blocked on its full stdin.
+        let writer = child.stdin.take().map(|mut stdin| {
+            let input = input.to_vec();
+            thread::spawn(move || {
+                // A program that stops reading early has seen all it wants.
+                let _ = stdin.write_all(&input);
+            })
+        });
+        let output = child.wait_with_output().map_err(|err| err.to_string())?;
+        if let Some(writer) = writer {
+            let _ = writer.join();
+        }
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(match stderr.trim() {
+                "" => format!("{} exited with {}", self.config.command, output.status),
+                stderr => stderr.to_string(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl Plugin for ExternalPlugin {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn on_query_submit(&mut self, sql: &str) -> Result<Option<String>, String> {
+        if !self.config.rewrite_queries {
+            return Ok(None);
+        }
+        let rewritten = self.run(&["submit"], sql.as_bytes(), None)?;
+        let rewritten = rewritten.trim();
+        Ok((!rewritten.is_empty() && rewritten != sql.trim()).then(|| rewritten.to_string()))
+    }
+
+    fn on_result(&mut self, sql: &str, data: &[RecordBatch]) -> Result<(), String> {
+        if self.config.on_result {
+            self.run(&["result"], &to_csv(data)?, Some(sql))?;
+        }
+        Ok(())
+    }
+
+    fn commands(&self) -> Vec<String> {
+        self.config.commands.clone()
+    }
+
+    fn run_command(
+        &mut self,
+        command: &str,
+        args: &[&str],
+        data: &[RecordBatch],
+    ) -> Result<Output, String> {
+        let args: Vec<&str> = std::iter::once(command)
+            .chain(args.iter().copied())
+            .collect();
+        let printed = self.run(&args, &to_csv(data)?, None)?;
+        let lines: Vec<String> = printed.lines().map(str::to_string).collect();
+        Ok(match lines.as_slice() {
+            [] => Output::Status(format!("{command} done")),
+            [line] => Output::Status(line.clone()),
+            _ => Output::Pane(PluginPane {
+                title: format!("{}: {command}", self.config.name),
+                lines,
+                scroll: 0,
+            }),
+        })
+    }
+}
+
+/// The result as CSV with a header line.
+pub fn to_csv(data: &[RecordBatch]) -> Result<Vec<u8>, String> {
+    let mut writer = Writer::new(Vec::new());
+    for batch in data {
+        writer.write(batch).map_err(|err| err.to_string())?;
+    }
+    Ok(writer.into_inner())
+}
@@ -0,0 +1,190 @@
+use std::{
+    cmp::Reverse,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use arrow::array::RecordBatch;
+use duckdb::{params_from_iter, Connection};
+use ratatui::widgets::TableState;
+use serde_json::Value;
+
+use crate::sql::quote_literal;
+
+pub struct OperatorProfile {
+    pub name: String,
+    pub depth: usize,
+    pub rows: u64,
+    pub seconds: f64,
+    /// Share of the summed operator time, from 0 to 100.
+    pub percent: f64,
+    /// Position in the plan, so the tree order can be restored after sorting.
+    pub order: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Plan,
+    Time,
+    Rows,
+    Name,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Plan => SortKey::Time,
+            SortKey::Time => SortKey::Rows,
+            SortKey::Rows => SortKey::Name,
+            SortKey::Name => SortKey::Plan,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Plan => "plan",
+            SortKey::Time => "time",
+            SortKey::Rows => "rows",
+            SortKey::Name => "name",
+        }
+    }
+}
+
+/// Per-operator timings of a query run with DuckDB's JSON profiling output.
+pub struct ProfileView {
+    pub operators: Vec<OperatorProfile>,
+    pub total_seconds: f64,
+    pub sort: SortKey,
+    pub state: TableState,
+}
+
+impl ProfileView {
+    /// Where the profile of a query run from this process is written.
+    pub fn output_path() -> PathBuf {
+        env::temp_dir().join(format!("civciv-profile-{}.json", process::id()))
+    }
+
+    /// Runs the query with profiling written to `output`, handing each
+    /// batch to `batch` rather than keeping it, until that says to stop.
+    /// Profiling is left on only for this one query, even when it failed.
+    pub fn run(
+        db: &Connection,
+        sql: &str,
+        params: &[Option<String>],
+        output: &Path,
+        mut batch: impl FnMut(RecordBatch) -> bool,
+    ) -> duckdb::Result<()> {
+        db.execute_batch(&format!(
+            "PRAGMA enable_profiling='json'; PRAGMA profiling_output={};",
+            quote_literal(&output.to_string_lossy())
+        ))?;
+        let result = db.prepare(sql).and_then(|mut stmt| {
+            for next in stmt.query_arrow(params_from_iter(params))? {
+                if !batch(next) {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        db.execute_batch("PRAGMA disable_profiling;")?;
+        result
+    }
+
+    /// Reads the profile a query run with `run` wrote, and removes it.
+    pub fn load(output: &Path) -> Result<Self, Box<dyn Error>> {
+        let json = fs::read_to_string(output)?;
+        let _ = fs::remove_file(output);
+        let value: Value = serde_json::from_str(&json)?;
+
+        let mut operators = Vec::new();
+        for child in children(&value) {
+            flatten(child, 0, &mut operators);
+        }
+
+        let total_seconds: f64 = operators.iter().map(|op| op.seconds).sum();
+        for op in &mut operators {
+            op.percent = if total_seconds > 0.0 {
+                op.seconds / total_seconds * 100.0
+            } else {
+                0.0
+            };
+        }
+
+        let mut state = TableState::default();
+        state.select(Some(0));
+        Ok(Self {
+            operators,
+            total_seconds,
+            sort: SortKey::Plan,
+            state,
+        })
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        match self.sort {
+            SortKey::Plan => self.operators.sort_by_key(|op| op.order),
+            SortKey::Time => self
+                .operators
+                .sort_by(|a, b| b.seconds.total_cmp(&a.seconds)),
+            SortKey::Rows => self.operators.sort_by_key(|op| Reverse(op.rows)),
+            SortKey::Name => self
+                .operators
+                .sort_by(|a, b| a.name.cmp(&b.name).then(a.order.cmp(&b.order))),
+        }
+        self.state.select(Some(0));
+    }
+
+    pub fn select_next(&mut self) {
+        let last = self.operators.len().saturating_sub(1);
+        let next = self.state.selected().map_or(0, |i| i + 1);
+        self.state.select(Some(next.min(last)));
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(previous));
+    }
+}
+
+fn children(value: &Value) -> &[Value] {
+    value
+        .get("children")
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+/// Walks the operator tree below the root query node. Newer DuckDB versions
+/// prefix the keys with `operator_`.
+fn flatten(value: &Value, depth: usize, operators: &mut Vec<OperatorProfile>) {
+    let name = ["operator_name", "operator_type", "name"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(Value::as_str))
+        .unwrap_or("?")
+        .trim()
+        .to_string();
+    let seconds = ["operator_timing", "timing"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(Value::as_f64))
+        .unwrap_or_default();
+    let rows = ["operator_cardinality", "cardinality"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(Value::as_u64))
+        .unwrap_or_default();
+
+    operators.push(OperatorProfile {
+        name,
+        depth,
+        rows,
+        seconds,
+        percent: 0.0,
+        order: operators.len(),
+    });
+
+    for child in children(value) {
+        flatten(child, depth + 1, operators);
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+
+const MAX_ENTRIES: usize = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RecentKind {
+    Database,
+    File,
+}
+
+/// Recently opened databases and data files, most recent first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Recent {
+    pub databases: Vec<String>,
+    pub files: Vec<String>,
+}
+
+impl Recent {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("civciv").join("recent.json"))
+    }
+
+    /// Loads the list, treating a missing or unreadable file as empty.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn add_database(&mut self, target: &str) {
+        let target = if db::is_motherduck(target) {
+            without_token(target)
+        } else {
+            absolute(target)
+        };
+        push_front(&mut self.databases, target);
+    }
+
+    pub fn add_file(&mut self, path: &str) {
+        push_front(&mut self.files, absolute(path));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.databases.is_empty() && self.files.is_empty()
+    }
+
+    pub fn entries(&self) -> Vec<(RecentKind, &str)> {
+        let databases = self
+            .databases
+            .iter()
+            .map(|target| (RecentKind::Database, target.as_str()));
+        let files = self
+            .files
+            .iter()
+            .map(|path| (RecentKind::File, path.as_str()));
+        databases.chain(files).collect()
+    }
+}
+
+fn absolute(path: &str) -> String {
+    fs::canonicalize(path).map_or_else(
+        |_| path.to_string(),
+        |path| path.to_string_lossy().into_owned(),
+    )
+}
+
+fn push_front(list: &mut Vec<String>, entry: String) {
+    list.retain(|existing| *existing != entry);
+    list.insert(0, entry);
+    list.truncate(MAX_ENTRIES);
+}
+
+/// Drops a `motherduck_token` option from an `md:` connection string, so the
+/// token never ends up in the recent list on disk.
+fn without_token(target: &str) -> String {
+    let Some((database, options)) = target.split_once('?') else {
+        return target.to_string();
+    };
+    let options: Vec<&str> = options
+        .split('&')
+        .filter(|option| {
+            let name = option.split('=').next().unwrap_or_default();
+            !name.eq_ignore_ascii_case("motherduck_token")
+        })
+        .collect();
+    if options.is_empty() {
+        database.to_string()
+    } else {
+        format!("{database}?{}", options.join("&"))
+    }
+}
@@ -0,0 +1,264 @@
+use std::env;
+
+use duckdb::Connection;
+use serde::Deserialize;
+
+use crate::sql::{quote_identifier, quote_literal};
+
+/// A database server DuckDB can attach through one of its scanner
+/// extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteKind {
+    Postgres,
+    Mysql,
+}
+
+impl RemoteKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "postgres" | "postgresql" | "pg" => Some(Self::Postgres),
+            "mysql" => Some(Self::Mysql),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Postgres => "Postgres",
+            Self::Mysql => "MySQL",
+        }
+    }
+
+    /// The extension that reads it, which is also its `TYPE` in `ATTACH`.
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Postgres => "postgres",
+            Self::Mysql => "mysql",
+        }
+    }
+
+    /// URI schemes a connection string can start with.
+    fn schemes(self) -> &'static [&'static str] {
+        match self {
+            Self::Postgres => &["postgres://", "postgresql://"],
+            Self::Mysql => &[],
+        }
+    }
+
+    /// Environment variables the client library falls back on, by field.
+    fn env_var(self, field: Field) -> Option<&'static str> {
+        match (self, field) {
+            (Self::Postgres, Field::Host) => Some("PGHOST"),
+            (Self::Postgres, Field::Port) => Some("PGPORT"),
+            (Self::Postgres, Field::Database) => Some("PGDATABASE"),
+            (Self::Postgres, Field::User) => Some("PGUSER"),
+            (Self::Mysql, Field::Host) => Some("MYSQL_HOST"),
+            (Self::Mysql, Field::Port) => Some("MYSQL_TCP_PORT"),
+            (Self::Mysql, Field::Database) => Some("MYSQL_DATABASE"),
+            (Self::Mysql, Field::User) => Some("MYSQL_USER"),
+            _ => None,
+        }
+    }
+
+    /// The connection string's key for a field.
+    fn key(self, field: Field) -> Option<&'static str> {
+        match field {
+            Field::Host => Some("host"),
+            Field::Port => Some("port"),
+            Field::Database if self == Self::Mysql => Some("database"),
+            Field::Database => Some("dbname"),
+            Field::User => Some("user"),
+            Field::Password => Some("password"),
+            Field::ConnectionString | Field::Alias => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    ConnectionString,
+    Host,
+    Port,
+    Database,
+    User,
+    Password,
+    Alias,
+}
+
+impl Field {
+    const ALL: [Field; 7] = [
+        Field::ConnectionString,
+        Field::Host,
+        Field::Port,
+        Field::Database,
+        Field::User,
+        Field::Password,
+        Field::Alias,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::ConnectionString => "Connection string",
+            Field::Host => "Host",
+            Field::Port => "Port",
+            Field::Database => "Database",
+            Field::User => "User",
+            Field::Password => "Password",
+            Field::Alias => "Attach as",
+        }
+    }
+}
+
+/// State of the form that attaches a database server: either a whole
+/// connection string or its parts, with the password typed in hidden.
+/// Parts left empty fall back on the client library's environment
+/// variables, which the form starts out filled with.
+pub struct RemoteForm {
+    pub kind: RemoteKind,
+    pub fields: Vec<(Field, String)>,
+    pub selected: usize,
+    pub error: Option<String>,
+}
+
+impl RemoteForm {
+    pub fn new(kind: RemoteKind) -> Self {
+        let fields = Field::ALL
+            .into_iter()
+            .map(|field| {
+                let value = match field {
+                    Field::Alias => kind.extension().to_string(),
+                    _ => kind
+                        .env_var(field)
+                        .and_then(|name| env::var(name).ok())
+                        .unwrap_or_default(),
+                };
+                (field, value)
+            })
+            .collect();
+        Self {
+            kind,
+            fields,
+            selected: 0,
+            error: None,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.selected = (self.selected + 1) % self.fields.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        let len = self.fields.len();
+        self.selected = (self.selected + len - 1) % len;
+    }
+
+    pub fn enter_char(&mut self, c: char) {
+        self.fields[self.selected].1.push(c);
+    }
+
+    pub fn delete_char(&mut self) {
+        self.fields[self.selected].1.pop();
+    }
+
+    fn value(&self, field: Field) -> &str {
+        self.fields
+            .iter()
+            .find(|(f, _)| *f == field)
+            .map_or("", |(_, value)| value.trim())
+    }
+
+    /// The connection string given, with the password added when one was
+    /// typed, or one made of the parts filled in.
+    pub fn connection_string(&self) -> String {
+        let password = self.value(Field::Password);
+        let given = self.value(Field::ConnectionString);
+        if self
+            .kind
+            .schemes()
+            .iter()
+            .any(|scheme| given.starts_with(scheme))
+        {
+            if password.is_empty() {
+                return given.to_string();
+            }
+            let separator = if given.contains('?') { '&' } else { '?' };
+            return format!("{given}{separator}password={}", percent_encode(password));
+        }
+
+        let mut parts = vec![];
+        if !given.is_empty() {
+            parts.push(given.to_string());
+        }
+        for field in Field::ALL {
+            let Some(key) = self.kind.key(field) else {
+                continue;
+            };
+            // A connection string sets everything but the password.
+            let value = self.value(field);
+            if value.is_empty() || (!given.is_empty() && field != Field::Password) {
+                continue;
+            }
+            parts.push(format!("{key}={}", quote_value(value)));
+        }
+        parts.join(" ")
+    }
+
+    /// Attaches the database. Returns the name it was attached as.
+    pub fn attach(&self, db: &Connection) -> duckdb::Result<String> {
+        let alias = match self.value(Field::Alias) {
+            "" => self.kind.extension(),
+            alias => alias,
+        };
+        attach(db, self.kind, &self.connection_string(), alias, false)?;
+        Ok(alias.to_string())
+    }
+}
+
+/// Loads the kind's scanner extension, installing it on first use, and
+/// attaches the database `connection` points at as `alias`, unless it
+/// already is.
+pub fn attach(
+    db: &Connection,
+    kind: RemoteKind,
+    connection: &str,
+    alias: &str,
+    read_only: bool,
+) -> duckdb::Result<()> {
+    let attached: bool = db.query_row(
+        "SELECT count(*) > 0 FROM duckdb_databases() WHERE database_name = ?",
+        [alias],
+        |row| row.get(0),
+    )?;
+    if attached {
+        return Ok(());
+    }
+    let extension = kind.extension();
+    db.execute_batch(&format!("INSTALL {extension}; LOAD {extension};"))?;
+    let read_only = if read_only { ", READ_ONLY" } else { "" };
+    db.execute_batch(&format!(
+        "ATTACH {} AS {} (TYPE {extension}{read_only})",
+        quote_literal(connection),
+        quote_identifier(alias)
+    ))
+}
+
+/// Quotes a value for a `key=value` connection string when it has to be.
+fn quote_value(value: &str) -> String {
+    if !value.is_empty() && !value.contains([' ', '\'', '\\']) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,99 @@
+use duckdb::Connection;
+
+/// A small table that ships with civciv, for trying things out without a
+/// database of one's own.
+pub struct Sample {
+    pub name: &'static str,
+    pub description: &'static str,
+    sql: &'static str,
+}
+
+/// The sample tables, which join on `country_code` and `employee_id`.
+pub const SAMPLES: &[Sample] = &[
+    Sample {
+        name: "countries",
+        description: "Ten countries with their continent and population",
+        sql: "CREATE OR REPLACE TABLE countries AS
+            SELECT * FROM (VALUES
+                ('BR', 'Brazil', 'South America', 216.4),
+                ('CA', 'Canada', 'North America', 38.8),
+                ('DE', 'Germany', 'Europe', 84.5),
+                ('IN', 'India', 'Asia', 1428.6),
+                ('JP', 'Japan', 'Asia', 123.3),
+                ('KE', 'Kenya', 'Africa', 55.1),
+                ('NL', 'Netherlands', 'Europe', 17.9),
+                ('NZ', 'New Zealand', 'Oceania', 5.2),
+                ('TR', 'Turkey', 'Europe', 85.3),
+                ('US', 'United States', 'North America', 339.9)
+            ) countries(code, name, continent, population_millions)",
+    },
+    Sample {
+        name: "employees",
+        description: "Twelve employees with their department, country and salary",
+        sql: "CREATE OR REPLACE TABLE employees AS
+            SELECT id, name, department, country_code, salary, hired::DATE AS hired
+            FROM (VALUES
+                (1, 'Ada Byron', 'Engineering', 'NL', 98000, '2019-03-01'),
+                (2, 'Alan Hughes', 'Engineering', 'DE', 91000, '2020-07-15'),
+                (3, 'Grace Mendes', 'Engineering', 'BR', 87000, '2021-01-11'),
+                (4, 'Kenji Sato', 'Sales', 'JP', 72000, '2018-09-24'),
+                (5, 'Amara Otieno', 'Sales', 'KE', 64000, '2022-02-07'),
+                (6, 'Deniz Kaya', 'Sales', 'TR', 61000, '2023-05-02'),
+                (7, 'Priya Nair', 'Support', 'IN', 48000, '2021-11-29'),
+                (8, 'Liam Walker', 'Support', 'NZ', 52000, '2020-04-20'),
+                (9, 'Emma Tremblay', 'Marketing', 'CA', 69000, '2019-08-05'),
+                (10, 'Noah Fischer', 'Marketing', 'DE', 71000, '2022-10-17'),
+                (11, 'Olivia Brooks', 'Finance', 'US', 83000, '2017-06-12'),
+                (12, 'Mateus Costa', 'Finance', 'BR', 76000, '2023-01-09')
+            ) employees(id, name, department, country_code, salary, hired)",
+    },
+    Sample {
+        name: "orders",
+        description: "A thousand orders over 2023, each taken by an employee",
+        sql: "CREATE OR REPLACE TABLE orders AS
+            SELECT
+                i AS id,
+                1 + i * 7 % 12 AS employee_id,
+                DATE '2023-01-01' + (i * 13 % 365)::INTEGER AS ordered,
+                (['Keyboard', 'Mouse', 'Monitor', 'Laptop', 'Headset'])[1 + i % 5] AS product,
+                1 + i * 3 % 4 AS quantity,
+                round(5 + i * 37 % 1000 / 4.0, 2) AS price,
+                CASE WHEN i % 17 = 0 THEN 'returned' WHEN i % 5 = 0 THEN 'pending'
+                    ELSE 'shipped' END AS status
+            FROM range(1, 1001) t(i)",
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Sample> {
+    SAMPLES
+        .iter()
+        .find(|sample| sample.name.eq_ignore_ascii_case(name))
+}
+
+/// Creates the sample tables that aren't in the current schema yet,
+/// leaving tables of the same name alone.
+pub fn load_missing(db: &Connection) -> duckdb::Result<()> {
+    for sample in SAMPLES {
+        let exists: bool = db.query_row(
+            "SELECT count(*) > 0 FROM duckdb_tables()
+             WHERE database_name = current_database()
+                AND schema_name = current_schema()
+                AND table_name = ?",
+            [sample.name],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            db.execute_batch(sample.sql)?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates the sample tables in the current schema, replacing ones of the
+/// same name.
+pub fn load(db: &Connection, samples: &[&Sample]) -> duckdb::Result<()> {
+    for sample in samples {
+        db.execute_batch(sample.sql)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use duckdb::Connection;
+use ratatui::widgets::ListState;
+
+use crate::sql::quote_literal;
+
+pub struct Column {
+    pub name: String,
+    pub data_type: String,
+}
+
+pub struct Table {
+    pub name: String,
+    pub is_view: bool,
+    pub columns: Vec<Column>,
+    /// Keys of the table and foreign keys from and to it, as
+    /// `PRIMARY KEY (id)` or `REFERENCED BY orders (user_id)`.
+    pub constraints: Vec<String>,
+    pub primary_key: Vec<String>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub expanded: bool,
+}
+
+/// A foreign key of a table, to a table in the same schema.
+pub struct ForeignKey {
+    pub columns: Vec<String>,
+    pub table: String,
+    pub referenced_columns: Vec<String>,
+}
+
+pub struct Schema {
+    pub name: String,
+    pub tables: Vec<Table>,
+    pub expanded: bool,
+}
+
+pub struct Catalog {
+    pub name: String,
+    pub schemas: Vec<Schema>,
+    pub expanded: bool,
+}
+
+/// A row of the tree as it is currently displayed, addressed by indices into
+/// the catalog list.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Node {
+    Catalog(usize),
+    Schema(usize, usize),
+    Table(usize, usize, usize),
+    Column(usize, usize, usize, usize),
+    Constraint(usize, usize, usize, usize),
+}
+
+#[derive(Default)]
+pub struct SchemaBrowser {
+    pub catalogs: Vec<Catalog>,
+    pub state: ListState,
+}
+
+impl SchemaBrowser {
+    /// Reads every attached catalog, including remote MotherDuck databases,
+    /// from the DuckDB metadata functions.
+    pub fn load(db: &Connection) -> duckdb::Result<Self> {
+        let mut browser = Self::default();
+
+        let mut stmt = db.prepare(
+            "SELECT database_name FROM duckdb_databases() WHERE NOT internal ORDER BY 1",
+        )?;
+        for name in stmt.query_map([], |row| row.get::<_, String>(0))? {
+            browser.catalogs.push(Catalog {
+                name: name?,
+                schemas: vec![],
+                expanded: false,
+            });
+        }
+
+        // `main` is flagged as internal, so filter the built-in schemas by name.
+        let mut stmt = db.prepare(
+            "SELECT database_name, schema_name FROM duckdb_schemas()
+             WHERE schema_name NOT IN ('information_schema', 'pg_catalog')
+             ORDER BY 1, 2",
+        )?;
+        for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))? {
+            let (catalog, name) = row?;
+            if let Some(catalog) = browser.catalog_mut(&catalog) {
+                catalog.schemas.push(Schema {
+                    name,
+                    tables: vec![],
+                    expanded: false,
+                });
+            }
+        }
+
+        let mut stmt = db.prepare(
+            "SELECT database_name, schema_name, table_name, false FROM duckdb_tables() WHERE NOT internal
+             UNION ALL
+             SELECT database_name, schema_name, view_name, true FROM duckdb_views() WHERE NOT internal
+             ORDER BY 1, 2, 3",
+        )?;
+        for row in stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get(2)?,
+                row.get(3)?,
+            ))
+        })? {
+            let (catalog, schema, name, is_view) = row?;
+            if let Some(schema) = browser.schema_mut(&catalog, &schema) {
+                schema.tables.push(Table {
+                    name,
+                    is_view,
+                    columns: vec![],
+                    constraints: vec![],
+                    primary_key: vec![],
+                    foreign_keys: vec![],
+                    expanded: false,
+                });
+            }
+        }
+
+        let mut stmt = db.prepare(
+            "SELECT database_name, schema_name, table_name, column_name, data_type
+             FROM duckdb_columns() WHERE NOT internal
+             ORDER BY database_name, schema_name, table_name, column_index",
+        )?;
+        for row in stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })? {
+            let (catalog, schema, table, name, data_type) = row?;
+            if let Some(table) = browser
+                .schema_mut(&catalog, &schema)
+                .and_then(|schema| schema.tables.iter_mut().find(|t| t.name == table))
+            {
+                table.columns.push(Column { name, data_type });
+            }
+        }
+
+        let mut stmt = db.prepare(
+            "SELECT database_name, schema_name, table_name, constraint_type, constraint_text,
+                    array_to_string(constraint_column_names, ', ')
+             FROM duckdb_constraints()
+             WHERE constraint_type IN ('PRIMARY KEY', 'UNIQUE', 'FOREIGN KEY')
+             ORDER BY database_name, schema_name, table_name, constraint_index",
+        )?;
+        let mut references = vec![];
+        for row in stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })? {
+            let (catalog, schema, table, kind, text, columns) = row?;
+            let Some(entry) = browser.table_mut(&catalog, &schema, &table) else {
+                continue;
+            };
+            // The text repeats key columns in some DuckDB versions, so only
+            // foreign keys use it, for the table they reference.
+            if kind == "FOREIGN KEY" {
+                if let Some(key) = foreign_key(&text) {
+                    references.push((
+                        catalog.clone(),
+                        schema.clone(),
+                        key.table.clone(),
+                        format!("REFERENCED BY {table} ({columns})"),
+                    ));
+                    entry.foreign_keys.push(key);
+                }
+                entry.constraints.push(text);
+            } else {
+                if kind == "PRIMARY KEY" {
+                    entry.primary_key = columns.split(", ").map(str::to_string).collect();
+                }
+                entry.constraints.push(format!("{kind} ({columns})"));
+            }
+        }
+        for (catalog, schema, table, reference) in references {
+            if let Some(table) = browser.table_mut(&catalog, &schema, &table) {
+                table.constraints.push(reference);
+            }
+        }
+
+        // A single catalog is the common case, so show its contents right away.
+        if let [catalog] = browser.catalogs.as_mut_slice() {
+            catalog.expanded = true;
+        }
+        browser.state.select(Some(0));
+
+        Ok(browser)
+    }
+
+    /// Expands what was expanded in `previous` and keeps its selection, so
+    /// loading the schema again doesn't fold the tree back up.
+    pub fn keep_expanded(&mut self, previous: &SchemaBrowser) {
+        for catalog in &mut self.catalogs {
+            let Some(old) = previous.catalogs.iter().find(|c| c.name == catalog.name) else {
+                continue;
+            };
+            catalog.expanded = old.expanded;
+            for schema in &mut catalog.schemas {
+                let Some(old) = old.schemas.iter().find(|s| s.name == schema.name) else {
+                    continue;
+                };
+                schema.expanded = old.expanded;
+                for table in &mut schema.tables {
+                    if let Some(old) = old.tables.iter().find(|t| t.name == table.name) {
+                        table.expanded = old.expanded;
+                    }
+                }
+            }
+        }
+        if let Some(selected) = previous.state.selected() {
+            let last = self.visible_nodes().len().saturating_sub(1);
+            self.state.select(Some(selected.min(last)));
+        }
+    }
+
+    /// The `CREATE` statement DuckDB keeps for the table or view at `node`.
+    pub fn ddl(&self, db: &Connection, node: Node) -> duckdb::Result<Option<(String, String)>> {
+        let Node::Table(c, s, t) = node else {
+            return Ok(None);
+        };
+        let (catalog, schema) = (&self.catalogs[c], &self.catalogs[c].schemas[s]);
+        let table = &schema.tables[t];
+        let (function, column) = if table.is_view {
+            ("duckdb_views()", "view_name")
+        } else {
+            ("duckdb_tables()", "table_name")
+        };
+        let sql: Option<String> = db.query_row(
+            &format!(
+                "SELECT sql FROM {function}
+                 WHERE database_name = {} AND schema_name = {} AND {column} = {}",
+                quote_literal(&catalog.name),
+                quote_literal(&schema.name),
+                quote_literal(&table.name)
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(Some((table.name.clone(), sql.unwrap_or_default())))
+    }
+
+    fn catalog_mut(&mut self, name: &str) -> Option<&mut Catalog> {
+        self.catalogs.iter_mut().find(|c| c.name == name)
+    }
+
+    fn table_mut(&mut self, catalog: &str, schema: &str, name: &str) -> Option<&mut Table> {
+        self.schema_mut(catalog, schema)?
+            .tables
+            .iter_mut()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    fn schema_mut(&mut self, catalog: &str, name: &str) -> Option<&mut Schema> {
+        self.catalog_mut(catalog)?
+            .schemas
+            .iter_mut()
+            .find(|s| s.name == name)
+    }
+
+    pub fn visible_nodes(&self) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        for (c, catalog) in self.catalogs.iter().enumerate() {
+            nodes.push(Node::Catalog(c));
+            if !catalog.expanded {
+                continue;
+            }
+            for (s, schema) in catalog.schemas.iter().enumerate() {
+                nodes.push(Node::Schema(c, s));
+                if !schema.expanded {
+                    continue;
+                }
+                for (t, table) in schema.tables.iter().enumerate() {
+                    nodes.push(Node::Table(c, s, t));
+                    if table.expanded {
+                        nodes.extend((0..table.columns.len()).map(|i| Node::Column(c, s, t, i)));
+                        nodes.extend(
+                            (0..table.constraints.len()).map(|i| Node::Constraint(c, s, t, i)),
+                        );
+                    }
+                }
+            }
+        }
+        nodes
+    }
+
+    pub fn label(&self, node: Node) -> String {
+        let marker = |expanded: bool| if expanded { "▾" } else { "▸" };
+        match node {
+            Node::Catalog(c) => {
+                let catalog = &self.catalogs[c];
+                format!("{} {}", marker(catalog.expanded), catalog.name)
+            }
+            Node::Schema(c, s) => {
+                let schema = &self.catalogs[c].schemas[s];
+                format!("  {} {}", marker(schema.expanded), schema.name)
+            }
+            Node::Table(c, s, t) => {
+                let table = &self.catalogs[c].schemas[s].tables[t];
+                let kind = if table.is_view { " (view)" } else { "" };
+                format!("    {} {}{kind}", marker(table.expanded), table.name)
+            }
+            Node::Column(c, s, t, i) => {
+                let column = &self.catalogs[c].schemas[s].tables[t].columns[i];
+                format!("        {} {}", column.name, column.data_type)
+            }
+            Node::Constraint(c, s, t, i) => {
+                let constraint = &self.catalogs[c].schemas[s].tables[t].constraints[i];
+                format!("        • {constraint}")
+            }
+        }
+    }
+
+    pub fn selected(&self) -> Option<Node> {
+        self.state
+            .selected()
+            .and_then(|i| self.visible_nodes().get(i).copied())
+    }
+
+    pub fn select_next(&mut self) {
+        let len = self.visible_nodes().len();
+        let next = self.state.selected().map_or(0, |i| i.saturating_add(1));
+        self.state.select(Some(next.min(len.saturating_sub(1))));
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(previous));
+    }
+
+    pub fn set_expanded(&mut self, expanded: bool) {
+        let Some(node) = self.selected() else {
+            return;
+        };
+        match node {
+            Node::Catalog(c) => self.catalogs[c].expanded = expanded,
+            Node::Schema(c, s) => self.catalogs[c].schemas[s].expanded = expanded,
+            Node::Table(c, s, t) => self.catalogs[c].schemas[s].tables[t].expanded = expanded,
+            Node::Column(..) | Node::Constraint(..) => {}
+        }
+    }
+
+    pub fn toggle_expanded(&mut self) {
+        let expanded = match self.selected() {
+            Some(Node::Catalog(c)) => self.catalogs[c].expanded,
+            Some(Node::Schema(c, s)) => self.catalogs[c].schemas[s].expanded,
+            Some(Node::Table(c, s, t)) => self.catalogs[c].schemas[s].tables[t].expanded,
+            _ => return,
+        };
+        self.set_expanded(!expanded);
+    }
+}
+
+/// Reads a foreign key's text, `FOREIGN KEY (a, b) REFERENCES t(c, d)`. The
+/// referenced table's schema is dropped.
+fn foreign_key(text: &str) -> Option<ForeignKey> {
+    let names = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(|name| name.trim().trim_matches('"').to_string())
+            .collect()
+    };
+    let (key, rest) = text.split_once("REFERENCES ")?;
+    let (_, columns) = key.split_once('(')?;
+    let (table, referenced) = rest.split_once('(')?;
+    let table = table.trim().rsplit('.').next()?.trim_matches('"');
+    Some(ForeignKey {
+        columns: names(columns.trim().trim_end_matches(')')),
+        table: table.to_string(),
+        referenced_columns: names(referenced.trim().trim_end_matches(')')),
+    })
+}
@@ -0,0 +1,370 @@
+//! Scripts written in [Rhai](https://rhai.rs) that automate civciv. A script
+//! runs once when civciv starts and can register `:` commands and keys with
+//! `command` and `bind`. It can also hook into every statement by defining
+//! `on_query_submit(sql)`, `on_result(sql, result)` and
+//! `transform_result(sql, result)`.
+//!
+//! Results are maps holding `columns`, the column names, and `rows`, an
+//! array of rows that are each an array of values. Scripts read more of
+//! them with `query(sql)`, run statements with `execute(sql)`, format them
+//! with `table(result)` and save reports with `write_file(path, text)`.
+
+use std::{cell::RefCell, fs, path::Path, rc::Rc, sync::Arc};
+
+use arrow::{
+    array::{
+        Array as _, ArrayRef, AsArray, BooleanArray, Float64Array, Int64Array, RecordBatch,
+        StringArray,
+    },
+    compute::cast,
+    datatypes::{DataType, Field, Float64Type, Int64Type, Schema},
+    util::display::array_value_to_string,
+};
+use duckdb::Connection;
+use rhai::{
+    Array, CallFnOptions, Dynamic, Engine, EvalAltResult, FnPtr, FuncArgs, Map, Scope, AST,
+};
+
+use crate::{
+    app::batches_to_table,
+    plugin::{Output, Plugin, PluginPane},
+};
+
+/// What a script registered while it ran.
+#[derive(Default)]
+struct Registry {
+    commands: Vec<(String, FnPtr)>,
+    keys: Vec<(String, String)>,
+}
+
+pub struct Script {
+    name: String,
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    registry: Rc<RefCell<Registry>>,
+}
+
+impl Script {
+    /// Compiles and runs the script at `path`. Its queries run on `db`, a
+    /// session of its own.
+    pub fn load(path: &Path, db: Connection) -> Result<Self, String> {
+        let db = Rc::new(db);
+        let registry = Rc::new(RefCell::new(Registry::default()));
+        let mut engine = Engine::new();
+
+        let session = Rc::clone(&db);
+        engine.register_fn(
+            "query",
+            move |sql: &str| -> Result<Map, Box<EvalAltResult>> {
+                let data = query(&session, sql).map_err(|err| err.to_string())?;
+                Ok(to_result(&data)?)
+            },
+        );
+        let session = Rc::clone(&db);
+        engine.register_fn(
+            "execute",
+            move |sql: &str| -> Result<(), Box<EvalAltResult>> {
+                Ok(session.execute_batch(sql).map_err(|err| err.to_string())?)
+            },
+        );
+        engine.register_fn(
+            "table",
+            |result: Map| -> Result<String, Box<EvalAltResult>> {
+                let table =
+                    batches_to_table(&to_batches(result)?).map_err(|err| err.to_string())?;
+                Ok(table.to_string())
+            },
+        );
+        engine.register_fn(
+            "write_file",
+            |path: &str, text: &str| -> Result<(), Box<EvalAltResult>> {
+                Ok(fs::write(path, text).map_err(|err| format!("{path}: {err}"))?)
+            },
+        );
+        let commands = Rc::clone(&registry);
+        engine.register_fn("command", move |name: &str, run: FnPtr| {
+            commands.borrow_mut().commands.push((name.to_string(), run));
+        });
+        let keys = Rc::clone(&registry);
+        engine.register_fn("bind", move |key: &str, command: &str| {
+            keys.borrow_mut()
+                .keys
+                .push((key.to_string(), command.to_string()));
+        });
+
+        let source =
+            fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let ast = engine.compile(source).map_err(|err| err.to_string())?;
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|err| err.to_string())?;
+
+        let name = path.file_stem().map_or_else(
+            || path.display().to_string(),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+        Ok(Self {
+            name,
+            engine,
+            ast,
+            scope,
+            registry,
+        })
+    }
+
+    /// Calls the script's function `name`, if it defines one.
+    fn call_hook(&mut self, name: &str, args: impl FuncArgs) -> Result<Option<Dynamic>, String> {
+        if !self
+            .ast
+            .iter_functions()
+            .any(|function| function.name == name)
+        {
+            return Ok(None);
+        }
+        // The top level already ran when the script was loaded.
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options(options, &mut self.scope, &self.ast, name, args)
+            .map(Some)
+            .map_err(|err| err.to_string())
+    }
+}
+
+impl Plugin for Script {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_query_submit(&mut self, sql: &str) -> Result<Option<String>, String> {
+        let rewritten = self.call_hook("on_query_submit", (sql.to_string(),))?;
+        Ok(rewritten
+            .and_then(|rewritten| rewritten.into_string().ok())
+            .filter(|rewritten| rewritten.trim() != sql.trim()))
+    }
+
+    fn on_result(&mut self, sql: &str, data: &[RecordBatch]) -> Result<(), String> {
+        self.call_hook("on_result", (sql.to_string(), to_result(data)?))?;
+        Ok(())
+    }
+
+    fn transform_result(
+        &mut self,
+        sql: &str,
+        data: &[RecordBatch],
+    ) -> Result<Option<Vec<RecordBatch>>, String> {
+        match self.call_hook("transform_result", (sql.to_string(), to_result(data)?))? {
+            Some(result) if !result.is_unit() => {
+                let result = result
+                    .try_cast::<Map>()
+                    .ok_or("transform_result must return a result or ()")?;
+                to_batches(result).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn commands(&self) -> Vec<String> {
+        let registry = self.registry.borrow();
+        registry
+            .commands
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn key_bindings(&self) -> Vec<(String, String)> {
+        self.registry.borrow().keys.clone()
+    }
+
+    /// Calls the command's function with the words after its name and the
+    /// current result. A string it returns goes to the status bar, an array
+    /// is shown a line per element in a pane.
+    fn run_command(
+        &mut self,
+        command: &str,
+        args: &[&str],
+        data: &[RecordBatch],
+    ) -> Result<Output, String> {
+        let run = self
+            .registry
+            .borrow()
+            .commands
+            .iter()
+            .find(|(name, _)| name == command)
+            .map(|(_, run)| run.clone())
+            .ok_or_else(|| format!("{} has no command {command}", self.name))?;
+        let args: Array = args
+            .iter()
+            .map(|arg| Dynamic::from(arg.to_string()))
+            .collect();
+        let output: Dynamic = run
+            .call(&self.engine, &self.ast, (args, to_result(data)?))
+            .map_err(|err| err.to_string())?;
+
+        if output.is_unit() {
+            return Ok(Output::Status(format!("{command} done")));
+        }
+        if !output.is_array() {
+            return Ok(Output::Status(output.to_string()));
+        }
+        let lines = output.cast::<Array>();
+        Ok(Output::Pane(PluginPane {
+            title: format!("{}: {command}", self.name),
+            lines: lines.iter().map(Dynamic::to_string).collect(),
+            scroll: 0,
+        }))
+    }
+}
+
+fn query(db: &Connection, sql: &str) -> duckdb::Result<Vec<RecordBatch>> {
+    let mut stmt = db.prepare(sql)?;
+    let data = stmt.query_arrow([])?.collect();
+    Ok(data)
+}
+
+/// A result as a script sees it.
+fn to_result(data: &[RecordBatch]) -> Result<Map, String> {
+    let columns: Array = data.first().map_or_else(Array::new, |batch| {
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| Dynamic::from(field.name().clone()))
+            .collect()
+    });
+    let mut rows = Array::new();
+    for batch in data {
+        let values = batch
+            .columns()
+            .iter()
+            .map(values)
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.extend((0..batch.num_rows()).map(|row| {
+            Dynamic::from_array(values.iter().map(|column| column[row].clone()).collect())
+        }));
+    }
+
+    let mut result = Map::new();
+    result.insert("columns".into(), Dynamic::from_array(columns));
+    result.insert("rows".into(), Dynamic::from_array(rows));
+    Ok(result)
+}
+
+/// A column's values as numbers and booleans where they are, as text
+/// otherwise, and `()` for NULL.
+fn values(column: &ArrayRef) -> Result<Vec<Dynamic>, String> {
+    let rows = 0..column.len();
+    let values: Vec<Dynamic> = match column.data_type() {
+        DataType::Boolean => {
+            let column = column.as_boolean();
+            rows.map(|row| Dynamic::from_bool(column.value(row)))
+                .collect()
+        }
+        data_type if data_type.is_integer() => {
+            let column = cast(column, &DataType::Int64).map_err(|err| err.to_string())?;
+            let column = column.as_primitive::<Int64Type>();
+            rows.map(|row| Dynamic::from_int(column.value(row)))
+                .collect()
+        }
+        data_type if data_type.is_floating() => {
+            let column = cast(column, &DataType::Float64).map_err(|err| err.to_string())?;
+            let column = column.as_primitive::<Float64Type>();
+            rows.map(|row| Dynamic::from_float(column.value(row)))
+                .collect()
+        }
+        _ => rows
+            .map(|row| array_value_to_string(column, row).map(Dynamic::from))
+            .collect::<Result<_, _>>()
+            .map_err(|err| err.to_string())?,
+    };
+    Ok(values
+        .into_iter()
+        .enumerate()
+        .map(|(row, value)| {
+            if column.is_null(row) {
+                Dynamic::UNIT
+            } else {
+                value
+            }
+        })
+        .collect())
+}
+
+/// Turns a result a script built back into a batch, typing each column by
+/// the values in it.
+fn to_batches(result: Map) -> Result<Vec<RecordBatch>, String> {
+    let array = |key: &str| {
+        result
+            .get(key)
+            .cloned()
+            .and_then(|value| value.try_cast::<Array>())
+            .ok_or_else(|| format!("A result needs a `{key}` array"))
+    };
+    let columns: Vec<String> = array("columns")?.iter().map(Dynamic::to_string).collect();
+    let rows = array("rows")?
+        .into_iter()
+        .map(|row| row.try_cast::<Array>().ok_or("Each row must be an array"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let arrays: Vec<ArrayRef> = (0..columns.len())
+        .map(|i| {
+            let values: Vec<Dynamic> = rows
+                .iter()
+                .map(|row| row.get(i).cloned().unwrap_or(Dynamic::UNIT))
+                .collect();
+            to_array(&values)
+        })
+        .collect();
+    let fields: Vec<Field> = columns
+        .iter()
+        .zip(&arrays)
+        .map(|(name, array)| Field::new(name, array.data_type().clone(), true))
+        .collect();
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+        .map_err(|err| err.to_string())?;
+    Ok(vec![batch])
+}
+
+/// Values as booleans, integers or floats when they all are, as text
+/// otherwise. `()` becomes NULL.
+fn to_array(values: &[Dynamic]) -> ArrayRef {
+    let present = || values.iter().filter(|value| !value.is_unit());
+    if present().next().is_none() {
+        Arc::new(StringArray::new_null(values.len()))
+    } else if present().all(Dynamic::is_bool) {
+        Arc::new(
+            values
+                .iter()
+                .map(|value| value.as_bool().ok())
+                .collect::<BooleanArray>(),
+        )
+    } else if present().all(Dynamic::is_int) {
+        Arc::new(
+            values
+                .iter()
+                .map(|value| value.as_int().ok())
+                .collect::<Int64Array>(),
+        )
+    } else if present().all(|value| value.is_int() || value.is_float()) {
+        Arc::new(
+            values
+                .iter()
+                .map(|value| {
+                    value
+                        .as_float()
+                        .ok()
+                        .or_else(|| value.as_int().ok().map(|int| int as f64))
+                })
+                .collect::<Float64Array>(),
+        )
+    } else {
+        Arc::new(
+            values
+                .iter()
+                .map(|value| (!value.is_unit()).then(|| value.to_string()))
+                .collect::<StringArray>(),
+        )
+    }
+}
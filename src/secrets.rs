@@ -0,0 +1,244 @@
+use duckdb::Connection;
+use ratatui::widgets::ListState;
+
+use crate::sql::{quote_identifier, quote_literal};
+
+/// A secret as `duckdb_secrets()` lists it, with its values redacted.
+pub struct Secret {
+    pub name: String,
+    pub kind: String,
+    pub provider: String,
+    pub persistent: bool,
+    pub scope: String,
+    pub redacted: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Kind,
+    Provider,
+    KeyId,
+    Secret,
+    Region,
+    SessionToken,
+    Endpoint,
+    Scope,
+    Persistent,
+}
+
+impl Field {
+    const ALL: [Field; 10] = [
+        Field::Name,
+        Field::Kind,
+        Field::Provider,
+        Field::KeyId,
+        Field::Secret,
+        Field::Region,
+        Field::SessionToken,
+        Field::Endpoint,
+        Field::Scope,
+        Field::Persistent,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Name => "Name",
+            Field::Kind => "Type (s3, gcs, r2, azure, http…)",
+            Field::Provider => "Provider (config, credential_chain…)",
+            Field::KeyId => "Key ID",
+            Field::Secret => "Secret",
+            Field::Region => "Region",
+            Field::SessionToken => "Session token",
+            Field::Endpoint => "Endpoint",
+            Field::Scope => "Scope",
+            Field::Persistent => "Persist to disk (y/n)",
+        }
+    }
+
+    /// Whether the value is typed in hidden.
+    pub fn is_hidden(self) -> bool {
+        matches!(self, Field::Secret | Field::SessionToken)
+    }
+
+    /// The option it sets in `CREATE SECRET`.
+    fn option(self) -> Option<&'static str> {
+        match self {
+            Field::Kind => Some("TYPE"),
+            Field::Provider => Some("PROVIDER"),
+            Field::KeyId => Some("KEY_ID"),
+            Field::Secret => Some("SECRET"),
+            Field::Region => Some("REGION"),
+            Field::SessionToken => Some("SESSION_TOKEN"),
+            Field::Endpoint => Some("ENDPOINT"),
+            Field::Scope => Some("SCOPE"),
+            Field::Name | Field::Persistent => None,
+        }
+    }
+}
+
+/// The form that creates a secret. Its statement runs straight away, so
+/// the credentials never pass through the editor or the history.
+pub struct SecretForm {
+    pub fields: Vec<(Field, String)>,
+    pub selected: usize,
+    pub error: Option<String>,
+}
+
+impl SecretForm {
+    fn new() -> Self {
+        Self {
+            fields: Field::ALL
+                .into_iter()
+                .map(|field| (field, String::new()))
+                .collect(),
+            selected: 0,
+            error: None,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.selected = (self.selected + 1) % self.fields.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        let len = self.fields.len();
+        self.selected = (self.selected + len - 1) % len;
+    }
+
+    pub fn enter_char(&mut self, c: char) {
+        self.fields[self.selected].1.push(c);
+    }
+
+    pub fn delete_char(&mut self) {
+        self.fields[self.selected].1.pop();
+    }
+
+    fn value(&self, field: Field) -> &str {
+        self.fields
+            .iter()
+            .find(|(f, _)| *f == field)
+            .map_or("", |(_, value)| value.trim())
+    }
+
+    /// The `CREATE SECRET` for the filled in fields. `TYPE` stays a bare
+    /// word, everything else is a string.
+    fn statement(&self) -> Result<String, String> {
+        let name = self.value(Field::Name);
+        if name.is_empty() || self.value(Field::Kind).is_empty() {
+            return Err("A secret needs a name and a type".to_string());
+        }
+        let options: Vec<String> = Field::ALL
+            .into_iter()
+            .filter_map(|field| {
+                let option = field.option()?;
+                let value = self.value(field);
+                match (field, value) {
+                    (_, "") => None,
+                    (Field::Kind | Field::Provider, value) => Some(format!("{option} {value}")),
+                    (_, value) => Some(format!("{option} {}", quote_literal(value))),
+                }
+            })
+            .collect();
+        let persistent = if self.value(Field::Persistent).starts_with(['y', 'Y']) {
+            "PERSISTENT "
+        } else {
+            ""
+        };
+        Ok(format!(
+            "CREATE OR REPLACE {persistent}SECRET {} ({})",
+            quote_identifier(name),
+            options.join(", ")
+        ))
+    }
+}
+
+/// State of the secrets panel, which lists the secrets DuckDB holds and
+/// creates and drops them.
+#[derive(Default)]
+pub struct SecretsPanel {
+    pub secrets: Vec<Secret>,
+    pub state: ListState,
+    pub form: Option<SecretForm>,
+}
+
+impl SecretsPanel {
+    pub fn load(db: &Connection) -> duckdb::Result<Self> {
+        let mut panel = Self::default();
+        panel.refresh(db)?;
+        Ok(panel)
+    }
+
+    fn refresh(&mut self, db: &Connection) -> duckdb::Result<()> {
+        let mut stmt = db.prepare(
+            "SELECT name, type, provider, persistent, array_to_string(scope, ', '), secret_string
+             FROM duckdb_secrets(redact = true)
+             ORDER BY name",
+        )?;
+        self.secrets = stmt
+            .query_map([], |row| {
+                Ok(Secret {
+                    name: row.get(0)?,
+                    kind: row.get(1)?,
+                    provider: row.get(2)?,
+                    persistent: row.get(3)?,
+                    scope: row.get(4)?,
+                    redacted: row.get(5)?,
+                })
+            })?
+            .collect::<duckdb::Result<_>>()?;
+        let last = self.secrets.len().checked_sub(1);
+        self.state
+            .select(last.map(|last| self.state.selected().unwrap_or(0).min(last)));
+        Ok(())
+    }
+
+    pub fn select_next(&mut self) {
+        let last = self.secrets.len().saturating_sub(1);
+        let next = self.state.selected().map_or(0, |i| i + 1);
+        self.state.select(Some(next.min(last)));
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(previous));
+    }
+
+    pub fn open_form(&mut self) {
+        self.form = Some(SecretForm::new());
+    }
+
+    /// Creates the secret from the form and closes it, or keeps it open
+    /// with the error.
+    pub fn create(&mut self, db: &Connection) {
+        let Some(form) = &self.form else {
+            return;
+        };
+        let created = form
+            .statement()
+            .and_then(|sql| db.execute_batch(&sql).map_err(|err| err.to_string()));
+        match created.and_then(|()| self.refresh(db).map_err(|err| err.to_string())) {
+            Ok(()) => self.form = None,
+            Err(err) => {
+                if let Some(form) = self.form.as_mut() {
+                    form.error = Some(err);
+                }
+            }
+        }
+    }
+
+    /// Drops the selected secret. Returns its name.
+    pub fn drop_selected(&mut self, db: &Connection) -> duckdb::Result<Option<String>> {
+        let Some(secret) = self.state.selected().and_then(|i| self.secrets.get(i)) else {
+            return Ok(None);
+        };
+        let persistent = if secret.persistent { "PERSISTENT " } else { "" };
+        let name = secret.name.clone();
+        db.execute_batch(&format!(
+            "DROP {persistent}SECRET {}",
+            quote_identifier(&name)
+        ))?;
+        self.refresh(db)?;
+        Ok(Some(name))
+    }
+}
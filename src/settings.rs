@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use duckdb::Connection;
+use ratatui::widgets::ListState;
+
+use crate::sql::quote_literal;
+
+/// A row of `duckdb_settings()`.
+pub struct Setting {
+    pub name: String,
+    pub value: String,
+    pub description: String,
+    pub input_type: String,
+}
+
+/// Every setting's current value, by name.
+pub fn values(db: &Connection) -> duckdb::Result<BTreeMap<String, String>> {
+    let mut stmt = db.prepare("SELECT name, value FROM duckdb_settings()")?;
+    let values = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<duckdb::Result<_>>()?;
+    Ok(values)
+}
+
+/// State of the settings panel, which lists DuckDB's settings and changes
+/// them with `SET`.
+#[derive(Default)]
+pub struct SettingsPanel {
+    pub settings: Vec<Setting>,
+    pub state: ListState,
+    /// Only settings whose name contains this are listed.
+    pub filter: String,
+    pub editing_filter: bool,
+    /// The new value being typed for the selected setting.
+    pub edit: Option<String>,
+    pub error: Option<String>,
+}
+
+impl SettingsPanel {
+    pub fn load(db: &Connection) -> duckdb::Result<Self> {
+        let mut panel = Self::default();
+        panel.refresh(db)?;
+        panel.state.select(Some(0));
+        Ok(panel)
+    }
+
+    fn refresh(&mut self, db: &Connection) -> duckdb::Result<()> {
+        let mut stmt = db.prepare(
+            "SELECT name, value, description, input_type FROM duckdb_settings() ORDER BY name",
+        )?;
+        self.settings = stmt
+            .query_map([], |row| {
+                Ok(Setting {
+                    name: row.get(0)?,
+                    value: row.get(1)?,
+                    description: row.get(2)?,
+                    input_type: row.get(3)?,
+                })
+            })?
+            .collect::<duckdb::Result<_>>()?;
+        Ok(())
+    }
+
+    /// The settings the filter lets through.
+    pub fn visible(&self) -> Vec<&Setting> {
+        let filter = self.filter.to_lowercase();
+        self.settings
+            .iter()
+            .filter(|setting| setting.name.contains(&filter))
+            .collect()
+    }
+
+    fn selected(&self) -> Option<&Setting> {
+        self.state
+            .selected()
+            .and_then(|i| self.visible().get(i).copied())
+    }
+
+    pub fn select_next(&mut self) {
+        let last = self.visible().len().saturating_sub(1);
+        let next = self.state.selected().map_or(0, |i| i + 1);
+        self.state.select(Some(next.min(last)));
+    }
+
+    pub fn select_previous(&mut self) {
+        let previous = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(previous));
+    }
+
+    /// Narrows the list as the filter is typed, from its top.
+    pub fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.state.select(Some(0));
+    }
+
+    /// Starts typing a new value for the selected setting, from its
+    /// current one.
+    pub fn start_edit(&mut self) {
+        self.error = None;
+        self.edit = self.selected().map(|setting| setting.value.clone());
+    }
+
+    /// Sets the selected setting to the typed value with `SET`, in this
+    /// session, or for every session when the setting is global.
+    pub fn apply(&mut self, db: &Connection) {
+        let (Some(value), Some(setting)) = (self.edit.take(), self.selected()) else {
+            return;
+        };
+        let sql = format!("SET {} = {}", setting.name, quote_literal(&value));
+        let applied = db.execute_batch(&sql).and_then(|()| self.refresh(db));
+        if let Err(err) = applied {
+            self.edit = Some(value);
+            self.error = Some(err.to_string());
+        }
+    }
+}
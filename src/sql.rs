@@ -0,0 +1,442 @@
+use std::{collections::BTreeMap, env, ops::Range};
+
+use sqlformat::{FormatOptions, Indent, QueryParams};
+
+/// Quotes a value as a SQL string literal.
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Quotes a name as a SQL identifier.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Pretty-prints SQL with upper case keywords, a line per clause and
+/// indented subqueries. Placeholders are left as they are.
+pub fn format(sql: &str) -> String {
+    let options = FormatOptions {
+        indent: Indent::Spaces(2),
+        uppercase: true,
+        lines_between_queries: 1,
+    };
+    sqlformat::format(sql.trim(), &QueryParams::None, options)
+}
+
+/// The possibly qualified or quoted name around byte `cursor`, or ending
+/// right before it.
+pub fn identifier_at(sql: &str, cursor: usize) -> Option<&str> {
+    let is_part = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '"');
+    let start = sql[..cursor]
+        .rfind(|c: char| !is_part(c))
+        .map_or(0, |i| i + 1);
+    let end = sql[cursor..]
+        .find(|c: char| !is_part(c))
+        .map_or(sql.len(), |i| cursor + i);
+    Some(&sql[start..end]).filter(|name| !name.trim_matches(['.', '"']).is_empty())
+}
+
+/// Whether the statement returns rows that can be wrapped in a subquery.
+pub fn is_query(sql: &str) -> bool {
+    starts_with_any(sql, &["select", "with", "from", "values", "table", "pivot"])
+}
+
+/// Whether the statement creates, drops or changes tables, views or
+/// databases, so the schema browser is out of date after it.
+pub fn changes_schema(sql: &str) -> bool {
+    starts_with_any(sql, &["create", "drop", "alter", "attach", "detach"])
+}
+
+/// Whether the first keyword of the statement is one of `keywords`, after
+/// any comments and opening parens.
+fn starts_with_any(sql: &str, keywords: &[&str]) -> bool {
+    let mut start = None;
+    scan_code(sql, |i, c| {
+        if start.is_none() && !c.is_whitespace() && c != '(' {
+            start = Some(i);
+        }
+    });
+    let first = start
+        .and_then(|start| {
+            sql[start..]
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .next()
+        })
+        .unwrap_or_default();
+    keywords
+        .iter()
+        .any(|keyword| first.eq_ignore_ascii_case(keyword))
+}
+
+/// Parenthesizes a query for use as a subquery. The closing paren goes on a
+/// line of its own, so a trailing `--` comment can't swallow it.
+pub fn subquery(sql: &str) -> String {
+    format!("(\n{}\n)", sql.trim().trim_end_matches(';'))
+}
+
+/// Wraps a query so it returns at most `limit` rows after skipping `offset`.
+pub fn limited(sql: &str, limit: usize, offset: usize) -> String {
+    format!(
+        "SELECT * FROM {} LIMIT {limit} OFFSET {offset}",
+        subquery(sql)
+    )
+}
+
+/// Wraps a query so its rows come sorted by one of its columns.
+pub fn ordered(sql: &str, column: &str, descending: bool) -> String {
+    let direction = if descending { "DESC" } else { "ASC" };
+    format!(
+        "SELECT * FROM {} ORDER BY {} {direction}",
+        subquery(sql),
+        quote_identifier(column)
+    )
+}
+
+/// Counts how often each value of a query's column comes up, most common
+/// first.
+pub fn value_counts(sql: &str, column: &str) -> String {
+    format!(
+        "SELECT {}, count(*) AS count\nFROM {}\nGROUP BY 1\nORDER BY 2 DESC",
+        quote_identifier(column),
+        subquery(sql)
+    )
+}
+
+/// Wraps a query so only rows whose columns, as text, match their regex in
+/// `filters` are left.
+pub fn filtered(sql: &str, filters: &BTreeMap<String, String>) -> String {
+    if filters.is_empty() {
+        return sql.to_string();
+    }
+    let conditions: Vec<String> = filters
+        .iter()
+        .map(|(column, regex)| {
+            format!(
+                "regexp_matches(CAST({} AS VARCHAR), {})",
+                quote_identifier(column),
+                quote_literal(regex)
+            )
+        })
+        .collect();
+    format!(
+        "SELECT * FROM {} WHERE {}",
+        subquery(sql),
+        conditions.join(" AND ")
+    )
+}
+
+/// Byte offsets of the bracket at `cursor`, or else right before it, and of
+/// the one that pairs with it. Brackets in strings, quoted names and
+/// comments don't count.
+pub fn matching_bracket(sql: &str, cursor: usize) -> Option<(usize, usize)> {
+    let brackets = brackets(sql);
+    let at = brackets
+        .iter()
+        .position(|&(i, _)| i == cursor)
+        .or_else(|| {
+            brackets
+                .iter()
+                .position(|&(i, c)| i + c.len_utf8() == cursor)
+        })?;
+    let (start, bracket) = brackets[at];
+    let (open, close, forward) = match bracket {
+        '(' => ('(', ')', true),
+        '[' => ('[', ']', true),
+        ')' => ('(', ')', false),
+        _ => ('[', ']', false),
+    };
+    let mut depth = 0;
+    let mut candidates: Box<dyn Iterator<Item = &(usize, char)>> = if forward {
+        Box::new(brackets[at..].iter())
+    } else {
+        Box::new(brackets[..=at].iter().rev())
+    };
+    candidates.find_map(|&(i, c)| {
+        if c == open {
+            depth += if forward { 1 } else { -1 };
+        } else if c == close {
+            depth += if forward { -1 } else { 1 };
+        }
+        (depth == 0).then_some((start, i))
+    })
+}
+
+/// Where the brackets outside strings, quoted names and comments are.
+fn brackets(sql: &str) -> Vec<(usize, char)> {
+    let mut brackets = vec![];
+    scan_code(sql, |i, c| {
+        if matches!(c, '(' | ')' | '[' | ']') {
+            brackets.push((i, c));
+        }
+    });
+    brackets
+}
+
+/// Keywords uppercased as they are typed. Words that are often column
+/// names too, such as `key` or `type`, are left out.
+const KEYWORDS: &str = "\
+    add all alter analyze and anti as asc asof attach begin between by case cast commit copy \
+    create cross delete desc describe detach distinct drop else end except exclude exists \
+    explain false following from full group having ilike in inner insert install intersect into \
+    is join lateral left like limit load natural not null offset on or order outer over \
+    partition pivot pragma preceding qualify recursive rename replace returning right rollback \
+    select semi set show summarize table temp temporary then to transaction true unbounded \
+    union unpivot update use using values view when where window with";
+
+/// The byte range of the keyword that ends at `cursor`, unless it is in a
+/// string, a comment or a qualified name.
+pub fn keyword_before(sql: &str, cursor: usize) -> Option<Range<usize>> {
+    let start = sql[..cursor]
+        .rfind(|c: char| !c.is_alphanumeric() && c != '_')
+        .map_or(0, |i| i + 1);
+    let word = &sql[start..cursor];
+    let keyword = KEYWORDS
+        .split_whitespace()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word));
+    // A word typed in front of another one isn't done yet.
+    let word_goes_on = sql[cursor..]
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_');
+    if !keyword || word_goes_on || sql[..start].ends_with('.') {
+        return None;
+    }
+    let mut in_code = false;
+    scan_code(&sql[..cursor], |i, _| in_code |= i == start);
+    in_code.then_some(start..cursor)
+}
+
+/// Splits a script into its statements, at the semicolons outside strings,
+/// quoted names, dollar-quoted strings and comments. Statements with
+/// nothing but comments are dropped.
+pub fn split_statements(sql: &str) -> Vec<&str> {
+    let mut ends = vec![];
+    let mut code = vec![];
+    scan_code(sql, |i, c| {
+        if c == ';' {
+            ends.push(i);
+        } else if !c.is_whitespace() {
+            code.push(i);
+        }
+    });
+    ends.push(sql.len());
+    let mut statements = vec![];
+    let mut start = 0;
+    for end in ends {
+        if code.iter().any(|&i| (start..end).contains(&i)) {
+            statements.push(sql[start..end].trim());
+        }
+        start = end + 1;
+    }
+    statements
+}
+
+/// Calls `visit` with each character outside strings, quoted names,
+/// dollar-quoted strings and comments, and its byte offset. A doubled quote
+/// inside a string reads as its end and the start of another, which skips
+/// the same text.
+fn scan_code(sql: &str, mut visit: impl FnMut(usize, char)) {
+    let mut i = 0;
+    while let Some(c) = sql[i..].chars().next() {
+        let rest = &sql[i..];
+        // What to skip from `i`, up to the end when it isn't closed.
+        let skipped = |start: usize, closer: &str| {
+            rest[start..]
+                .find(closer)
+                .map_or(rest.len(), |end| start + end + closer.len())
+        };
+        i += if rest.starts_with("--") {
+            skipped(2, "\n")
+        } else if rest.starts_with("/*") {
+            skipped(2, "*/")
+        } else if c == '\'' || c == '"' {
+            skipped(1, &rest[..1])
+        } else if let Some(tag) = dollar_tag(rest) {
+            skipped(tag.len(), tag)
+        } else {
+            visit(i, c);
+            c.len_utf8()
+        };
+    }
+}
+
+/// The `$tag$` or `$$` that opens a dollar-quoted string at the start of
+/// `sql`. `$1` and such are parameters instead.
+fn dollar_tag(sql: &str) -> Option<&str> {
+    let tag = sql.strip_prefix('$')?;
+    let end = tag.find(|c: char| !c.is_alphanumeric() && c != '_')?;
+    let starts_with_digit = tag.starts_with(|c: char| c.is_ascii_digit());
+    (tag[end..].starts_with('$') && !starts_with_digit).then(|| &sql[..end + 2])
+}
+
+/// The query with its `?`, `$name` and `$1` placeholders numbered as `$1`,
+/// `$2` and so on, so they can be bound by position, and their names in
+/// that order. A name used twice binds the same value; each `?` is a
+/// placeholder of its own.
+pub fn placeholders(sql: &str) -> (String, Vec<String>) {
+    let mut names: Vec<String> = vec![];
+    let numbered = replace_placeholders(sql, |name| {
+        let number = match names.iter().position(|known| known == name) {
+            Some(position) => position + 1,
+            None => {
+                names.push(name.to_string());
+                names.len()
+            }
+        };
+        format!("${number}")
+    });
+    (numbered, names)
+}
+
+/// The query with each placeholder swapped for what `replace` gives for its
+/// name, `?1` and so on for the `?`s.
+pub fn replace_placeholders(sql: &str, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut found = vec![];
+    let mut positional = 0;
+    scan_code(sql, |i, c| {
+        let name = match c {
+            '?' => {
+                positional += 1;
+                format!("?{positional}")
+            }
+            '$' => {
+                let rest = &sql[i + 1..];
+                let end = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                if end == 0 {
+                    return;
+                }
+                rest[..end].to_string()
+            }
+            _ => return,
+        };
+        let len = match c {
+            '?' => 1,
+            _ => name.len() + 1,
+        };
+        found.push((i, len, name));
+    });
+
+    let mut replaced = String::new();
+    let mut rest = 0;
+    for (i, len, name) in found {
+        replaced.push_str(&sql[rest..i]);
+        replaced.push_str(&replace(&name));
+        rest = i + len;
+    }
+    replaced.push_str(&sql[rest..]);
+    replaced
+}
+
+/// Replaces each `${NAME}` with the environment variable `NAME`, strings
+/// and comments included, failing on one that isn't set.
+pub fn interpolate_env(sql: &str) -> Result<String, String> {
+    let mut interpolated = String::new();
+    let mut rest = sql;
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        let name = after.find('}').map(|end| &after[..end]).filter(|name| {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        let Some(name) = name else {
+            interpolated.push_str(&rest[..start + 2]);
+            rest = after;
+            continue;
+        };
+        let value =
+            env::var(name).map_err(|_| format!("Environment variable {name} is not set"))?;
+        interpolated.push_str(&rest[..start]);
+        interpolated.push_str(&value);
+        rest = &after[name.len() + 1..];
+    }
+    interpolated.push_str(rest);
+    Ok(interpolated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_query_looks_past_comments_and_parens() {
+        for sql in [
+            "SELECT 1",
+            "  with t AS (SELECT 1) SELECT * FROM t",
+            "-- all of them\nSELECT * FROM t",
+            "/* all of them */ SELECT * FROM t",
+            "/* one */ -- two\n  /* three */ FROM t",
+            "(SELECT 1) UNION (SELECT 2)",
+            "( ( VALUES (1) ) )",
+        ] {
+            assert!(is_query(sql), "{sql}");
+        }
+    }
+
+    #[test]
+    fn is_query_rejects_other_statements() {
+        for sql in [
+            "",
+            "-- SELECT 1",
+            "/* SELECT 1 */ DELETE FROM t",
+            "INSERT INTO t SELECT 1",
+            "selected",
+        ] {
+            assert!(!is_query(sql), "{sql}");
+        }
+    }
+
+    #[test]
+    fn split_statements_splits_at_semicolons() {
+        assert_eq!(
+            split_statements("SELECT 1;\n  SELECT 2 ; SELECT 3"),
+            ["SELECT 1", "SELECT 2", "SELECT 3"]
+        );
+        assert_eq!(split_statements("SELECT 1;"), ["SELECT 1"]);
+        assert!(split_statements(" ; ;\n").is_empty());
+    }
+
+    #[test]
+    fn split_statements_skips_semicolons_in_strings_and_comments() {
+        for sql in [
+            "SELECT 'a;b'",
+            "SELECT 'it''s; here'",
+            "SELECT \"a;b\" FROM t",
+            "SELECT 1 -- one; two\n+ 1",
+            "SELECT 1 /* one; two */ + 1",
+            "SELECT $$a;b$$",
+            "SELECT $body$a; $$ ;b$body$",
+        ] {
+            assert_eq!(split_statements(sql), [sql], "{sql}");
+        }
+    }
+
+    #[test]
+    fn split_statements_drops_trailing_comments() {
+        assert_eq!(split_statements("SELECT 1; -- done"), ["SELECT 1"]);
+        assert_eq!(
+            split_statements("SELECT 1;\n/* done; really */\n"),
+            ["SELECT 1"]
+        );
+        assert_eq!(
+            split_statements("-- first\nSELECT 1; SELECT 2"),
+            ["-- first\nSELECT 1", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn split_statements_keeps_parameters_apart_from_dollar_quotes() {
+        assert_eq!(
+            split_statements("SELECT $1; SELECT $name"),
+            ["SELECT $1", "SELECT $name"]
+        );
+    }
+
+    #[test]
+    fn changes_schema_looks_past_comments() {
+        assert!(changes_schema("-- a new table\nCREATE TABLE t (a INT)"));
+        assert!(!changes_schema("/* DROP TABLE t */ SELECT 1"));
+    }
+}
@@ -0,0 +1,82 @@
+use duckdb::Connection;
+
+use crate::sql::{quote_identifier, subquery};
+
+/// Summary of one column of a query's result.
+pub struct ColumnStats {
+    pub column: usize,
+    pub name: String,
+    pub count: u64,
+    pub nulls: u64,
+    /// HyperLogLog estimate from `approx_count_distinct`.
+    pub distinct: u64,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// Only computed for numeric columns.
+    pub mean: Option<f64>,
+    pub stddev: Option<f64>,
+}
+
+impl ColumnStats {
+    /// Aggregates the column over the whole query rather than the fetched
+    /// batches, so the numbers hold for results that were cut short.
+    pub fn compute(
+        db: &Connection,
+        query: &str,
+        column: usize,
+        name: &str,
+        numeric: bool,
+    ) -> duckdb::Result<Self> {
+        let query = subquery(query);
+        let c = quote_identifier(name);
+        let (mean, stddev) = if numeric {
+            (
+                format!("avg({c})::DOUBLE"),
+                format!("stddev_samp({c})::DOUBLE"),
+            )
+        } else {
+            ("NULL::DOUBLE".to_string(), "NULL::DOUBLE".to_string())
+        };
+        let sql = format!(
+            "SELECT
+                count({c}),
+                count(*) - count({c}),
+                approx_count_distinct({c}),
+                min({c})::VARCHAR,
+                max({c})::VARCHAR,
+                {mean},
+                {stddev}
+            FROM {query}"
+        );
+
+        db.query_row(&sql, [], |row| {
+            Ok(Self {
+                column,
+                name: name.to_string(),
+                count: row.get::<_, i64>(0)? as u64,
+                nulls: row.get::<_, i64>(1)? as u64,
+                distinct: row.get::<_, i64>(2)? as u64,
+                min: row.get(3)?,
+                max: row.get(4)?,
+                mean: row.get(5)?,
+                stddev: row.get(6)?,
+            })
+        })
+    }
+
+    /// Label and value pairs in display order.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_else(|| "NULL".to_string());
+        let number =
+            |value: Option<f64>| value.map_or_else(|| "-".to_string(), |v| format!("{v:.4}"));
+        vec![
+            ("count", self.count.to_string()),
+            ("nulls", self.nulls.to_string()),
+            ("distinct (approx.)", self.distinct.to_string()),
+            ("min", text(&self.min)),
+            ("max", text(&self.max)),
+            ("mean", number(self.mean)),
+            ("stddev", number(self.stddev)),
+        ]
+    }
+}
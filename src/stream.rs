@@ -0,0 +1,97 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+use arrow::array::RecordBatch;
+use duckdb::{params_from_iter, Connection};
+
+use crate::db;
+
+pub enum Fetch {
+    Batch(RecordBatch),
+    /// How work other than a query is getting on, or what it found.
+    Report(String),
+    Done,
+    Failed(String),
+}
+
+/// A query or other work on a pane's session running on a background
+/// thread, handing its batches over as they are read so the result can be
+/// drawn before the query finishes.
+pub struct QueryStream {
+    pub started: Instant,
+    receiver: Receiver<Fetch>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl QueryStream {
+    /// Runs `sql` with `params` bound to its placeholders on a pane's
+    /// session, which stays locked until the last batch has been read. The
+    /// other pane has a session of its own, so it isn't held up.
+    pub fn start(db: Arc<Mutex<Connection>>, sql: String, params: Vec<Option<String>>) -> Self {
+        Self::spawn(db, move |db, emit| {
+            let mut stmt = db.prepare(&sql)?;
+            for batch in stmt.query_arrow(params_from_iter(&params))? {
+                if !emit(Fetch::Batch(batch)) {
+                    break;
+                }
+            }
+            Ok::<_, duckdb::Error>(())
+        })
+    }
+
+    /// Runs `work` on a pane's session on a background thread, which keeps
+    /// it locked until `work` returns. `work` hands messages over with the
+    /// function it is given, which says whether anyone still wants them.
+    /// The last message is sent once the session is unlocked again.
+    pub fn spawn<E: ToString>(
+        db: Arc<Mutex<Connection>>,
+        work: impl FnOnce(&Connection, &dyn Fn(Fetch) -> bool) -> Result<(), E> + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let stop = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            // Nobody is waiting for the rest once the stream is cancelled
+            // or dropped.
+            let emit = |fetch| !stop.load(Ordering::Relaxed) && sender.send(fetch).is_ok();
+            let db = db::lock(&db);
+            let result = work(&db, &emit).map_err(|err| err.to_string());
+            drop(db);
+            let _ = sender.send(match result {
+                Ok(()) => Fetch::Done,
+                Err(err) => Fetch::Failed(err),
+            });
+        });
+
+        Self {
+            started: Instant::now(),
+            receiver,
+            cancelled,
+        }
+    }
+
+    /// Stops handing batches over. duckdb-rs 0.9 has no way to interrupt
+    /// the statement itself, so the session stays locked until DuckDB is
+    /// done with it and the thread gets to its next batch.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// The next message from the query, if one has arrived.
+    pub fn try_next(&self) -> Option<Fetch> {
+        match self.receiver.try_recv() {
+            Ok(fetch) => Some(fetch),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Fetch::Failed("Query thread stopped".to_string()))
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// A record of a session's statements, when each ran, how long it took and
+/// what it returned, appended to a file as they finish.
+pub struct Transcript {
+    file: File,
+}
+
+impl Transcript {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// Appends a statement with its result drawn as a table, or the error it
+    /// failed with.
+    pub fn record(
+        &mut self,
+        sql: &str,
+        elapsed: Duration,
+        result: Result<&str, &str>,
+    ) -> io::Result<()> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        writeln!(self.file, "-- {now}, took {elapsed:.2?}")?;
+        writeln!(self.file, "{}", sql.trim())?;
+        match result {
+            Ok(result) => writeln!(self.file, "{result}\n")?,
+            Err(err) => writeln!(self.file, "Error: {err}\n")?,
+        }
+        self.file.flush()
+    }
+}
@@ -0,0 +1,94 @@
+use std::{fs, io, path::PathBuf};
+
+/// The steps of the guided tour, each done by pressing the keys it names.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Edit,
+    Run,
+    Scroll,
+    Export,
+}
+
+impl Step {
+    const ALL: [Step; 4] = [Step::Edit, Step::Run, Step::Scroll, Step::Export];
+
+    fn text(self) -> &'static [&'static str] {
+        match self {
+            Step::Edit => &[
+                "civciv starts in the result view, where keys move around",
+                "the result. Press e to type SQL in the editor.",
+            ],
+            Step::Run => &[
+                "Type SELECT * FROM orders and press Enter to run it.",
+                "Tab completes file paths, as in FROM 'data/'.",
+            ],
+            Step::Scroll => &[
+                "Press Esc to go back to the result, then j and k or the",
+                "arrow keys to move through the rows, h and l for columns.",
+            ],
+            Step::Export => &[
+                "Press : and type export orders.csv, then Enter, to write",
+                "the whole result to a file. Parquet and JSON work too.",
+            ],
+        }
+    }
+}
+
+/// A first-run tour of the basics. It only watches what is done and moves
+/// on, so every key keeps doing its usual job.
+pub struct Tutorial {
+    pub step: Step,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Self { step: Step::Edit }
+    }
+}
+
+impl Tutorial {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("civciv").join("tutorial_seen"))
+    }
+
+    /// Whether the tour was finished or skipped before.
+    pub fn seen() -> bool {
+        Self::path().is_some_and(|path| path.exists())
+    }
+
+    /// Keeps the tour from starting on its own again.
+    pub fn mark_seen() -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, "")
+    }
+
+    /// Moves on if `step` is the one being shown. Returns whether the tour
+    /// is over.
+    pub fn reached(&mut self, step: Step) -> bool {
+        if step != self.step {
+            return false;
+        }
+        match Step::ALL.iter().position(|&s| s == step) {
+            Some(i) if i + 1 < Step::ALL.len() => {
+                self.step = Step::ALL[i + 1];
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// The title of the overlay, with how far along the tour is.
+    pub fn title(&self) -> String {
+        let number = Step::ALL.iter().position(|&s| s == self.step).unwrap_or(0) + 1;
+        format!("Tour {number}/{} (:tutorial off to skip)", Step::ALL.len())
+    }
+
+    pub fn text(&self) -> &'static [&'static str] {
+        self.step.text()
+    }
+}
@@ -0,0 +1,1816 @@
+use std::{collections::BTreeMap, ops::Range, time::Duration};
+
+use ratatui::{prelude::*, widgets::*};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{self, batches_to_table, App, Ddl, Describe, InputMode},
+    chart::{self, ChartKind},
+    diff::{DiffKind, ResultDiff},
+    erd::Diagram,
+    grid,
+    import::Field as ImportField,
+    insert::InsertForm,
+    jobs::JobState,
+    lint::Lint,
+    pane::{self, SplitLayout},
+    params::{ParamsForm, Submit},
+    perf::{format_bytes, PerfStats},
+    pivot::PivotForm,
+    plugin::PluginPane,
+    profile,
+    recent::RecentKind,
+    remote::{Field, RemoteForm},
+    secrets::SecretsPanel,
+    settings::SettingsPanel,
+    sql,
+    tutorial::Tutorial,
+};
+
+/// Draws the whole app.
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    // Areas are recorded again as they are drawn, so clicks only land on
+    // what is on screen.
+    app.schema_area = Rect::default();
+    for pane in &mut app.panes {
+        pane.editor_area = Rect::default();
+        pane.result_area = Rect::default();
+    }
+
+    let main_area = if app.show_schema || app.files.is_some() {
+        let horizontal = Layout::horizontal([Constraint::Length(32), Constraint::Min(1)]);
+        let [sidebar_area, main_area] = horizontal.areas(frame.size());
+        match (app.show_schema, app.files.is_some()) {
+            (true, true) => {
+                let vertical = Layout::vertical([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)]);
+                let [schema_area, files_area] = vertical.areas(sidebar_area);
+                render_schema(frame, app, schema_area);
+                render_files(frame, app, files_area);
+            }
+            (true, false) => render_schema(frame, app, sidebar_area),
+            _ => render_files(frame, app, sidebar_area),
+        }
+        main_area
+    } else {
+        frame.size()
+    };
+
+    let [panes_area, status_area] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(main_area);
+    render_status(frame, app, status_area);
+
+    let pane_areas: Vec<(usize, Rect)> = match app.split {
+        None => vec![(app.active, panes_area)],
+        Some(layout) => {
+            let direction = match layout {
+                SplitLayout::SideBySide => Direction::Horizontal,
+                SplitLayout::Stacked => Direction::Vertical,
+            };
+            let areas = Layout::new(direction, [Constraint::Ratio(1, 2); 2]).split(panes_area);
+            areas.iter().copied().enumerate().collect()
+        }
+    };
+
+    let editor = match app.sizes.editor_percent {
+        Some(percent) => Constraint::Percentage(percent),
+        None => Constraint::Length(3),
+    };
+    let mut active_areas = (panes_area, panes_area);
+    for (i, area) in pane_areas {
+        let [sql_area, result_area] = Layout::vertical([editor, Constraint::Min(1)]).areas(area);
+        app.panes[i].editor_area = sql_area;
+        app.panes[i].result_area = result_area;
+        render_editor(frame, app, i, sql_area);
+        if i != app.active {
+            render_result(frame, app, i, result_area);
+            continue;
+        }
+        active_areas = (sql_area, result_area);
+
+        if app.profile.is_some() {
+            render_profile(frame, app, result_area);
+        } else if app.plan.is_some() {
+            render_plan(frame, app, result_area);
+        } else if let Some(kind) = app.chart {
+            render_chart(frame, app, kind, result_area);
+        } else if let Some(diff) = &app.diff {
+            render_diff(
+                frame,
+                diff,
+                app.pane().selected_row,
+                &app.config.null_display,
+                result_area,
+            );
+        } else {
+            render_result(frame, app, i, result_area);
+        }
+    }
+    let (sql_area, result_area) = active_areas;
+
+    if !app.completions.is_empty() {
+        render_completions(frame, app, sql_area);
+    }
+
+    if app.import.is_some() {
+        render_import(frame, app);
+    }
+
+    if let InputMode::Recent = app.input_mode {
+        render_recent(frame, app);
+    }
+
+    if let InputMode::Connections = app.input_mode {
+        render_connections(frame, app);
+    }
+
+    if let InputMode::Jobs = app.input_mode {
+        render_jobs(frame, app);
+    }
+
+    if let InputMode::Templates = app.input_mode {
+        render_templates(frame, app);
+    }
+
+    if let InputMode::Columns = app.input_mode {
+        render_column_picker(frame, app);
+    }
+
+    if app.stats.is_some() {
+        render_stats(frame, app, result_area);
+    }
+
+    if let Some(ddl) = app.ddl.as_mut() {
+        render_ddl(frame, ddl);
+    }
+
+    if let Some(describe) = &app.describe {
+        render_describe(frame, describe);
+    }
+
+    if let Some(pane) = app.plugin_pane.as_mut() {
+        render_plugin_pane(frame, pane);
+    }
+
+    if let Some(diagram) = app.diagram.as_mut() {
+        render_diagram(frame, diagram);
+    }
+
+    if app.finder.is_some() {
+        render_finder(frame, app);
+    }
+
+    if let Some(form) = &app.insert {
+        render_insert_form(frame, form);
+    }
+
+    if let Some(form) = &app.remote {
+        render_remote_form(frame, form);
+    }
+
+    if let Some(form) = &app.params {
+        render_params_form(frame, form);
+    }
+
+    if let Some(form) = &app.pivot {
+        render_pivot_form(frame, form);
+    }
+
+    if let Some(panel) = app.secrets.as_mut() {
+        render_secrets(frame, panel);
+    }
+
+    if let Some(panel) = app.settings.as_mut() {
+        render_settings(frame, panel, &app.initial_settings);
+    }
+
+    if let Some(tutorial) = &app.tutorial {
+        render_tutorial(frame, tutorial, result_area);
+    }
+
+    if let Some(sql) = &app.pending {
+        render_confirm(frame, sql);
+    }
+
+    if let Some(perf) = &app.perf {
+        render_perf(frame, app, perf);
+    }
+}
+
+/// Timings and memory in the top right corner, over everything else.
+fn render_perf(frame: &mut Frame, app: &App, perf: &PerfStats) {
+    let pane = app.pane();
+    let rows = app::row_count(&pane.data);
+    let window = match &pane.page {
+        Some(page) if page.start > 0 => format!(" from row {}", page.start + 1),
+        _ => String::new(),
+    };
+    let running = match &pane.running {
+        Some(running) => format!(
+            "{} rows in {:.1?}",
+            running.rows,
+            running.stream.started.elapsed()
+        ),
+        None => "idle".to_string(),
+    };
+    let lines = vec![
+        format!(
+            "frame    {:.1?} (worst {:.1?}), {} drawn",
+            perf.frame, perf.worst_frame, perf.frames
+        ),
+        format!(
+            "latency  {:.1?} (worst {:.1?})",
+            perf.latency, perf.worst_latency
+        ),
+        format!(
+            "result   {} in {} batches, {rows} rows{window}",
+            format_bytes(app::result_bytes(&pane.data)),
+            pane.data.len()
+        ),
+        format!("cache    {}", format_bytes(app.cache.bytes())),
+        format!("query    {running}"),
+    ];
+
+    let size = frame.size();
+    let width = lines
+        .iter()
+        .map(|line| line.width() as u16 + 2)
+        .max()
+        .unwrap_or(0)
+        .min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(size.width - width, 0, width, height);
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+        .style(Style::default().fg(Color::Yellow))
+        .block(Block::default().borders(Borders::ALL).title("perf (F12)"));
+    frame.render_widget(paragraph, area);
+}
+
+/// The tour's current step in the bottom right corner of the result, where
+/// it leaves the editor and the first rows in sight.
+fn render_tutorial(frame: &mut Frame, tutorial: &Tutorial, result_area: Rect) {
+    let title = tutorial.title();
+    let lines = tutorial.text();
+    let width = lines
+        .iter()
+        .map(|line| line.width())
+        .chain([title.width()])
+        .max()
+        .unwrap_or(0) as u16
+        + 4;
+    let width = width.min(result_area.width);
+    let height = (lines.len() as u16 + 2).min(result_area.height);
+    let area = Rect::new(
+        result_area.right() - width,
+        result_area.bottom() - height,
+        width,
+        height,
+    );
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(
+        lines
+            .iter()
+            .map(|&line| Line::from(line))
+            .collect::<Vec<_>>(),
+    )
+    .style(Style::default().fg(Color::Cyan))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .padding(Padding::horizontal(1))
+            .title(title),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_editor(frame: &mut Frame, app: &App, i: usize, area: Rect) {
+    let active = i == app.active;
+    let title = match app.split {
+        Some(_) => format!("SQL {}", i + 1),
+        None => "SQL".to_string(),
+    };
+    let border_style = if active && app.split.is_some() {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    };
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(title);
+    // Only what the input says now, not what it said a moment ago.
+    let pane = &app.panes[i];
+    if let (Some(lint), true) = (pane.lints.first(), pane.linted == pane.input) {
+        let (message, color) = match lint {
+            Lint::Error { message, .. } => (message, Color::Red),
+            Lint::Warning(message) => (message, Color::Yellow),
+        };
+        let more = match pane.lints.len() {
+            1 => String::new(),
+            n => format!(" (+{} more)", n - 1),
+        };
+        block = block.title(
+            block::Title::from(Line::styled(
+                format!("{message}{more}"),
+                Style::default().fg(color),
+            ))
+            .position(block::Position::Bottom),
+        );
+    }
+
+    let mut marks = vec![];
+    if pane.linted == pane.input {
+        let error = Style::default().fg(Color::White).bg(Color::Red);
+        marks.extend(pane.lints.iter().filter_map(|lint| match lint {
+            Lint::Error {
+                span: Some(span), ..
+            } => Some((span.clone(), error)),
+            _ => None,
+        }));
+    }
+    if let (true, Some((bracket, other))) = (
+        active,
+        sql::matching_bracket(&pane.input, pane.cursor_position),
+    ) {
+        let matched = Style::default().fg(Color::Black).bg(Color::Cyan);
+        marks.push((bracket..bracket + 1, matched));
+        marks.push((other..other + 1, matched));
+    }
+    let input = Paragraph::new(editor_text(&pane.input, &marks))
+        .style(match app.input_mode {
+            InputMode::Editing if active => Style::default().fg(Color::Yellow),
+            _ => Style::default(),
+        })
+        .block(block);
+
+    frame.render_widget(input, area);
+}
+
+/// The editor's lines, with the byte ranges in `marks` picked out in their
+/// styles, the last one winning where they overlap. Once there is more
+/// than one line they are numbered, to go with the line numbers in
+/// DuckDB's errors.
+fn editor_text<'a>(input: &'a str, marks: &[(Range<usize>, Style)]) -> Text<'a> {
+    let count = input.split('\n').count();
+    let gutter = if count > 1 {
+        count.to_string().len()
+    } else {
+        0
+    };
+    let mut start = 0;
+    let lines = input.split('\n').enumerate().map(|(n, line)| {
+        let mut spans = vec![];
+        if gutter > 0 {
+            spans.push(Span::styled(
+                format!("{:>gutter$} ", n + 1),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        // Runs of characters in the same style become one span.
+        let style_at = |i: usize| {
+            marks
+                .iter()
+                .rev()
+                .find(|(range, _)| range.contains(&(start + i)))
+                .map(|&(_, style)| style)
+        };
+        let mut run = (0, style_at(0));
+        for (i, _) in line.char_indices().skip(1) {
+            let style = style_at(i);
+            if style != run.1 {
+                spans.push(Span::styled(&line[run.0..i], run.1.unwrap_or_default()));
+                run = (i, style);
+            }
+        }
+        spans.push(Span::styled(&line[run.0..], run.1.unwrap_or_default()));
+        start += line.len() + 1;
+        Line::from(spans)
+    });
+    Text::from(lines.collect::<Vec<_>>())
+}
+
+fn render_plugin_pane(frame: &mut Frame, pane: &mut PluginPane) {
+    let area = centered_rect(80, 80, frame.size());
+    frame.render_widget(Clear, area);
+    pane.scroll = clamp_scroll(pane.scroll, pane.lines.len(), area.height.saturating_sub(2));
+
+    let lines: Vec<Line> = pane
+        .lines
+        .iter()
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    let title = format!("{} (Esc: close)", pane.title);
+    let paragraph = Paragraph::new(lines)
+        .scroll((pane.scroll, 0))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_diagram(frame: &mut Frame, diagram: &mut Diagram) {
+    let area = centered_rect(90, 90, frame.size());
+    frame.render_widget(Clear, area);
+
+    // Boxes are laid out again for the current width, so a resize can leave
+    // the old offsets past the end.
+    let lines = diagram.lines(area.width.saturating_sub(2) as usize);
+    let widest = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+    let (rows, columns) = diagram.scroll;
+    diagram.scroll = (
+        clamp_scroll(rows, lines.len(), area.height.saturating_sub(2)),
+        clamp_scroll(columns, widest, area.width.saturating_sub(2)),
+    );
+    let lines: Vec<Line> = lines.into_iter().map(Line::from).collect();
+    let title = format!("{} (arrows: scroll, Esc: close)", diagram.title);
+    let paragraph = Paragraph::new(lines)
+        .scroll(diagram.scroll)
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_finder(frame: &mut Frame, app: &mut App) {
+    let Some(finder) = app.finder.as_mut() else {
+        return;
+    };
+    let area = centered_rect(60, 60, frame.size());
+    frame.render_widget(Clear, area);
+    let [input_area, list_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(area);
+
+    let input = Paragraph::new(finder.query.as_str()).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Find table (Enter: insert name, Tab: preview, Esc: close)"),
+    );
+    frame.render_widget(input, input_area);
+
+    let items: Vec<ListItem> = finder
+        .matches
+        .iter()
+        .map(|name| ListItem::new(name.as_str()))
+        .collect();
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_stateful_widget(list, list_area, &mut finder.state);
+}
+
+fn render_describe(frame: &mut Frame, describe: &Describe) {
+    let area = centered_rect(70, 60, frame.size());
+    frame.render_widget(Clear, area);
+
+    let rows = describe
+        .columns
+        .iter()
+        .map(|column| Row::new(column.clone()));
+    let widths = [
+        Constraint::Percentage(30),
+        Constraint::Percentage(30),
+        Constraint::Length(5),
+        Constraint::Length(5),
+        Constraint::Min(1),
+    ];
+    let header = Row::new(["name", "type", "null", "key", "default"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let title = format!("{} (Esc: close)", describe.name);
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(table, area);
+}
+
+/// The frame of the spinner shown while a query runs, picked by how long it
+/// has been running.
+fn spinner(elapsed: Duration) -> char {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    FRAMES[(elapsed.as_millis() / 100) as usize % FRAMES.len()]
+}
+
+/// Elapsed time as `mm:ss`, or `h:mm:ss` from an hour on.
+fn clock(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+/// Keeps a scroll offset from going past the point where the last of `len`
+/// lines or columns reaches the edge of a view `size` long.
+fn clamp_scroll(scroll: u16, len: usize, size: u16) -> u16 {
+    let last = len.saturating_sub(size as usize);
+    scroll.min(last.try_into().unwrap_or(u16::MAX))
+}
+
+fn render_ddl(frame: &mut Frame, ddl: &mut Ddl) {
+    let area = centered_rect(80, 70, frame.size());
+    frame.render_widget(Clear, area);
+    ddl.scroll = clamp_scroll(
+        ddl.scroll,
+        ddl.sql.lines().count(),
+        area.height.saturating_sub(2),
+    );
+
+    let title = format!("DDL: {} (y: copy, Esc: close)", ddl.name);
+    let paragraph = Paragraph::new(ddl.sql.as_str())
+        .scroll((ddl.scroll, 0))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_stats(frame: &mut Frame, app: &App, result_area: Rect) {
+    let Some(stats) = app.stats.as_ref() else {
+        return;
+    };
+
+    let rows: Vec<Row> = stats
+        .rows()
+        .into_iter()
+        .map(|(label, value)| {
+            Row::new([
+                Cell::from(label).style(Style::default().fg(Color::DarkGray)),
+                Cell::from(value),
+            ])
+        })
+        .collect();
+
+    let height = rows.len() as u16 + 2;
+    let area = centered_rect(60, 100, result_area);
+    let area = Rect {
+        y: area.y + area.height.saturating_sub(height) / 2,
+        height: height.min(area.height),
+        ..area
+    };
+    frame.render_widget(Clear, area);
+
+    let title = format!("Stats: {} (←/→: column, Esc: close)", stats.name);
+    let table = Table::new(rows, [Constraint::Length(20), Constraint::Min(1)])
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(table, area);
+}
+
+fn render_result(frame: &mut Frame, app: &mut App, i: usize, chart_area: Rect) {
+    let watched = app.watch.as_ref().filter(|watch| watch.pane == i);
+    let mut title = match watched {
+        Some(watch) => format!("Result (every {:?})", watch.interval),
+        None => "Result".to_string(),
+    };
+
+    let null_display = app.config.null_display.as_str();
+    let pane = &mut app.panes[i];
+    if let (Some(rows), Some(running)) = (pane.rows_so_far(), pane.running.as_ref()) {
+        let elapsed = running.stream.started.elapsed();
+        let progress = match rows {
+            0 => "running…".to_string(),
+            rows => format!("{rows} rows, {} so far…", format_bytes(running.bytes)),
+        };
+        title.push_str(&format!(
+            " {} {} — {progress}",
+            spinner(elapsed),
+            clock(elapsed)
+        ));
+    } else if let Some(job) = pane.job.as_ref() {
+        let elapsed = job.stream.started.elapsed();
+        let report = job
+            .report
+            .as_ref()
+            .map_or_else(String::new, |report| format!(", {report}"));
+        title.push_str(&format!(
+            " {} {} — {}{report}…",
+            spinner(elapsed),
+            clock(elapsed),
+            job.label
+        ));
+    } else if let Some(cancelling) = pane.cancelling.as_ref() {
+        let elapsed = cancelling.stream.started.elapsed();
+        title.push_str(&format!(
+            " {} {} — cancelling…",
+            spinner(elapsed),
+            clock(elapsed)
+        ));
+    } else if let Some(page) = pane.page.as_ref().filter(|page| page.start > 0) {
+        title.push_str(&format!(" — rows {}–{}", page.start + 1, page.fetched));
+        if page.more {
+            title.push_str(", m: fetch more");
+        }
+    } else if let Some(page) = pane.page.as_ref().filter(|page| page.more) {
+        title.push_str(&format!(" — first {} rows, m: fetch more", page.fetched));
+    }
+    if pane.untransposed.is_some() {
+        title.push_str(" — transposed, T: back");
+    }
+    let mut block = Block::default().borders(Borders::ALL).title(title);
+
+    // The header is drawn apart from the rows, so it stays in view while
+    // they scroll underneath.
+    let total = app::row_count(&pane.data);
+    let header_height = if app.show_types { 2 } else { 1 };
+    let visible = chart_area.height.saturating_sub(2 + header_height) as usize;
+    pane.page_rows = visible;
+    pane.selected_row = pane.selected_row.min(total.saturating_sub(1));
+    let (page, pages) = pane.page_position();
+    if pages > 1 {
+        let more = if pane.page.as_ref().is_some_and(|page| page.more) {
+            "+"
+        } else {
+            ""
+        };
+        block = block.title(
+            block::Title::from(format!("page {page} of {pages}{more}"))
+                .position(block::Position::Bottom)
+                .alignment(Alignment::Right),
+        );
+    }
+    // Keep the cursor in view without scrolling past the last row.
+    pane.vertical_scroll = pane.vertical_scroll.clamp(
+        (pane.selected_row + 1).saturating_sub(visible.max(1)),
+        pane.selected_row.min(total.saturating_sub(visible)),
+    );
+    let header = grid::header(&pane.data);
+    let types = grid::types(&pane.data);
+    let grid =
+        grid::column_widths(&header, app.show_types, &pane.data, &app.config).and_then(|widths| {
+            Ok((
+                widths,
+                grid::rows(
+                    &pane.data,
+                    pane.vertical_scroll,
+                    visible,
+                    &app.config.format,
+                )?,
+            ))
+        });
+    let (widths, mut rows) = match grid {
+        Ok(grid) => grid,
+        Err(err) => {
+            frame.render_widget(Paragraph::new(err.to_string()).block(block), chart_area);
+            return;
+        }
+    };
+
+    // Numbered within the whole result, and sized for the last row held so
+    // the gutter keeps its width while scrolling.
+    let first_row = pane.first_row();
+    let gutter = app
+        .show_row_numbers
+        .then(|| (first_row + total).max(1).to_string().len() as u16);
+    let available = chart_area
+        .width
+        .saturating_sub(2 + gutter.map_or(0, |width| width + 1));
+    let columns = grid::visible_columns(pane, &header, &widths, available);
+    // Columns after the frozen first one, against those of them that fit.
+    let scrollable = header
+        .iter()
+        .filter(|name| !pane.hidden_columns.contains(*name))
+        .count()
+        .saturating_sub(1);
+    let mut used = 0;
+    let fitting = columns
+        .iter()
+        .take_while(|c| {
+            used += c.width + grid::COLUMN_SPACING;
+            used <= available + grid::COLUMN_SPACING
+        })
+        .count()
+        .saturating_sub(1);
+    let wrapped: Vec<bool> = columns
+        .iter()
+        .map(|c| pane.wrapped_columns.contains(&header[c.index]))
+        .collect();
+    // Wrapped rows take several lines, so the selected row can still be
+    // below the view, most often right after a resize narrows the columns.
+    let height = |cells: &Vec<Option<String>>| {
+        columns
+            .iter()
+            .zip(&wrapped)
+            .map(|(c, &wrap)| match cells.get(c.index) {
+                Some(Some(cell)) if wrap => grid::wrap(cell, c.width as usize).len(),
+                _ => 1,
+            })
+            .max()
+            .unwrap_or(1)
+    };
+    let scrolled_from = pane.vertical_scroll;
+    while pane.vertical_scroll < pane.selected_row {
+        let upto = pane.selected_row - pane.vertical_scroll;
+        let skipped = pane.vertical_scroll - scrolled_from;
+        let used: usize = rows.iter().skip(skipped).take(upto + 1).map(height).sum();
+        if used <= visible {
+            break;
+        }
+        pane.vertical_scroll += 1;
+    }
+    if pane.vertical_scroll != scrolled_from {
+        match grid::rows(
+            &pane.data,
+            pane.vertical_scroll,
+            visible,
+            &app.config.format,
+        ) {
+            Ok(scrolled) => rows = scrolled,
+            Err(err) => {
+                frame.render_widget(Paragraph::new(err.to_string()).block(block), chart_area);
+                return;
+            }
+        }
+    }
+
+    if pane.horizontal_scroll > 0 {
+        block = block.title(
+            block::Title::from(format!("← {} columns", pane.horizontal_scroll))
+                .alignment(Alignment::Right),
+        );
+    }
+
+    let (selected_rows, selected_columns) = pane.selection();
+    let rows: Vec<Row> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(j, cells)| {
+            let mut height = 1;
+            let cells: Vec<Cell> = columns
+                .iter()
+                .zip(&wrapped)
+                .map(|(c, &wrap)| {
+                    let width = c.width as usize;
+                    let (cell, style) = match cells.get(c.index) {
+                        Some(Some(cell)) if wrap => {
+                            let lines = grid::wrap(cell, width);
+                            height = height.max(lines.len());
+                            (Cell::from(lines.join("\n")), Style::default())
+                        }
+                        Some(Some(cell)) => {
+                            (Cell::from(grid::truncate(cell, width)), Style::default())
+                        }
+                        _ => (
+                            Cell::from(grid::truncate(null_display, width)),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    };
+                    let highlighted = pane.highlight.is_some()
+                        && cells.get(c.index).and_then(Option::as_ref) == pane.highlight.as_ref();
+                    let style = if highlighted {
+                        style.fg(Color::Black).bg(Color::Yellow)
+                    } else {
+                        style
+                    };
+                    let row = pane.vertical_scroll + j;
+                    if row == pane.selected_row && c.index == pane.selected_column {
+                        cell.style(style.add_modifier(Modifier::REVERSED))
+                    } else if selected_rows.contains(&row) && selected_columns.contains(&c.index) {
+                        cell.style(style.bg(Color::DarkGray))
+                    } else {
+                        cell.style(style)
+                    }
+                })
+                .collect();
+            let number = gutter.map(|_| {
+                Cell::from((first_row + pane.vertical_scroll + j + 1).to_string())
+                    .style(Style::default().fg(Color::DarkGray))
+            });
+            let row = Row::new(number.into_iter().chain(cells)).height(height as u16);
+            if pane.changed_rows.contains(&(pane.vertical_scroll + j)) {
+                row.style(Style::default().fg(Color::Green))
+            } else {
+                row
+            }
+        })
+        .collect();
+    let header = columns.iter().map(|c| {
+        let mut name = match &pane.sort {
+            Some(sort) if sort.column == header[c.index] => {
+                let arrow = if sort.descending { '▼' } else { '▲' };
+                format!("{} {arrow}", header[c.index])
+            }
+            _ => header[c.index].clone(),
+        };
+        if pane.filters.contains_key(&header[c.index]) {
+            name.push_str(" ~");
+        }
+        let name = if c.index == pane.selected_column {
+            Span::styled(name, Style::default().add_modifier(Modifier::REVERSED))
+        } else {
+            Span::raw(name)
+        };
+        let mut lines = vec![Line::from(name)];
+        if app.show_types {
+            lines.push(Line::styled(
+                grid::truncate(&types[c.index], c.width as usize),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        Cell::from(Text::from(lines))
+    });
+    let header = gutter.map(|_| Cell::from("#")).into_iter().chain(header);
+    let mut x = chart_area.x + 1 + gutter.map_or(0, |width| width + grid::COLUMN_SPACING);
+    pane.header_edges = columns
+        .iter()
+        .map(|c| {
+            x += c.width;
+            let edge = pane::HeaderEdge {
+                column: c.index,
+                x,
+                y: chart_area.y + 1,
+                width: c.width,
+            };
+            x += grid::COLUMN_SPACING;
+            edge
+        })
+        .collect();
+    let widths = gutter
+        .into_iter()
+        .chain(columns.iter().map(|c| c.width))
+        .map(Constraint::Length);
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(header)
+                .height(header_height)
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(block);
+    frame.render_widget(table, chart_area);
+
+    // Measured in rows of the whole result, counting those before the
+    // window, with one position for each row that can be at the top. The
+    // bar runs alongside the rows only.
+    let rows_in_result = first_row + total;
+    let top = first_row + pane.vertical_scroll;
+    pane.vertical_scroll_state = pane
+        .vertical_scroll_state
+        .content_length(rows_in_result.saturating_sub(visible) + 1)
+        .viewport_content_length(visible.max(1))
+        .position(top);
+    let rows_area = Rect {
+        y: chart_area.y + 1 + header_height,
+        height: visible as u16,
+        ..chart_area
+    };
+    if total > visible || first_row > 0 {
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(Some("↑"))
+                .end_symbol(Some("↓")),
+            rows_area,
+            &mut pane.vertical_scroll_state,
+        );
+    }
+
+    if pane.horizontal_scroll + fitting < scrollable || pane.horizontal_scroll > 0 {
+        pane.horizontal_scroll_state = pane
+            .horizontal_scroll_state
+            .content_length(
+                scrollable
+                    .saturating_sub(fitting)
+                    .max(pane.horizontal_scroll)
+                    + 1,
+            )
+            .viewport_content_length(fitting.max(1))
+            .position(pane.horizontal_scroll);
+        let bottom = Rect {
+            x: chart_area.x + 1,
+            y: chart_area.bottom().saturating_sub(1),
+            width: chart_area.width.saturating_sub(2),
+            height: 1,
+        };
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::HorizontalBottom)
+                .begin_symbol(Some("←"))
+                .end_symbol(Some("→")),
+            bottom,
+            &mut pane.horizontal_scroll_state,
+        );
+    }
+}
+
+fn render_diff(
+    frame: &mut Frame,
+    diff: &ResultDiff,
+    scroll: usize,
+    null_display: &str,
+    area: Rect,
+) {
+    let mut title = format!(
+        "Diff against pinned: +{} -{} ~{}, {} unchanged (d: back to result)",
+        diff.count(DiffKind::Added),
+        diff.count(DiffKind::Removed),
+        diff.count(DiffKind::Changed),
+        diff.unchanged
+    );
+    if diff.columns_differ {
+        title.push_str(" — columns differ");
+    }
+    let block = Block::default().borders(Borders::ALL).title(title);
+    if diff.rows.is_empty() {
+        frame.render_widget(Paragraph::new("Identical results").block(block), area);
+        return;
+    }
+
+    let rows: Vec<Row> = diff
+        .rows
+        .iter()
+        .skip(scroll)
+        .map(|row| {
+            let (marker, color) = match row.kind {
+                DiffKind::Added => ("+", Color::Green),
+                DiffKind::Removed => ("-", Color::Red),
+                DiffKind::Changed => ("~", Color::Yellow),
+            };
+            let show = |cell: &Option<String>| cell.as_deref().unwrap_or(null_display).to_string();
+            let cells = row.cells.iter().enumerate().map(|(i, cell)| {
+                match row.before.as_ref().and_then(|before| before.get(i)) {
+                    Some(before) if before != cell => format!("{} → {}", show(before), show(cell)),
+                    _ => show(cell),
+                }
+            });
+            Row::new(std::iter::once(marker.to_string()).chain(cells))
+                .style(Style::default().fg(color))
+        })
+        .collect();
+
+    let widths: Vec<Constraint> = std::iter::once(Constraint::Length(1))
+        .chain(diff.header.iter().map(|_| Constraint::Min(8)))
+        .collect();
+    let header = Row::new(std::iter::once(String::new()).chain(diff.header.iter().cloned()))
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    frame.render_widget(Table::new(rows, widths).header(header).block(block), area);
+}
+
+fn render_profile(frame: &mut Frame, app: &mut App, area: Rect) {
+    let Some(profile) = app.profile.as_mut() else {
+        return;
+    };
+
+    let indent = profile.sort == profile::SortKey::Plan;
+    let rows: Vec<Row> = profile
+        .operators
+        .iter()
+        .map(|op| {
+            let name = if indent {
+                format!("{}{}", "  ".repeat(op.depth), op.name)
+            } else {
+                op.name.clone()
+            };
+            let heat = Style::default().fg(heat_color(op.percent));
+            Row::new(vec![
+                Cell::from(name).style(heat),
+                Cell::from(op.rows.to_string()),
+                Cell::from(format!("{:.3}", op.seconds * 1000.0)),
+                Cell::from(format!("{:.1}%", op.percent)).style(heat),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Min(20),
+        Constraint::Length(12),
+        Constraint::Length(12),
+        Constraint::Length(8),
+    ];
+    let title = format!(
+        "Profile: {:.3} ms total, sorted by {} (o: sort, Esc: close)",
+        profile.total_seconds * 1000.0,
+        profile.sort.label()
+    );
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Operator", "Rows", "Time (ms)", "Share"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    frame.render_stateful_widget(table, area, &mut profile.state);
+}
+
+const SERIES_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Green,
+    Color::Red,
+    Color::Blue,
+];
+
+fn render_histogram(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(histogram) = app.histogram.as_ref() else {
+        let block = Block::default().borders(Borders::ALL).title("Histogram");
+        frame.render_widget(Paragraph::new("No histogram").block(block), area);
+        return;
+    };
+
+    let title = format!(
+        "Histogram of {}, {} bins (←/→: column, +/-: bins, Esc: back to table)",
+        histogram.column,
+        histogram.counts.len()
+    );
+    let labels = histogram.bucket_labels();
+    let bins = histogram.counts.len().max(1) as u16;
+    let bar_width = (area.width.saturating_sub(2) / bins)
+        .saturating_sub(1)
+        .max(1);
+    let bars: Vec<Bar> = histogram
+        .counts
+        .iter()
+        .zip(&labels)
+        .map(|(count, label)| {
+            Bar::default()
+                .value(*count)
+                .label(Line::raw(label.as_str()))
+        })
+        .collect();
+
+    frame.render_widget(
+        BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(Color::Cyan))
+            .data(BarGroup::default().bars(&bars)),
+        area,
+    );
+}
+
+fn render_chart(frame: &mut Frame, app: &App, kind: ChartKind, area: Rect) {
+    let (title, plot, graph_type) = match kind {
+        ChartKind::Histogram { .. } => return render_histogram(frame, app, area),
+        ChartKind::Line => (
+            "Line chart (Esc: back to table)",
+            chart::line_chart(&app.pane().data),
+            GraphType::Line,
+        ),
+        ChartKind::Scatter { x, y } => (
+            "Scatter plot (←/→: x column, ↑/↓: y column, Esc: back to table)",
+            chart::scatter_plot(&app.pane().data, x, y),
+            GraphType::Scatter,
+        ),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+
+    let chart = match plot {
+        Ok(chart) => chart,
+        Err(reason) => {
+            frame.render_widget(Paragraph::new(reason).block(block), area);
+            return;
+        }
+    };
+
+    let datasets: Vec<Dataset> = chart
+        .series
+        .iter()
+        .zip(SERIES_COLORS.iter().cycle())
+        .map(|(series, color)| {
+            Dataset::default()
+                .name(series.name.as_str())
+                .marker(symbols::Marker::Braille)
+                .graph_type(graph_type)
+                .style(Style::default().fg(*color))
+                .data(&series.points)
+        })
+        .collect();
+
+    let x_axis = Axis::default().bounds(chart.x_bounds).labels(
+        chart
+            .x_labels
+            .iter()
+            .map(|l| Span::raw(l.as_str()))
+            .collect(),
+    );
+    let y_axis = Axis::default().bounds(chart.y_bounds).labels(
+        chart::axis_labels(chart.y_bounds)
+            .into_iter()
+            .map(Span::raw)
+            .collect(),
+    );
+
+    frame.render_widget(
+        Chart::new(datasets)
+            .block(block)
+            .x_axis(x_axis)
+            .y_axis(y_axis)
+            .legend_position(Some(LegendPosition::TopLeft)),
+        area,
+    );
+}
+
+/// Maps a share of the total time onto a green → yellow → red scale.
+fn heat_color(percent: f64) -> Color {
+    let share = (percent / 100.0).clamp(0.0, 1.0);
+    let (red, green) = if share < 0.5 {
+        (share * 2.0 * 255.0, 200.0)
+    } else {
+        (255.0, (1.0 - share) * 2.0 * 200.0)
+    };
+    Color::Rgb(red as u8, green as u8, 0)
+}
+
+fn render_plan(frame: &mut Frame, app: &mut App, area: Rect) {
+    let Some(plan) = app.plan.as_mut() else {
+        return;
+    };
+
+    let horizontal = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]);
+    let [tree_area, details_area] = horizontal.areas(area);
+
+    let items: Vec<ListItem> = plan
+        .rows()
+        .iter()
+        .filter_map(|row| {
+            let node = plan.node(&row.path)?;
+            let marker = match (node.children.is_empty(), node.expanded) {
+                (true, _) => " ",
+                (false, true) => "▾",
+                (false, false) => "▸",
+            };
+            let mut spans = vec![Span::raw(format!(
+                "{}{marker} {}",
+                "  ".repeat(row.depth),
+                node.name
+            ))];
+            if let Some(cardinality) = &node.estimated_cardinality {
+                spans.push(Span::styled(
+                    format!("  ~{cardinality} rows"),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            Some(ListItem::new(Line::from(spans)))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Plan (Enter: collapse/expand, Esc: close)"),
+        );
+    frame.render_stateful_widget(list, tree_area, &mut plan.state);
+
+    let details: Vec<Line> = plan
+        .selected()
+        .map(|node| {
+            node.details
+                .iter()
+                .flat_map(|(key, value)| {
+                    let mut lines = Vec::new();
+                    if !key.is_empty() {
+                        lines.push(Line::styled(
+                            key.as_str(),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ));
+                    }
+                    lines.extend(value.lines().map(|line| Line::raw(format!("  {line}"))));
+                    lines
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    frame.render_widget(
+        Paragraph::new(details)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title("Details")),
+        details_area,
+    );
+}
+
+fn render_files(frame: &mut Frame, app: &mut App, area: Rect) {
+    let focused = matches!(app.input_mode, InputMode::Files);
+    let Some(browser) = app.files.as_mut() else {
+        return;
+    };
+
+    let items: Vec<ListItem> = browser
+        .entries
+        .iter()
+        .map(|entry| {
+            let suffix = if entry.is_dir { "/" } else { "" };
+            ListItem::new(format!("{}{suffix}", entry.name))
+        })
+        .collect();
+
+    let mut title = browser.dir.to_string_lossy().into_owned();
+    if browser.editing_filter || !browser.filter.is_empty() {
+        title = format!("{title} [*.{}]", browser.filter);
+    }
+
+    let list = List::new(items)
+        .style(if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        })
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    frame.render_stateful_widget(list, area, &mut browser.state);
+}
+
+fn render_insert_form(frame: &mut Frame, form: &InsertForm) {
+    let area = centered_rect(60, 60, frame.size());
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = form
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let not_null = if field.nullable { "" } else { " NOT NULL" };
+            let value = match (&field.default, field.value.is_empty()) {
+                (Some(default), true) => Span::styled(
+                    format!("default {default}"),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                _ => Span::raw(field.value.as_str()),
+            };
+            let style = if i == form.selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{} {}{not_null}: ", field.name, field.data_type),
+                    style,
+                ),
+                value,
+            ])
+        })
+        .collect();
+
+    let title = format!(
+        "Insert into {} (Tab: next field, empty: default, NULL for null, Enter: preview, Esc: cancel)",
+        form.table
+    );
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_params_form(frame: &mut Frame, form: &ParamsForm) {
+    let area = centered_rect(60, 40, frame.size());
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = form
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let style = if i == form.selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{}: ", param.name), style),
+                Span::raw(param.value.as_str()),
+            ])
+        })
+        .collect();
+
+    let title = match form.submit {
+        Submit::Run(_) => {
+            "Query parameters (Tab: next field, NULL for null, Enter: run, Esc: cancel)"
+        }
+        Submit::Fill => {
+            "Template placeholders (Tab: next field, NULL for null, Enter: fill in, Esc: cancel)"
+        }
+    };
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_pivot_form(frame: &mut Frame, form: &PivotForm) {
+    let area = centered_rect(60, 30, frame.size());
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = form
+        .fields()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (label, value))| {
+            let style = if i == form.selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{label}: "), style),
+                Span::raw(format!("◂ {value} ▸")),
+            ])
+        })
+        .collect();
+
+    let title = "Pivot (Tab: next field, ←/→: change, Enter: run, Esc: cancel)";
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_remote_form(frame: &mut Frame, form: &RemoteForm) {
+    let area = centered_rect(60, 50, frame.size());
+    frame.render_widget(Clear, area);
+
+    let mut lines: Vec<Line> = form
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, (field, value))| {
+            let style = if i == form.selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            let value = match field {
+                Field::Password => "•".repeat(value.chars().count()),
+                _ => value.clone(),
+            };
+            Line::from(vec![
+                Span::styled(format!("{}: ", field.label()), style),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+    lines.push(Line::styled(
+        "Empty fields fall back on the client's environment variables",
+        Style::default().fg(Color::DarkGray),
+    ));
+    if let Some(err) = &form.error {
+        lines.push(Line::styled(err.as_str(), Style::default().fg(Color::Red)));
+    }
+
+    let title = format!(
+        "Attach {} (Tab: next field, Enter: attach, Esc: cancel)",
+        form.kind.name()
+    );
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_settings(
+    frame: &mut Frame,
+    panel: &mut SettingsPanel,
+    initial: &BTreeMap<String, String>,
+) {
+    let area = centered_rect(70, 70, frame.size());
+    frame.render_widget(Clear, area);
+
+    let items: Vec<ListItem> = panel
+        .visible()
+        .into_iter()
+        .enumerate()
+        .map(|(i, setting)| {
+            let editing = panel
+                .edit
+                .as_ref()
+                .filter(|_| panel.state.selected() == Some(i));
+            let value = match editing {
+                Some(value) => {
+                    Span::styled(format!("{value}▏"), Style::default().fg(Color::Yellow))
+                }
+                // Changed since civciv started.
+                None if initial.get(&setting.name) != Some(&setting.value) => Span::styled(
+                    setting.value.clone(),
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                None => Span::raw(setting.value.clone()),
+            };
+            ListItem::new(vec![
+                Line::from(vec![
+                    Span::raw(format!("{} = ", setting.name)),
+                    value,
+                    Span::styled(
+                        format!("  {}", setting.input_type),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]),
+                Line::styled(
+                    format!("  {}", setting.description),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        })
+        .collect();
+
+    let title = match (&panel.error, panel.editing_filter) {
+        (Some(err), _) => format!("Settings: {err}"),
+        (None, true) => format!("Settings /{}▏", panel.filter),
+        (None, false) if !panel.filter.is_empty() => format!(
+            "Settings /{} (Enter: edit, /: filter, Esc: close)",
+            panel.filter
+        ),
+        (None, false) => "Settings (Enter: edit, /: filter, Esc: close)".to_string(),
+    };
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_stateful_widget(list, area, &mut panel.state);
+}
+
+fn render_secrets(frame: &mut Frame, panel: &mut SecretsPanel) {
+    let area = centered_rect(70, 60, frame.size());
+    frame.render_widget(Clear, area);
+
+    let items: Vec<ListItem> = panel
+        .secrets
+        .iter()
+        .map(|secret| {
+            let persistent = if secret.persistent {
+                "  persistent"
+            } else {
+                ""
+            };
+            ListItem::new(vec![
+                Line::from(format!(
+                    "{}  {} ({})  {}{persistent}",
+                    secret.name, secret.kind, secret.provider, secret.scope
+                )),
+                Line::styled(
+                    secret.redacted.clone(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        })
+        .collect();
+    let title = if items.is_empty() {
+        "Secrets: none yet (n: new, Esc: close)"
+    } else {
+        "Secrets (n: new, d: drop, Esc: close)"
+    };
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_stateful_widget(list, area, &mut panel.state);
+
+    let Some(form) = &panel.form else {
+        return;
+    };
+    let area = centered_rect(60, 60, frame.size());
+    frame.render_widget(Clear, area);
+    let mut lines: Vec<Line> = form
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, (field, value))| {
+            let style = if i == form.selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            let value = if field.is_hidden() {
+                "•".repeat(value.chars().count())
+            } else {
+                value.clone()
+            };
+            Line::from(vec![
+                Span::styled(format!("{}: ", field.label()), style),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+    if let Some(err) = &form.error {
+        lines.push(Line::styled(err.as_str(), Style::default().fg(Color::Red)));
+    }
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("New secret (Tab: next field, empty: left out, Enter: create, Esc: cancel)"),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_confirm(frame: &mut Frame, sql: &str) {
+    let area = centered_rect(60, 30, frame.size());
+    frame.render_widget(Clear, area);
+    let paragraph = Paragraph::new(sql).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Run this statement? (Enter: run, Esc: cancel)"),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_status(frame: &mut Frame, app: &App, area: Rect) {
+    let status = match &app.status {
+        _ if matches!(app.input_mode, InputMode::Command) => {
+            Line::raw(format!(":{}", app.command_input))
+        }
+        _ if app.cell_edit.is_some() => {
+            let edit = app.cell_edit.as_ref().expect("checked above");
+            let column = grid::header(&app.pane().data)
+                .get(edit.column)
+                .cloned()
+                .unwrap_or_default();
+            Line::raw(format!("{column} = {} (Enter: preview, NULL for null, Esc: cancel)", edit.value))
+        }
+        Some(status) => Line::raw(status.as_str()),
+        None if app.recording.is_some() => Line::styled(
+            format!("recording @{}  Q: stop", app.recording.unwrap_or_default()),
+            Style::default().fg(Color::Red),
+        ),
+        None => Line::styled(
+            "e: edit  s: schema  f: files  r: re-run  m: more rows  x: explain  X: profile  c: chart  w: split  v: columns  </>: column width  z: wrap  t: types  n: row numbers  g: go to row  /: filter column  F: clear filters  *: highlight value  G: count values  T: transpose  PgUp/PgDn/Home/End: pages  shift+arrows: select  y/Y: copy  u: update cell  D: delete rows  K: describe  ctrl+t: find table  +/-: resize  P: pin  d: diff  p: plot  H: histogram  S: stats  o: open file  R: recent  C: connections  Q/@: record/play macro  :command  q: quit",
+            Style::default().fg(Color::DarkGray),
+        ),
+    };
+    let mut right = vec![];
+    // Without the split, the other pane's query is out of sight.
+    let other = &app.panes[1 - app.active];
+    if let (None, Some(started)) = (app.split, other.busy_since()) {
+        let elapsed = started.elapsed();
+        right.push(Span::styled(
+            format!("{} other pane {}", spinner(elapsed), clock(elapsed)),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if let InputMode::Editing = app.input_mode {
+        let (line, column) = app.pane().cursor_line_column();
+        if !right.is_empty() {
+            right.push(Span::raw("  "));
+        }
+        right.push(Span::raw(format!("Ln {line}, Col {column}")));
+    }
+    let right = Line::from(right);
+    let area = if right.width() > 0 {
+        let [area, right_area] = Layout::horizontal([
+            Constraint::Min(1),
+            Constraint::Length(right.width() as u16 + 1),
+        ])
+        .areas(area);
+        frame.render_widget(
+            Paragraph::new(right).alignment(Alignment::Right),
+            right_area,
+        );
+        area
+    } else {
+        area
+    };
+    frame.render_widget(Paragraph::new(status), area);
+}
+
+fn render_recent(frame: &mut Frame, app: &mut App) {
+    let items: Vec<ListItem> = app
+        .recent
+        .entries()
+        .into_iter()
+        .map(|(kind, target)| {
+            let kind = match kind {
+                RecentKind::Database => "db  ",
+                RecentKind::File => "file",
+            };
+            ListItem::new(format!("{kind} {target}"))
+        })
+        .collect();
+
+    let area = centered_rect(60, 50, frame.size());
+    frame.render_widget(Clear, area);
+
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title("Recent"));
+    frame.render_stateful_widget(list, area, &mut app.recent_state);
+}
+
+fn render_connections(frame: &mut Frame, app: &mut App) {
+    let items: Vec<ListItem> = app
+        .config
+        .connections
+        .iter()
+        .map(|(name, profile)| {
+            // A server's connection string may hold its password.
+            let path = match (profile.kind, profile.path.as_deref()) {
+                (Some(kind), _) => kind.name(),
+                (None, path) => path.unwrap_or(":memory:"),
+            };
+            let mode = if profile.read_only {
+                "  (read-only)"
+            } else {
+                ""
+            };
+            ListItem::new(format!("{name}  {path}{mode}"))
+        })
+        .collect();
+
+    let area = centered_rect(60, 50, frame.size());
+    frame.render_widget(Clear, area);
+
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title("Connections"));
+    frame.render_stateful_widget(list, area, &mut app.connections_state);
+}
+
+fn render_jobs(frame: &mut Frame, app: &mut App) {
+    let area = centered_rect(70, 50, frame.size());
+    frame.render_widget(Clear, area);
+
+    let width = usize::from(area.width).saturating_sub(30);
+    let items: Vec<ListItem> = app
+        .jobs()
+        .iter()
+        .map(|job| {
+            let style = match job.state {
+                JobState::Running => Style::default().fg(Color::Yellow),
+                JobState::Done => Style::default(),
+                JobState::Failed => Style::default().fg(Color::Red),
+                JobState::Cancelling | JobState::Cancelled => Style::default().fg(Color::DarkGray),
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("pane {}  ", job.pane + 1)),
+                Span::styled(format!("{:<10}", job.state.label()), style),
+                Span::raw(format!("{:>6}  {}", clock(job.elapsed), job.snippet(width))),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Jobs (Enter: go to pane, x: cancel, Esc: close)"),
+        );
+    frame.render_stateful_widget(list, area, &mut app.jobs_state);
+}
+
+fn render_templates(frame: &mut Frame, app: &mut App) {
+    let items: Vec<ListItem> = app
+        .config
+        .templates
+        .iter()
+        .map(|(name, sql)| {
+            let first = sql.lines().map(str::trim).find(|line| !line.is_empty());
+            ListItem::new(format!("{name}  {}", first.unwrap_or_default()))
+        })
+        .collect();
+
+    let area = centered_rect(60, 50, frame.size());
+    frame.render_widget(Clear, area);
+
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title("Templates"));
+    frame.render_stateful_widget(list, area, &mut app.templates_state);
+}
+
+fn render_column_picker(frame: &mut Frame, app: &mut App) {
+    let pane = &app.panes[app.active];
+    let items: Vec<ListItem> = grid::header(&pane.data)
+        .into_iter()
+        .map(|name| {
+            let mark = if pane.hidden_columns.contains(&name) {
+                "[ ]"
+            } else {
+                "[x]"
+            };
+            ListItem::new(format!("{mark} {name}"))
+        })
+        .collect();
+
+    let area = centered_rect(40, 60, frame.size());
+    frame.render_widget(Clear, area);
+
+    let list = List::new(items)
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Columns (Space: show/hide, Esc: close)"),
+        );
+    frame.render_stateful_widget(list, area, &mut app.column_picker);
+}
+
+fn render_completions(frame: &mut Frame, app: &App, sql_area: Rect) {
+    let names: Vec<&str> = app
+        .completions
+        .iter()
+        .map(|candidate| {
+            let trimmed = candidate.trim_end_matches('/');
+            let start = trimmed.rfind('/').map_or(0, |i| i + 1);
+            &candidate[start..]
+        })
+        .collect();
+
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0) as u16 + 2;
+    let height =
+        (names.len() as u16 + 2).min(frame.size().height.saturating_sub(sql_area.bottom()));
+    let area = Rect::new(
+        sql_area.x + 1,
+        sql_area.bottom(),
+        width.min(sql_area.width.saturating_sub(1)),
+        height,
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        List::new(names).block(Block::default().borders(Borders::ALL)),
+        area,
+    );
+}
+
+fn render_schema(frame: &mut Frame, app: &mut App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .schema
+        .visible_nodes()
+        .into_iter()
+        .map(|node| ListItem::new(app.schema.label(node)))
+        .collect();
+
+    let list = List::new(items)
+        .style(match app.input_mode {
+            InputMode::Browsing => Style::default().fg(Color::Yellow),
+            _ => Style::default(),
+        })
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title("Schema"));
+
+    frame.render_stateful_widget(list, area, &mut app.schema.state);
+    app.schema_area = area;
+}
+
+fn render_import(frame: &mut Frame, app: &App) {
+    let Some(wizard) = app.import.as_ref() else {
+        return;
+    };
+
+    let area = centered_rect(80, 80, frame.size());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Open data file (Tab: next field, Space: toggle, Enter: create, Esc: cancel)");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let mut lines = Vec::new();
+    for field in wizard.fields() {
+        let (label, value) = match field {
+            ImportField::Path => ("Path", wizard.path.clone()),
+            ImportField::Name => ("Name", wizard.name.clone()),
+            ImportField::Delimiter => ("Delimiter", wizard.delimiter.clone()),
+            ImportField::Header => ("Header", wizard.header.to_string()),
+            ImportField::Kind => (
+                "Create as",
+                if wizard.as_table { "table" } else { "view" }.to_string(),
+            ),
+            ImportField::Column(i) => {
+                let column = &wizard.columns[i];
+                (column.name.as_str(), column.data_type.clone())
+            }
+        };
+        let style = if field == wizard.field {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::styled(format!("{label:>12}: {value}"), style));
+    }
+
+    let vertical = Layout::vertical([
+        Constraint::Length(lines.len() as u16 + 1),
+        Constraint::Length(1),
+        Constraint::Min(1),
+    ]);
+    let [fields_area, error_area, preview_area] = vertical.areas(inner);
+
+    frame.render_widget(Paragraph::new(lines), fields_area);
+
+    if let Some(error) = &wizard.error {
+        frame.render_widget(
+            Paragraph::new(error.as_str()).style(Style::default().fg(Color::Red)),
+            error_area,
+        );
+    }
+
+    let preview = batches_to_table(&wizard.preview)
+        .map(|table| table.to_string())
+        .unwrap_or_else(|err| err.to_string());
+    frame.render_widget(
+        Paragraph::new(preview).block(Block::default().borders(Borders::TOP).title("Preview")),
+        preview_area,
+    );
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ]);
+    let [_, area, _] = vertical.areas(area);
+
+    let horizontal = Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ]);
+    let [_, area, _] = horizontal.areas(area);
+
+    area
+}